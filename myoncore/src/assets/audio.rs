@@ -0,0 +1,56 @@
+use std::{path::Path, sync::Arc};
+
+use super::{Asset, AssetContext};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AudioFormat {
+    Wav,
+    Ogg,
+    Flac,
+    Mp3,
+}
+
+impl AudioFormat {
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WAVE" {
+            Some(Self::Wav)
+        } else if bytes.starts_with(b"OggS") {
+            Some(Self::Ogg)
+        } else if bytes.starts_with(b"fLaC") {
+            Some(Self::Flac)
+        } else if bytes.starts_with(b"ID3")
+            || (bytes.len() >= 2 && bytes[0] == 0xFF && bytes[1] & 0xE0 == 0xE0)
+        {
+            Some(Self::Mp3)
+        } else {
+            None
+        }
+    }
+}
+
+/// Encoded audio data, ready to be handed to an audio backend for decoding.
+#[derive(Clone)]
+pub struct AudioClip {
+    pub data: Arc<[u8]>,
+    pub format: AudioFormat,
+}
+
+impl Asset for AudioClip {
+    type Data = (Vec<u8>, AudioFormat);
+
+    fn decode(bytes: Vec<u8>, _path: &Path) -> anyhow::Result<Self::Data> {
+        let format =
+            AudioFormat::detect(&bytes).ok_or_else(|| anyhow::anyhow!("Unknown audio format"))?;
+
+        Ok((bytes, format))
+    }
+
+    fn create(data: Self::Data, _path: &Path, _ctx: &mut AssetContext) -> anyhow::Result<Self> {
+        let (bytes, format) = data;
+
+        Ok(Self {
+            data: bytes.into(),
+            format,
+        })
+    }
+}
@@ -0,0 +1,35 @@
+use std::{path::Path, sync::Arc};
+
+use super::{Asset, AssetContext};
+
+const FONT_MAGICS: [[u8; 4]; 4] = [*b"\0\x01\0\0", *b"OTTO", *b"true", *b"ttcf"];
+
+/// TrueType or OpenType font data.
+#[derive(Clone)]
+pub struct Font {
+    pub data: Arc<[u8]>,
+}
+
+impl Font {
+    /// Wraps the font for `egui::FontDefinitions::font_data`.
+    pub fn to_egui(&self) -> Arc<egui::FontData> {
+        Arc::new(egui::FontData::from_owned(self.data.to_vec()))
+    }
+}
+
+impl Asset for Font {
+    type Data = Vec<u8>;
+
+    fn decode(bytes: Vec<u8>, _path: &Path) -> anyhow::Result<Self::Data> {
+        anyhow::ensure!(
+            FONT_MAGICS.iter().any(|magic| bytes.starts_with(magic)),
+            "Not a TrueType or OpenType font"
+        );
+
+        Ok(bytes)
+    }
+
+    fn create(data: Self::Data, _path: &Path, _ctx: &mut AssetContext) -> anyhow::Result<Self> {
+        Ok(Self { data: data.into() })
+    }
+}
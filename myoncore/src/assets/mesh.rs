@@ -0,0 +1,231 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Context;
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+use wgpu::util::DeviceExt;
+
+use super::{Asset, AssetContext};
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Default, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+impl Vertex {
+    pub const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Indexed triangle list on the CPU side.
+#[derive(Clone, Debug, Default)]
+pub struct MeshData {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    /// Parses a Wavefront OBJ file. Polygons are triangulated as fans, and smooth normals
+    /// are generated when the file doesn't have any.
+    pub fn from_obj(source: &str) -> anyhow::Result<Self> {
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+
+        let mut data = Self::default();
+        let mut lookup = HashMap::new();
+        let mut missing_normals = false;
+
+        for (number, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+
+            let result = (|| -> anyhow::Result<()> {
+                match keyword {
+                    "v" => positions.push(parse_floats::<3>(words)?),
+                    "vn" => normals.push(parse_floats::<3>(words)?),
+                    "vt" => {
+                        let [u, v] = parse_floats::<2>(words)?;
+                        uvs.push([u, 1.0 - v]);
+                    }
+
+                    "f" => {
+                        let mut face = Vec::new();
+
+                        for corner in words {
+                            let mut parts = corner.split('/');
+                            let position = resolve_index(parts.next(), positions.len())?
+                                .context("Face corner has no position")?;
+                            let uv = resolve_index(parts.next(), uvs.len())?;
+                            let normal = resolve_index(parts.next(), normals.len())?;
+
+                            missing_normals |= normal.is_none();
+
+                            let index =
+                                *lookup.entry((position, uv, normal)).or_insert_with(|| {
+                                    data.vertices.push(Vertex {
+                                        position: positions[position],
+                                        normal: normal.map_or([0.0; 3], |index| normals[index]),
+                                        uv: uv.map_or([0.0; 2], |index| uvs[index]),
+                                    });
+
+                                    data.vertices.len() as u32 - 1
+                                });
+
+                            face.push(index);
+                        }
+
+                        anyhow::ensure!(face.len() >= 3, "Face has fewer than 3 corners");
+
+                        for i in 1..face.len() - 1 {
+                            data.indices
+                                .extend_from_slice(&[face[0], face[i], face[i + 1]]);
+                        }
+                    }
+
+                    _ => {}
+                }
+
+                Ok(())
+            })();
+
+            result.with_context(|| format!("Line {}", number + 1))?;
+        }
+
+        if missing_normals {
+            data.generate_normals();
+        }
+
+        Ok(data)
+    }
+
+    /// Replaces the vertex normals with area weighted face normals.
+    pub fn generate_normals(&mut self) {
+        let mut normals = vec![Vec3::ZERO; self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] =
+                [0, 1, 2].map(|i| Vec3::from(self.vertices[triangle[i] as usize].position));
+            let normal = (b - a).cross(c - a);
+
+            for &index in triangle {
+                normals[index as usize] += normal;
+            }
+        }
+
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = normal.normalize_or_zero().into();
+        }
+    }
+}
+
+fn parse_floats<'a, const N: usize>(
+    mut words: impl Iterator<Item = &'a str>,
+) -> anyhow::Result<[f32; N]> {
+    let mut values = [0.0; N];
+
+    for value in &mut values {
+        *value = words
+            .next()
+            .context("Missing value")?
+            .parse()
+            .context("Invalid number")?;
+    }
+
+    Ok(values)
+}
+
+/// Turns a 1-based (or negative, relative) OBJ index into a 0-based one.
+fn resolve_index(part: Option<&str>, len: usize) -> anyhow::Result<Option<usize>> {
+    let Some(part) = part.filter(|part| !part.is_empty()) else {
+        return Ok(None);
+    };
+
+    let index: i64 = part.parse().context("Invalid index")?;
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+
+    anyhow::ensure!(
+        (0..len as i64).contains(&resolved),
+        "Index {index} is out of range"
+    );
+
+    Ok(Some(resolved as usize))
+}
+
+/// A mesh uploaded to the GPU, drawn with [`Vertex::layout`] and 32-bit indices.
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+}
+
+impl Mesh {
+    pub fn new(device: &wgpu::Device, data: &MeshData, label: Option<&str>) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label,
+            contents: bytemuck::cast_slice(&data.vertices),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label,
+            contents: bytemuck::cast_slice(&data.indices),
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: data.indices.len() as u32,
+        }
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+    }
+}
+
+impl Asset for Mesh {
+    type Data = MeshData;
+
+    fn decode(bytes: Vec<u8>, path: &Path) -> anyhow::Result<Self::Data> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+
+        match extension.to_ascii_lowercase().as_str() {
+            "obj" => MeshData::from_obj(
+                std::str::from_utf8(&bytes).context("OBJ file is not valid UTF-8")?,
+            ),
+            _ => anyhow::bail!("Unsupported mesh format {extension:?}"),
+        }
+    }
+
+    fn create(data: Self::Data, path: &Path, ctx: &mut AssetContext) -> anyhow::Result<Self> {
+        anyhow::ensure!(!data.indices.is_empty(), "Mesh has no triangles");
+
+        Ok(Self::new(&ctx.device, &data, path.to_str()))
+    }
+}
@@ -0,0 +1,204 @@
+pub mod audio;
+pub mod font;
+pub mod mesh;
+pub mod model;
+pub mod server;
+pub mod shader;
+pub mod text;
+pub mod texture;
+
+mod watcher;
+
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wgpu::{Device, Queue};
+
+pub use audio::{AudioClip, AudioFormat};
+pub use font::Font;
+pub use mesh::{Mesh, MeshData, Vertex};
+pub use model::{
+    Material, MaterialData, Model, ModelData, ModelMesh, Node, Primitive, PrimitiveData, TextureRef,
+};
+pub use server::AssetServer;
+pub use shader::Shader;
+pub use text::Text;
+pub use texture::{TextureData, TextureLoader};
+
+/// Something the [`AssetServer`] can load from a file.
+///
+/// `decode` runs on a worker thread and should do the expensive CPU work, `create` runs on
+/// the main thread and turns the decoded data into GPU resources. `decode` gets the full path
+/// of the file, so it can read files that it refers to.
+pub trait Asset: Send + Sync + Sized + 'static {
+    type Data: Send + 'static;
+
+    fn decode(bytes: Vec<u8>, path: &Path) -> anyhow::Result<Self::Data>;
+
+    fn create(data: Self::Data, path: &Path, ctx: &mut AssetContext) -> anyhow::Result<Self>;
+}
+
+/// GPU access handed to [`Asset::create`].
+pub struct AssetContext {
+    pub device: Device,
+    pub queue: Queue,
+    pub textures: TextureLoader,
+}
+
+impl AssetContext {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        Self {
+            device: device.clone(),
+            queue: queue.clone(),
+            textures: TextureLoader::new(device, queue),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
+pub struct AssetId(pub(crate) u64);
+
+pub struct Handle<T> {
+    id: AssetId,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(id: AssetId) -> Self {
+        Self {
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> AssetId {
+        self.id
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle<{}>({})", std::any::type_name::<T>(), self.id.0)
+    }
+}
+
+/// An asset referenced by path, for components that are saved in scenes. Serializes as just
+/// the path, the asset is loaded the first time [`AssetRef::handle`] is called.
+pub struct AssetRef<T> {
+    path: PathBuf,
+    handle: Option<Handle<T>>,
+}
+
+impl<T: Asset> AssetRef<T> {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            handle: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn handle(&mut self, assets: &mut AssetServer) -> Handle<T> {
+        *self.handle.get_or_insert_with(|| assets.load(&self.path))
+    }
+}
+
+impl<T> Clone for AssetRef<T> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            handle: self.handle,
+        }
+    }
+}
+
+impl<T> PartialEq for AssetRef<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl<T> fmt::Debug for AssetRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "AssetRef<{}>({})",
+            std::any::type_name::<T>(),
+            self.path.display()
+        )
+    }
+}
+
+impl<T> Serialize for AssetRef<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.path.serialize(serializer)
+    }
+}
+
+impl<'de, T: Asset> Deserialize<'de> for AssetRef<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PathBuf::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed(String),
+}
+
+/// Sent to [`crate::AppHandler::on_asset_event`] after a watched file changed on disk.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AssetEvent {
+    /// The asset behind the handle was replaced with the new version.
+    Reloaded { id: AssetId, path: PathBuf },
+    /// The new version couldn't be loaded, the handle keeps pointing at the old one.
+    ReloadFailed {
+        id: AssetId,
+        path: PathBuf,
+        error: String,
+    },
+}
+
+impl AssetEvent {
+    pub fn id(&self) -> AssetId {
+        match self {
+            Self::Reloaded { id, .. } | Self::ReloadFailed { id, .. } => *id,
+        }
+    }
+
+    pub fn is<T>(&self, handle: Handle<T>) -> bool {
+        self.id() == handle.id()
+    }
+}
@@ -0,0 +1,480 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Context;
+use glam::{Quat, Vec3, Vec4};
+use wgpu::TextureFormat;
+
+use crate::{
+    ecs::{Entity, World},
+    renderer::Texture,
+    scene::{Transform, set_parent},
+};
+
+use super::{Asset, AssetContext, Handle, Mesh, MeshData, TextureData, Vertex};
+
+/// A texture used by a [`MaterialData`], pointing into [`ModelData::images`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextureRef {
+    pub image: usize,
+    pub address_modes: [wgpu::AddressMode; 2],
+    pub mag_filter: wgpu::FilterMode,
+}
+
+/// glTF metallic-roughness material. Textures are multiplied with their factors.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MaterialData {
+    pub name: Option<String>,
+    pub base_color: Vec4,
+    pub base_color_texture: Option<TextureRef>,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// Roughness in the green channel, metalness in the blue one.
+    pub metallic_roughness_texture: Option<TextureRef>,
+    pub emissive: Vec3,
+    pub emissive_texture: Option<TextureRef>,
+}
+
+impl Default for MaterialData {
+    fn default() -> Self {
+        Self {
+            name: None,
+            base_color: Vec4::ONE,
+            base_color_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            emissive: Vec3::ZERO,
+            emissive_texture: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PrimitiveData {
+    pub mesh: MeshData,
+    pub material: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Node {
+    pub name: Option<String>,
+    pub transform: Transform,
+    pub mesh: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+/// A decoded glTF file. Primitives without a material use an extra default one at the end of
+/// `materials`.
+#[derive(Clone, Debug, Default)]
+pub struct ModelData {
+    pub meshes: Vec<Vec<PrimitiveData>>,
+    pub materials: Vec<MaterialData>,
+    /// sRGB RGBA8 images, uploaded as linear where the material needs it.
+    pub images: Vec<TextureData>,
+    pub nodes: Vec<Node>,
+    pub roots: Vec<usize>,
+}
+
+impl ModelData {
+    /// Parses a `.gltf` or `.glb` file. External buffers and images are read relative to
+    /// `base`, without it only embedded ones can be loaded.
+    pub fn from_gltf(bytes: &[u8], base: Option<&Path>) -> anyhow::Result<Self> {
+        let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(bytes)?;
+        let buffers = gltf::import_buffers(&document, base, blob)?;
+        let images = gltf::import_images(&document, base, &buffers)?;
+
+        let mut data = Self {
+            images: images.into_iter().map(image_to_rgba8).collect(),
+            ..Default::default()
+        };
+
+        for material in document.materials() {
+            let pbr = material.pbr_metallic_roughness();
+
+            data.materials.push(MaterialData {
+                name: material.name().map(String::from),
+                base_color: Vec4::from(pbr.base_color_factor()),
+                base_color_texture: pbr
+                    .base_color_texture()
+                    .map(|info| texture_ref(info.texture())),
+                metallic: pbr.metallic_factor(),
+                roughness: pbr.roughness_factor(),
+                metallic_roughness_texture: pbr
+                    .metallic_roughness_texture()
+                    .map(|info| texture_ref(info.texture())),
+                emissive: Vec3::from(material.emissive_factor()),
+                emissive_texture: material
+                    .emissive_texture()
+                    .map(|info| texture_ref(info.texture())),
+            });
+        }
+
+        let default_material = data.materials.len();
+        let mut uses_default_material = false;
+
+        for mesh in document.meshes() {
+            let mut primitives = Vec::new();
+
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    tracing::warn!(
+                        "Skipping {:?} primitive of mesh {}, only triangles are supported",
+                        primitive.mode(),
+                        mesh.index()
+                    );
+                    continue;
+                }
+
+                let reader = primitive.reader(|buffer| Some(&buffers.get(buffer.index())?.0));
+
+                let positions: Vec<[f32; 3]> = reader
+                    .read_positions()
+                    .with_context(|| format!("Mesh {} has no positions", mesh.index()))?
+                    .collect();
+                let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(Iterator::collect);
+                let uvs: Option<Vec<[f32; 2]>> = reader
+                    .read_tex_coords(0)
+                    .map(|uvs| uvs.into_f32().collect());
+
+                let vertices = positions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &position)| Vertex {
+                        position,
+                        normal: normals
+                            .as_ref()
+                            .and_then(|normals| normals.get(i).copied())
+                            .unwrap_or_default(),
+                        uv: uvs
+                            .as_ref()
+                            .and_then(|uvs| uvs.get(i).copied())
+                            .unwrap_or_default(),
+                    })
+                    .collect();
+
+                let indices: Vec<u32> = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect(),
+                    None => (0..positions.len() as u32).collect(),
+                };
+
+                anyhow::ensure!(
+                    indices
+                        .iter()
+                        .all(|&index| (index as usize) < positions.len()),
+                    "Mesh {} has out of range indices",
+                    mesh.index()
+                );
+
+                let mut mesh_data = MeshData { vertices, indices };
+                if normals.is_none() {
+                    mesh_data.generate_normals();
+                }
+
+                let material = primitive.material().index().unwrap_or_else(|| {
+                    uses_default_material = true;
+                    default_material
+                });
+
+                primitives.push(PrimitiveData {
+                    mesh: mesh_data,
+                    material,
+                });
+            }
+
+            data.meshes.push(primitives);
+        }
+
+        if uses_default_material {
+            data.materials.push(MaterialData::default());
+        }
+
+        for node in document.nodes() {
+            let (translation, rotation, scale) = node.transform().decomposed();
+
+            data.nodes.push(Node {
+                name: node.name().map(String::from),
+                transform: Transform {
+                    translation: Vec3::from(translation),
+                    rotation: Quat::from_array(rotation),
+                    scale: Vec3::from(scale),
+                },
+                mesh: node.mesh().map(|mesh| mesh.index()),
+                children: node.children().map(|child| child.index()).collect(),
+            });
+        }
+
+        data.roots = match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(scene) => scene.nodes().map(|node| node.index()).collect(),
+            None => (0..data.nodes.len())
+                .filter(|&index| !data.nodes.iter().any(|node| node.children.contains(&index)))
+                .collect(),
+        };
+
+        Ok(data)
+    }
+}
+
+fn texture_ref(texture: gltf::Texture<'_>) -> TextureRef {
+    let sampler = texture.sampler();
+    let address_mode = |mode| match mode {
+        gltf::texture::WrappingMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        gltf::texture::WrappingMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+        gltf::texture::WrappingMode::Repeat => wgpu::AddressMode::Repeat,
+    };
+
+    TextureRef {
+        image: texture.source().index(),
+        address_modes: [
+            address_mode(sampler.wrap_s()),
+            address_mode(sampler.wrap_t()),
+        ],
+        mag_filter: match sampler.mag_filter() {
+            Some(gltf::texture::MagFilter::Nearest) => wgpu::FilterMode::Nearest,
+            _ => wgpu::FilterMode::Linear,
+        },
+    }
+}
+
+/// Converts any glTF image to RGBA8. One channel images are grey, two channel ones are grey
+/// with alpha.
+fn image_to_rgba8(image: gltf::image::Data) -> TextureData {
+    use gltf::image::Format;
+
+    let (channels, depth) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        Format::R32G32B32FLOAT => (3, 4),
+        Format::R32G32B32A32FLOAT => (4, 4),
+    };
+
+    let mut pixels = Vec::with_capacity(image.width as usize * image.height as usize * 4);
+
+    for pixel in image.pixels.chunks_exact(channels * depth) {
+        let value = |channel: usize| {
+            let bytes = &pixel[channel * depth..(channel + 1) * depth];
+
+            match depth {
+                1 => bytes[0],
+                2 => (u16::from_ne_bytes([bytes[0], bytes[1]]) >> 8) as u8,
+                _ => {
+                    let value = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+                }
+            }
+        };
+
+        let rgba = match channels {
+            1 => [value(0), value(0), value(0), 255],
+            2 => [value(0), value(0), value(0), value(1)],
+            3 => [value(0), value(1), value(2), 255],
+            _ => [value(0), value(1), value(2), value(3)],
+        };
+
+        pixels.extend_from_slice(&rgba);
+    }
+
+    TextureData::from_rgba8(image.width, image.height, pixels)
+}
+
+/// Material of a [`Model`], drawn by [`crate::renderer::MeshBatch`]. Missing textures count
+/// as white.
+#[derive(Clone)]
+pub struct Material {
+    pub base_color: Vec4,
+    pub base_color_texture: Option<Texture>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub metallic_roughness_texture: Option<Texture>,
+    pub emissive: Vec3,
+    pub emissive_texture: Option<Texture>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            base_color: Vec4::ONE,
+            base_color_texture: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            metallic_roughness_texture: None,
+            emissive: Vec3::ZERO,
+            emissive_texture: None,
+        }
+    }
+}
+
+pub struct Primitive {
+    pub mesh: Mesh,
+    pub material: usize,
+}
+
+/// A glTF model on the GPU: meshes made of primitives, their materials, and the node
+/// hierarchy that places them.
+pub struct Model {
+    pub meshes: Vec<Vec<Primitive>>,
+    pub materials: Vec<Material>,
+    pub nodes: Vec<Node>,
+    pub roots: Vec<usize>,
+}
+
+impl Model {
+    pub fn new(
+        data: ModelData,
+        ctx: &mut AssetContext,
+        label: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let mut textures: HashMap<(usize, bool), Texture> = HashMap::new();
+
+        let mut texture = |texture_ref: Option<TextureRef>, srgb: bool| -> anyhow::Result<_> {
+            let Some(texture_ref) = texture_ref else {
+                return Ok(None);
+            };
+
+            let texture = match textures.get(&(texture_ref.image, srgb)) {
+                Some(texture) => texture.clone(),
+                None => {
+                    let mut image = data
+                        .images
+                        .get(texture_ref.image)
+                        .with_context(|| format!("Image {} doesn't exist", texture_ref.image))?
+                        .clone();
+                    if !srgb {
+                        image.format = TextureFormat::Rgba8Unorm;
+                    }
+
+                    let texture = ctx.textures.create(&image, label)?;
+                    textures.insert((texture_ref.image, srgb), texture.clone());
+
+                    texture
+                }
+            };
+
+            let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
+                label,
+                address_mode_u: texture_ref.address_modes[0],
+                address_mode_v: texture_ref.address_modes[1],
+                mag_filter: texture_ref.mag_filter,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            });
+
+            Ok(Some(Texture::from_parts(texture.texture, sampler)))
+        };
+
+        let mut materials = Vec::with_capacity(data.materials.len());
+        for material in &data.materials {
+            materials.push(Material {
+                base_color: material.base_color,
+                base_color_texture: texture(material.base_color_texture, true)?,
+                metallic: material.metallic,
+                roughness: material.roughness,
+                metallic_roughness_texture: texture(material.metallic_roughness_texture, false)?,
+                emissive: material.emissive,
+                emissive_texture: texture(material.emissive_texture, true)?,
+            });
+        }
+
+        let mut meshes = Vec::with_capacity(data.meshes.len());
+        for primitives in &data.meshes {
+            let mut uploaded = Vec::with_capacity(primitives.len());
+
+            for primitive in primitives
+                .iter()
+                .filter(|primitive| !primitive.mesh.indices.is_empty())
+            {
+                anyhow::ensure!(
+                    primitive.material < materials.len(),
+                    "Material {} doesn't exist",
+                    primitive.material
+                );
+
+                uploaded.push(Primitive {
+                    mesh: Mesh::new(&ctx.device, &primitive.mesh, label),
+                    material: primitive.material,
+                });
+            }
+
+            meshes.push(uploaded);
+        }
+
+        Ok(Self {
+            meshes,
+            materials,
+            nodes: data.nodes,
+            roots: data.roots,
+        })
+    }
+
+    pub fn find_node(&self, name: &str) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|node| node.name.as_deref() == Some(name))
+    }
+
+    /// Spawns the node hierarchy under a new root entity and returns the root. Nodes with a
+    /// mesh get a [`ModelMesh`].
+    pub fn spawn(&self, handle: Handle<Model>, world: &mut World) -> Entity {
+        let root = world.spawn((Transform::IDENTITY,));
+        let mut stack: Vec<(usize, Entity)> =
+            self.roots.iter().rev().map(|&node| (node, root)).collect();
+        let mut spawned = vec![false; self.nodes.len()];
+
+        while let Some((index, parent)) = stack.pop() {
+            let Some(node) = self.nodes.get(index) else {
+                continue;
+            };
+
+            if std::mem::replace(&mut spawned[index], true) {
+                tracing::error!("Node {index} appears more than once in the hierarchy");
+                continue;
+            }
+
+            let entity = world.spawn((node.transform,));
+            if let Some(mesh) = node.mesh {
+                world.insert(
+                    entity,
+                    ModelMesh {
+                        model: handle,
+                        mesh,
+                    },
+                );
+            }
+
+            set_parent(world, entity, parent);
+
+            stack.extend(node.children.iter().rev().map(|&child| (child, entity)));
+        }
+
+        root
+    }
+}
+
+impl Asset for Model {
+    type Data = ModelData;
+
+    fn decode(bytes: Vec<u8>, path: &Path) -> anyhow::Result<Self::Data> {
+        ModelData::from_gltf(&bytes, path.parent())
+    }
+
+    fn create(data: Self::Data, path: &Path, ctx: &mut AssetContext) -> anyhow::Result<Self> {
+        Self::new(data, ctx, path.to_str())
+    }
+}
+
+/// Component for an entity that draws one mesh of a [`Model`], see
+/// [`crate::renderer::MeshBatch::draw_world`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ModelMesh {
+    pub model: Handle<Model>,
+    pub mesh: usize,
+}
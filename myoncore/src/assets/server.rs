@@ -0,0 +1,427 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fs, mem, panic,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, mpsc},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::Context;
+
+use super::{Asset, AssetContext, AssetEvent, AssetId, Handle, LoadState, watcher::AssetWatcher};
+use crate::utils::normalize;
+
+type BoxedAsset = Box<dyn Any + Send + Sync>;
+type Finish = Box<dyn FnOnce(&mut AssetContext) -> anyhow::Result<BoxedAsset> + Send>;
+type Job = Box<dyn FnOnce() -> anyhow::Result<Finish> + Send>;
+
+const MAX_WORKERS: usize = 4;
+
+struct Entry {
+    path: Option<PathBuf>,
+    state: LoadState,
+    asset: Option<BoxedAsset>,
+    spawn: fn(&mut AssetServer, AssetId, PathBuf),
+    // Bumped on every (re)load so that a slow, outdated load can't overwrite a newer one.
+    generation: u64,
+    reloading: bool,
+}
+
+/// Loads assets on a background thread pool and hands out typed [`Handle`]s to them.
+///
+/// Loading the same path twice as the same asset type returns the same handle. Decoded
+/// assets are turned into GPU resources on the main thread in [`AssetServer::update`], which
+/// the engine calls at the start of every frame.
+///
+/// After [`AssetServer::watch`], files that change on disk are reloaded in place: existing
+/// handles point at the new version once it's ready, and an [`AssetEvent`] is queued.
+pub struct AssetServer {
+    root: PathBuf,
+    next_id: u64,
+    entries: HashMap<AssetId, Entry>,
+    by_path: HashMap<(TypeId, PathBuf), AssetId>,
+    jobs: Option<mpsc::Sender<(AssetId, u64, Job)>>,
+    results: mpsc::Receiver<(AssetId, u64, anyhow::Result<Finish>)>,
+    workers: Vec<JoinHandle<()>>,
+    ready: Vec<(AssetId, u64, Finish)>,
+    pending: usize,
+    context: Option<AssetContext>,
+    watcher: Option<AssetWatcher>,
+    changed_paths: Vec<PathBuf>,
+    events: Vec<AssetEvent>,
+}
+
+impl AssetServer {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let (job_sender, job_receiver) = mpsc::channel::<(AssetId, u64, Job)>();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let worker_count = thread::available_parallelism()
+            .map_or(1, |count| count.get())
+            .min(MAX_WORKERS);
+
+        let workers = (0..worker_count)
+            .map(|index| {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+
+                thread::Builder::new()
+                    .name(format!("myon-asset-{index}"))
+                    .spawn(move || {
+                        loop {
+                            let job = job_receiver
+                                .lock()
+                                .expect("Asset job queue poisoned")
+                                .recv();
+
+                            let Ok((id, generation, job)) = job else {
+                                break;
+                            };
+
+                            if result_sender.send((id, generation, job())).is_err() {
+                                break;
+                            }
+                        }
+                    })
+                    .expect("Failed to spawn asset worker")
+            })
+            .collect();
+
+        Self {
+            root: root.into(),
+            next_id: 0,
+            entries: HashMap::new(),
+            by_path: HashMap::new(),
+            jobs: Some(job_sender),
+            results,
+            workers,
+            ready: Vec::new(),
+            pending: 0,
+            context: None,
+            watcher: None,
+            changed_paths: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Gives the server access to the GPU. Until this is called, decoded assets stay queued.
+    pub fn attach(&mut self, context: AssetContext) {
+        self.context = Some(context);
+    }
+
+    pub fn context(&mut self) -> Option<&mut AssetContext> {
+        self.context.as_mut()
+    }
+
+    /// Starts watching the asset root so changed files get reloaded by [`AssetServer::update`].
+    pub fn watch(&mut self) -> anyhow::Result<()> {
+        self.watcher = Some(AssetWatcher::new(&self.root)?);
+
+        Ok(())
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    /// Starts loading `path`, relative to the asset root, unless it's already loaded or loading.
+    pub fn load<T: Asset>(&mut self, path: impl AsRef<Path>) -> Handle<T> {
+        let path = normalize(path.as_ref());
+        let key = (TypeId::of::<T>(), path.clone());
+
+        if let Some(id) = self.by_path.get(&key) {
+            return Handle::new(*id);
+        }
+
+        let id = self.insert(Entry {
+            path: Some(path.clone()),
+            state: LoadState::Loading,
+            asset: None,
+            spawn: Self::spawn::<T>,
+            generation: 0,
+            reloading: false,
+        });
+        self.by_path.insert(key, id);
+
+        self.spawn::<T>(id, path);
+
+        Handle::new(id)
+    }
+
+    /// Stores an asset that was created in code.
+    pub fn add<T: Asset>(&mut self, asset: T) -> Handle<T> {
+        Handle::new(self.insert(Entry {
+            path: None,
+            state: LoadState::Loaded,
+            asset: Some(Box::new(asset)),
+            spawn: Self::spawn::<T>,
+            generation: 0,
+            reloading: false,
+        }))
+    }
+
+    /// Loads the file behind `handle` again. The old version stays available until the new
+    /// one is ready, and is kept if loading fails.
+    pub fn reload<T>(&mut self, handle: Handle<T>) {
+        let Some(entry) = self.entries.get_mut(&handle.id()) else {
+            return;
+        };
+
+        let Some(path) = entry.path.clone() else {
+            return;
+        };
+
+        entry.reloading = true;
+        (entry.spawn)(self, handle.id(), path);
+    }
+
+    /// Reloads every asset that was loaded from `path`, whatever its type.
+    pub fn reload_path(&mut self, path: impl AsRef<Path>) {
+        let path = normalize(path.as_ref());
+
+        let ids: Vec<_> = self
+            .by_path
+            .iter()
+            .filter(|((_, asset_path), _)| *asset_path == path)
+            .map(|(_, id)| *id)
+            .collect();
+
+        for id in ids {
+            self.reload(Handle::<()>::new(id));
+        }
+    }
+
+    /// Takes the reload events queued since the last call.
+    pub fn take_events(&mut self) -> Vec<AssetEvent> {
+        mem::take(&mut self.events)
+    }
+
+    /// Takes the paths the watcher reported since the last call, including files that no
+    /// asset was loaded from, such as shader includes.
+    pub fn take_changed_paths(&mut self) -> Vec<PathBuf> {
+        mem::take(&mut self.changed_paths)
+    }
+
+    pub fn has_events(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    pub fn get<T: Asset>(&self, handle: Handle<T>) -> Option<&T> {
+        self.entries
+            .get(&handle.id())?
+            .asset
+            .as_ref()?
+            .downcast_ref()
+    }
+
+    pub fn get_mut<T: Asset>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.entries
+            .get_mut(&handle.id())?
+            .asset
+            .as_mut()?
+            .downcast_mut()
+    }
+
+    pub fn load_state<T>(&self, handle: Handle<T>) -> LoadState {
+        self.entries.get(&handle.id()).map_or_else(
+            || LoadState::Failed(String::from("Unknown handle")),
+            |entry| entry.state.clone(),
+        )
+    }
+
+    pub fn is_loaded<T>(&self, handle: Handle<T>) -> bool {
+        self.load_state(handle) == LoadState::Loaded
+    }
+
+    pub fn path<T>(&self, handle: Handle<T>) -> Option<&Path> {
+        self.entries.get(&handle.id())?.path.as_deref()
+    }
+
+    /// Number of assets that are still being decoded or waiting for the GPU.
+    pub fn pending(&self) -> usize {
+        self.pending + self.ready.len()
+    }
+
+    /// Collects finished background loads and creates their GPU resources.
+    pub fn update(&mut self) {
+        if let Some(watcher) = self.watcher.as_ref() {
+            for path in watcher.changed_paths() {
+                self.reload_path(&path);
+                self.changed_paths.push(path);
+            }
+        }
+
+        while let Ok((id, generation, result)) = self.results.try_recv() {
+            self.receive(id, generation, result);
+        }
+
+        self.finish_ready();
+    }
+
+    /// Blocks until every pending load has finished.
+    pub fn wait(&mut self) {
+        while self.pending > 0 {
+            let Ok((id, generation, result)) = self.results.recv() else {
+                break;
+            };
+
+            self.receive(id, generation, result);
+        }
+
+        self.finish_ready();
+    }
+
+    fn insert(&mut self, entry: Entry) -> AssetId {
+        let id = AssetId(self.next_id);
+        self.next_id += 1;
+        self.entries.insert(id, entry);
+
+        id
+    }
+
+    fn spawn<T: Asset>(&mut self, id: AssetId, path: PathBuf) {
+        let Some(entry) = self.entries.get_mut(&id) else {
+            return;
+        };
+
+        entry.generation += 1;
+        let generation = entry.generation;
+
+        let full_path = self.root.join(&path);
+
+        let job: Job = Box::new(move || {
+            // A panicking decoder would otherwise leave the load pending forever.
+            let data = panic::catch_unwind(|| {
+                let bytes = fs::read(&full_path)
+                    .with_context(|| format!("Failed to read {}", full_path.display()))?;
+
+                T::decode(bytes, &full_path)
+                    .with_context(|| format!("Failed to decode {}", path.display()))
+            })
+            .unwrap_or_else(|payload| {
+                Err(anyhow::anyhow!(
+                    "Decoding {} panicked: {}",
+                    path.display(),
+                    panic_message(payload.as_ref())
+                ))
+            })?;
+
+            let finish: Finish = Box::new(move |ctx| {
+                let asset = T::create(data, &path, ctx)
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+
+                Ok(Box::new(asset) as BoxedAsset)
+            });
+
+            Ok(finish)
+        });
+
+        self.pending += 1;
+        self.jobs
+            .as_ref()
+            .expect("Asset workers stopped")
+            .send((id, generation, job))
+            .expect("Asset workers stopped");
+    }
+
+    fn receive(&mut self, id: AssetId, generation: u64, result: anyhow::Result<Finish>) {
+        self.pending -= 1;
+
+        match result {
+            Ok(finish) => self.ready.push((id, generation, finish)),
+            Err(e) => Self::complete(&mut self.entries, &mut self.events, id, generation, Err(e)),
+        }
+    }
+
+    fn finish_ready(&mut self) {
+        let Some(context) = self.context.as_mut() else {
+            return;
+        };
+
+        for (id, generation, finish) in self.ready.drain(..) {
+            let is_current = self
+                .entries
+                .get(&id)
+                .is_some_and(|entry| entry.generation == generation);
+
+            // Don't bother creating GPU resources that a newer load will replace anyway.
+            if !is_current {
+                continue;
+            }
+
+            let result = finish(context);
+            Self::complete(&mut self.entries, &mut self.events, id, generation, result);
+        }
+    }
+
+    fn complete(
+        entries: &mut HashMap<AssetId, Entry>,
+        events: &mut Vec<AssetEvent>,
+        id: AssetId,
+        generation: u64,
+        result: anyhow::Result<BoxedAsset>,
+    ) {
+        let Some(entry) = entries.get_mut(&id) else {
+            return;
+        };
+
+        if entry.generation != generation {
+            return;
+        }
+
+        let path = entry.path.clone().unwrap_or_default();
+        let reloading = mem::take(&mut entry.reloading);
+
+        match result {
+            Ok(asset) => {
+                entry.asset = Some(asset);
+                entry.state = LoadState::Loaded;
+
+                if reloading {
+                    tracing::info!("Reloaded {}", path.display());
+                    events.push(AssetEvent::Reloaded { id, path });
+                }
+            }
+
+            Err(e) => {
+                tracing::error!("{e:#}");
+
+                if entry.asset.is_none() {
+                    entry.state = LoadState::Failed(format!("{e:#}"));
+                }
+
+                if reloading {
+                    events.push(AssetEvent::ReloadFailed {
+                        id,
+                        path,
+                        error: format!("{e:#}"),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl Drop for AssetServer {
+    fn drop(&mut self) {
+        self.jobs.take();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
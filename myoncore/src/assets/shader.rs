@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::{Asset, AssetContext};
+
+/// A compiled WGSL shader module along with the source it was built from.
+pub struct Shader {
+    pub module: wgpu::ShaderModule,
+    pub source: String,
+}
+
+impl Shader {
+    /// Compiles `source`, returning the validation error instead of panicking on bad WGSL.
+    pub fn from_wgsl(
+        device: &wgpu::Device,
+        source: String,
+        label: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label,
+            source: wgpu::ShaderSource::Wgsl(source.as_str().into()),
+        });
+
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            anyhow::bail!("Failed to compile shader: {error}");
+        }
+
+        Ok(Self { module, source })
+    }
+}
+
+impl Asset for Shader {
+    type Data = String;
+
+    fn decode(bytes: Vec<u8>, _path: &Path) -> anyhow::Result<Self::Data> {
+        String::from_utf8(bytes).context("Shader source is not valid UTF-8")
+    }
+
+    fn create(data: Self::Data, path: &Path, ctx: &mut AssetContext) -> anyhow::Result<Self> {
+        Self::from_wgsl(&ctx.device, data, path.to_str())
+    }
+}
@@ -0,0 +1,23 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::{Asset, AssetContext};
+
+/// A UTF-8 text file, such as a config file the app parses itself.
+#[derive(Clone, Debug)]
+pub struct Text {
+    pub contents: String,
+}
+
+impl Asset for Text {
+    type Data = String;
+
+    fn decode(bytes: Vec<u8>, _path: &Path) -> anyhow::Result<Self::Data> {
+        String::from_utf8(bytes).context("File is not valid UTF-8")
+    }
+
+    fn create(data: Self::Data, _path: &Path, _ctx: &mut AssetContext) -> anyhow::Result<Self> {
+        Ok(Self { contents: data })
+    }
+}
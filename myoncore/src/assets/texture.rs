@@ -0,0 +1,328 @@
+use std::{fs, io::Read, path::Path};
+
+use anyhow::Context;
+use wgpu::{Device, Queue, Sampler, TextureFormat};
+
+use crate::renderer::{
+    Texture,
+    mipmap::{MipmapGenerator, mip_level_count},
+};
+
+use super::{Asset, AssetContext};
+
+const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+/// Decoded texture data that hasn't been uploaded to the GPU yet.
+///
+/// `levels` holds one buffer per mip level, starting at the full size level.
+#[derive(Clone, Debug)]
+pub struct TextureData {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl TextureData {
+    pub fn from_rgba8(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        Self {
+            width,
+            height,
+            format: TextureFormat::Rgba8UnormSrgb,
+            levels: vec![pixels],
+        }
+    }
+
+    /// Decodes PNG, JPEG or KTX2 data. The format is detected from the contents.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Self::decode(bytes, None)
+    }
+
+    /// Like [`Self::from_bytes`], but also decodes TGA data, which has no signature to
+    /// detect, when `path` has a `.tga` extension.
+    pub fn from_bytes_at(bytes: &[u8], path: &Path) -> anyhow::Result<Self> {
+        Self::decode(bytes, Some(path))
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Self::from_bytes_at(&bytes, path)
+            .with_context(|| format!("Failed to load {}", path.display()))
+    }
+
+    /// Whether the lower mip levels have to be generated on the GPU after uploading.
+    pub fn needs_mipmaps(&self) -> bool {
+        self.levels.len() == 1
+            && mip_level_count(self.width, self.height) > 1
+            && matches!(
+                self.format,
+                TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+            )
+    }
+
+    fn decode(bytes: &[u8], path: Option<&Path>) -> anyhow::Result<Self> {
+        if bytes.starts_with(&KTX2_MAGIC) {
+            return Self::from_ktx2(bytes);
+        }
+
+        let is_tga = path
+            .and_then(Path::extension)
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("tga"));
+
+        let format = match image::guess_format(bytes) {
+            Ok(format) => format,
+            Err(_) if is_tga => image::ImageFormat::Tga,
+            Err(_) => anyhow::bail!("Unknown image format"),
+        };
+        let image = image::load_from_memory_with_format(bytes, format)
+            .with_context(|| format!("Failed to decode {format:?} image"))?
+            .to_rgba8();
+
+        Ok(Self::from_rgba8(
+            image.width(),
+            image.height(),
+            image.into_raw(),
+        ))
+    }
+
+    fn from_ktx2(bytes: &[u8]) -> anyhow::Result<Self> {
+        let reader = ktx2::Reader::new(bytes).context("Failed to parse KTX2 header")?;
+        let header = reader.header();
+
+        anyhow::ensure!(
+            header.pixel_depth <= 1 && header.layer_count <= 1 && header.face_count == 1,
+            "Only 2D KTX2 textures are supported"
+        );
+
+        let ktx2_format = header
+            .format
+            .context("KTX2 textures without a format (Basis Universal) are not supported")?;
+        let format = ktx2_format_to_wgpu(ktx2_format)
+            .with_context(|| format!("Unsupported KTX2 format {ktx2_format:?}"))?;
+
+        let levels = reader
+            .levels()
+            .map(|level| match header.supercompression_scheme {
+                None => Ok(level.data.to_vec()),
+
+                Some(ktx2::SupercompressionScheme::Zstandard) => {
+                    let mut data = Vec::with_capacity(level.uncompressed_byte_length as usize);
+                    ruzstd::decoding::StreamingDecoder::new(level.data)
+                        .context("Failed to start zstd decoder")?
+                        .read_to_end(&mut data)
+                        .context("Failed to decompress KTX2 level")?;
+
+                    Ok(data)
+                }
+
+                Some(scheme) => anyhow::bail!("Unsupported KTX2 supercompression {scheme:?}"),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        anyhow::ensure!(!levels.is_empty(), "KTX2 texture has no levels");
+
+        Ok(Self {
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            format,
+            levels,
+        })
+    }
+}
+
+fn ktx2_format_to_wgpu(format: ktx2::Format) -> Option<TextureFormat> {
+    use ktx2::Format as F;
+    use wgpu::{AstcBlock, AstcChannel};
+
+    Some(match format {
+        F::R8_UNORM => TextureFormat::R8Unorm,
+        F::R8G8_UNORM => TextureFormat::Rg8Unorm,
+        F::R8G8B8A8_UNORM => TextureFormat::Rgba8Unorm,
+        F::R8G8B8A8_SRGB => TextureFormat::Rgba8UnormSrgb,
+        F::B8G8R8A8_UNORM => TextureFormat::Bgra8Unorm,
+        F::B8G8R8A8_SRGB => TextureFormat::Bgra8UnormSrgb,
+        F::R16G16B16A16_SFLOAT => TextureFormat::Rgba16Float,
+        F::R32G32B32A32_SFLOAT => TextureFormat::Rgba32Float,
+        F::BC1_RGBA_UNORM_BLOCK => TextureFormat::Bc1RgbaUnorm,
+        F::BC1_RGBA_SRGB_BLOCK => TextureFormat::Bc1RgbaUnormSrgb,
+        F::BC3_UNORM_BLOCK => TextureFormat::Bc3RgbaUnorm,
+        F::BC3_SRGB_BLOCK => TextureFormat::Bc3RgbaUnormSrgb,
+        F::BC4_UNORM_BLOCK => TextureFormat::Bc4RUnorm,
+        F::BC5_UNORM_BLOCK => TextureFormat::Bc5RgUnorm,
+        F::BC6H_UFLOAT_BLOCK => TextureFormat::Bc6hRgbUfloat,
+        F::BC7_UNORM_BLOCK => TextureFormat::Bc7RgbaUnorm,
+        F::BC7_SRGB_BLOCK => TextureFormat::Bc7RgbaUnormSrgb,
+        F::ETC2_R8G8B8_UNORM_BLOCK => TextureFormat::Etc2Rgb8Unorm,
+        F::ETC2_R8G8B8_SRGB_BLOCK => TextureFormat::Etc2Rgb8UnormSrgb,
+        F::ETC2_R8G8B8A8_UNORM_BLOCK => TextureFormat::Etc2Rgba8Unorm,
+        F::ETC2_R8G8B8A8_SRGB_BLOCK => TextureFormat::Etc2Rgba8UnormSrgb,
+        F::ASTC_4x4_UNORM_BLOCK => TextureFormat::Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::Unorm,
+        },
+        F::ASTC_4x4_SRGB_BLOCK => TextureFormat::Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::UnormSrgb,
+        },
+        _ => return None,
+    })
+}
+
+/// Uploads [`TextureData`] to the GPU, generating mipmaps where needed.
+pub struct TextureLoader {
+    device: Device,
+    queue: Queue,
+    sampler: Sampler,
+    mipmaps: MipmapGenerator,
+}
+
+impl TextureLoader {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Default Texture Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            device: device.clone(),
+            queue: queue.clone(),
+            sampler,
+            mipmaps: MipmapGenerator::new(device),
+        }
+    }
+
+    pub fn load(&mut self, path: impl AsRef<Path>) -> anyhow::Result<Texture> {
+        let path = path.as_ref();
+        let data = TextureData::from_path(path)?;
+
+        self.create(&data, path.to_str())
+    }
+
+    pub fn load_from_bytes(
+        &mut self,
+        bytes: &[u8],
+        label: Option<&str>,
+    ) -> anyhow::Result<Texture> {
+        let data = TextureData::from_bytes(bytes)?;
+
+        self.create(&data, label)
+    }
+
+    pub fn create(&mut self, data: &TextureData, label: Option<&str>) -> anyhow::Result<Texture> {
+        let required_features = data.format.required_features();
+        anyhow::ensure!(
+            self.device.features().contains(required_features),
+            "Texture format {:?} needs device features {required_features:?}",
+            data.format
+        );
+
+        let max_size = self.device.limits().max_texture_dimension_2d;
+        anyhow::ensure!(
+            (1..=max_size).contains(&data.width) && (1..=max_size).contains(&data.height),
+            "Texture size {}x{} isn't between 1 and {max_size}",
+            data.width,
+            data.height
+        );
+
+        let generate_mipmaps = data.needs_mipmaps();
+        let mip_level_count = if generate_mipmaps {
+            mip_level_count(data.width, data.height)
+        } else {
+            data.levels.len() as u32
+        };
+
+        let mut usage = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
+        if generate_mipmaps {
+            usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: data.width,
+                height: data.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: data.format,
+            usage,
+            view_formats: &[],
+        });
+
+        let (block_width, block_height) = data.format.block_dimensions();
+        let block_size = data
+            .format
+            .block_copy_size(None)
+            .with_context(|| format!("Can't upload texture format {:?}", data.format))?;
+
+        for (level, bytes) in data.levels.iter().enumerate() {
+            let size = wgpu::Extent3d {
+                width: data.width,
+                height: data.height,
+                depth_or_array_layers: 1,
+            }
+            .mip_level_size(level as u32, wgpu::TextureDimension::D2);
+
+            let blocks_wide = size.width.div_ceil(block_width);
+            let blocks_high = size.height.div_ceil(block_height);
+
+            anyhow::ensure!(
+                bytes.len() as u64 >= blocks_wide as u64 * blocks_high as u64 * block_size as u64,
+                "Mip level {level} is too small"
+            );
+
+            self.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                bytes,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(blocks_wide * block_size),
+                    rows_per_image: Some(blocks_high),
+                },
+                size.physical_size(data.format),
+            );
+        }
+
+        if generate_mipmaps {
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Mipmap Encoder"),
+                });
+
+            self.mipmaps.generate(&mut encoder, &texture);
+
+            self.queue.submit(std::iter::once(encoder.finish()));
+        }
+
+        Ok(Texture::from_parts(texture, self.sampler.clone()))
+    }
+}
+
+impl Asset for Texture {
+    type Data = TextureData;
+
+    fn decode(bytes: Vec<u8>, path: &Path) -> anyhow::Result<Self::Data> {
+        TextureData::from_bytes_at(&bytes, path)
+    }
+
+    fn create(data: Self::Data, path: &Path, ctx: &mut AssetContext) -> anyhow::Result<Self> {
+        ctx.textures.create(&data, path.to_str())
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use anyhow::Context;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+/// Watches a directory tree and reports which files changed, relative to its root.
+pub(crate) struct AssetWatcher {
+    root: PathBuf,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl AssetWatcher {
+    pub(crate) fn new(root: &Path) -> anyhow::Result<Self> {
+        let root = root
+            .canonicalize()
+            .with_context(|| format!("Failed to find {}", root.display()))?;
+
+        let (sender, events) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("Failed to create watcher")?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", root.display()))?;
+
+        tracing::info!("Watching {} for changes", root.display());
+
+        Ok(Self {
+            root,
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    /// Files that were created or modified since the last call. Editors tend to write a
+    /// file several times when saving, so each path shows up at most once.
+    pub(crate) fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut seen = HashSet::new();
+        let mut paths = Vec::new();
+
+        for event in self.events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+
+                Err(e) => {
+                    tracing::warn!("Asset watcher error: {e}");
+                    continue;
+                }
+            };
+
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+
+            for path in event.paths {
+                let Ok(relative) = path.strip_prefix(&self.root) else {
+                    continue;
+                };
+
+                if seen.insert(relative.to_path_buf()) {
+                    paths.push(relative.to_path_buf());
+                }
+            }
+        }
+
+        paths
+    }
+}
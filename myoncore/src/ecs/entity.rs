@@ -0,0 +1,111 @@
+/// A handle to something in a [`super::World`]. Handles of despawned entities stay invalid
+/// even after their slot is reused.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// Slot of the entity, reused after it's despawned.
+    pub fn index(self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+struct Slot {
+    generation: u32,
+    /// Position in `Entities::alive`, `None` once despawned.
+    alive: Option<u32>,
+}
+
+/// Hands out entities and recycles the slots of despawned ones.
+#[derive(Default)]
+pub(crate) struct Entities {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    alive: Vec<Entity>,
+}
+
+impl Entities {
+    pub(crate) fn alloc(&mut self) -> Entity {
+        let position = Some(self.alive.len() as u32);
+
+        let entity = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.alive = position;
+
+                Entity {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+
+            None => {
+                let index = u32::try_from(self.slots.len()).expect("Too many entities");
+                self.slots.push(Slot {
+                    generation: 0,
+                    alive: position,
+                });
+
+                Entity {
+                    index,
+                    generation: 0,
+                }
+            }
+        };
+
+        self.alive.push(entity);
+
+        entity
+    }
+
+    /// Returns whether `entity` was alive.
+    pub(crate) fn free(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        let slot = &mut self.slots[entity.index as usize];
+        let position = slot.alive.take().expect("Alive entities have a position");
+        slot.generation = slot.generation.wrapping_add(1);
+
+        self.alive.swap_remove(position as usize);
+        if let Some(moved) = self.alive.get(position as usize) {
+            self.slots[moved.index as usize].alive = Some(position);
+        }
+
+        self.free.push(entity.index);
+
+        true
+    }
+
+    pub(crate) fn is_alive(&self, entity: Entity) -> bool {
+        self.slots
+            .get(entity.index as usize)
+            .is_some_and(|slot| slot.alive.is_some() && slot.generation == entity.generation)
+    }
+
+    pub(crate) fn as_slice(&self) -> &[Entity] {
+        &self.alive
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.alive.len()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        for entity in self.alive.drain(..) {
+            let slot = &mut self.slots[entity.index as usize];
+            slot.alive = None;
+            slot.generation = slot.generation.wrapping_add(1);
+
+            self.free.push(entity.index);
+        }
+    }
+}
@@ -0,0 +1,242 @@
+pub mod entity;
+pub mod query;
+pub mod schedule;
+pub mod storage;
+
+use std::{
+    any::{Any, TypeId, type_name},
+    cell::{Ref, RefCell, RefMut},
+    collections::HashMap,
+};
+
+pub use entity::Entity;
+pub use query::{Query, QueryData, QueryFilter, ReadOnlyQueryData, With, Without};
+pub use schedule::{Schedule, Stage, System};
+pub use storage::SparseSet;
+
+use entity::Entities;
+use storage::AnyStorage;
+
+/// Components that are inserted together, as a tuple.
+pub trait Bundle: 'static {
+    fn insert(self, world: &mut World, entity: Entity);
+}
+
+macro_rules! impl_bundle {
+    ($($name:ident $var:ident),+) => {
+        impl<$($name: 'static),+> Bundle for ($($name,)+) {
+            fn insert(self, world: &mut World, entity: Entity) {
+                let ($($var,)+) = self;
+                $(world.storage_entry::<$name>().insert(entity, $var);)+
+            }
+        }
+    };
+}
+
+impl_bundle!(A a);
+impl_bundle!(A a, B b);
+impl_bundle!(A a, B b, C c);
+impl_bundle!(A a, B b, C c, D d);
+impl_bundle!(A a, B b, C c, D d, E e);
+impl_bundle!(A a, B b, C c, D d, E e, F f);
+impl_bundle!(A a, B b, C c, D d, E e, F f, G g);
+impl_bundle!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+/// Entities, their components, and resources: singletons looked up by type.
+///
+/// Any `'static` type can be a component. Each component type is kept in its own
+/// [`SparseSet`], behind a `RefCell` so queries can borrow several of them at once.
+#[derive(Default)]
+pub struct World {
+    entities: Entities,
+    storages: HashMap<TypeId, RefCell<Box<dyn AnyStorage>>>,
+    resources: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self, bundle: impl Bundle) -> Entity {
+        let entity = self.entities.alloc();
+        bundle.insert(self, entity);
+
+        entity
+    }
+
+    pub fn spawn_empty(&mut self) -> Entity {
+        self.entities.alloc()
+    }
+
+    /// Removes `entity` and all its components. Returns whether it existed.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.entities.free(entity) {
+            return false;
+        }
+
+        for storage in self.storages.values_mut() {
+            storage.get_mut().remove_entity(entity);
+        }
+
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.is_alive(entity)
+    }
+
+    /// Every alive entity, in no particular order.
+    pub fn entities(&self) -> &[Entity] {
+        self.entities.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.len() == 0
+    }
+
+    /// Despawns every entity, keeping the resources.
+    pub fn clear(&mut self) {
+        self.entities.clear();
+        self.storages.clear();
+    }
+
+    /// Adds `component` to `entity`, replacing one of the same type. Returns false if the
+    /// entity doesn't exist.
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        self.storage_entry::<T>().insert(entity, component);
+
+        true
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        let storage: &mut dyn Any = self
+            .storages
+            .get_mut(&TypeId::of::<T>())?
+            .get_mut()
+            .as_mut();
+
+        storage.downcast_mut::<SparseSet<T>>()?.remove(entity)
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.storage::<T>()
+            .is_some_and(|storage| storage.contains(entity))
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        Ref::filter_map(self.storage::<T>()?, |storage| storage.get(entity)).ok()
+    }
+
+    pub fn get_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<'_, T>> {
+        RefMut::filter_map(self.storage_mut::<T>()?, |storage| storage.get_mut(entity)).ok()
+    }
+
+    /// Borrows the components in `Q` of every entity that has them, for example
+    /// `world.query::<(Entity, &mut Position, &Velocity)>()`.
+    pub fn query<Q: QueryData>(&self) -> Query<'_, Q> {
+        Query::new(self)
+    }
+
+    /// Like [`Self::query`], but only for entities that also pass `F`, for example
+    /// `world.query_filtered::<&mut Position, Without<Frozen>>()`.
+    pub fn query_filtered<Q: QueryData, F: QueryFilter>(&self) -> Query<'_, Q, F> {
+        Query::new(self)
+    }
+
+    /// Adds or replaces the resource of type `T`, returning the old one.
+    pub fn insert_resource<T: 'static>(&mut self, resource: T) -> Option<T> {
+        self.resources
+            .insert(TypeId::of::<T>(), RefCell::new(Box::new(resource)))
+            .and_then(|old| old.into_inner().downcast().ok())
+            .map(|old| *old)
+    }
+
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        self.resources
+            .remove(&TypeId::of::<T>())
+            .and_then(|resource| resource.into_inner().downcast().ok())
+            .map(|resource| *resource)
+    }
+
+    pub fn contains_resource<T: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn get_resource<T: 'static>(&self) -> Option<Ref<'_, T>> {
+        let resource = self.resources.get(&TypeId::of::<T>())?;
+        let resource = resource
+            .try_borrow()
+            .unwrap_or_else(|_| panic!("{} is already borrowed mutably", type_name::<T>()));
+
+        Ref::filter_map(resource, |resource| resource.downcast_ref()).ok()
+    }
+
+    pub fn get_resource_mut<T: 'static>(&self) -> Option<RefMut<'_, T>> {
+        let resource = self.resources.get(&TypeId::of::<T>())?;
+        let resource = resource
+            .try_borrow_mut()
+            .unwrap_or_else(|_| panic!("{} is already borrowed", type_name::<T>()));
+
+        RefMut::filter_map(resource, |resource| resource.downcast_mut()).ok()
+    }
+
+    /// Panics if there is no `T`.
+    pub fn resource<T: 'static>(&self) -> Ref<'_, T> {
+        self.get_resource()
+            .unwrap_or_else(|| panic!("Resource {} doesn't exist", type_name::<T>()))
+    }
+
+    /// Panics if there is no `T`.
+    pub fn resource_mut<T: 'static>(&self) -> RefMut<'_, T> {
+        self.get_resource_mut()
+            .unwrap_or_else(|| panic!("Resource {} doesn't exist", type_name::<T>()))
+    }
+
+    pub(crate) fn storage<T: 'static>(&self) -> Option<Ref<'_, SparseSet<T>>> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        let storage = storage
+            .try_borrow()
+            .unwrap_or_else(|_| panic!("{} is already borrowed mutably", type_name::<T>()));
+
+        Ref::filter_map(storage, |storage| {
+            let storage: &dyn Any = storage.as_ref();
+            storage.downcast_ref()
+        })
+        .ok()
+    }
+
+    pub(crate) fn storage_mut<T: 'static>(&self) -> Option<RefMut<'_, SparseSet<T>>> {
+        let storage = self.storages.get(&TypeId::of::<T>())?;
+        let storage = storage
+            .try_borrow_mut()
+            .unwrap_or_else(|_| panic!("{} is already borrowed", type_name::<T>()));
+
+        RefMut::filter_map(storage, |storage| {
+            let storage: &mut dyn Any = storage.as_mut();
+            storage.downcast_mut()
+        })
+        .ok()
+    }
+
+    fn storage_entry<T: 'static>(&mut self) -> &mut SparseSet<T> {
+        let storage: &mut dyn Any = self
+            .storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| RefCell::new(Box::new(SparseSet::<T>::default())))
+            .get_mut()
+            .as_mut();
+
+        storage
+            .downcast_mut()
+            .expect("Storages are keyed by their component type")
+    }
+}
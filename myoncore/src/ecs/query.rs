@@ -0,0 +1,375 @@
+use std::{
+    cell::{Ref, RefMut},
+    marker::PhantomData,
+};
+
+use super::{Entity, SparseSet, World};
+
+/// What a query fetches for every matching entity: `&T`, `&mut T`, `Option<&T>`,
+/// `Option<&mut T>`, [`Entity`], or tuples of those.
+///
+/// Each component type can only be borrowed mutably by one query at a time, borrowing it
+/// twice panics.
+pub trait QueryData {
+    type Fetch<'w>;
+    type Item<'f>;
+
+    /// `None` if a required component was never inserted, so nothing can match.
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>>;
+
+    /// The entities that have the required component with the fewest entities, or `None` if
+    /// no component is required.
+    fn candidates<'f>(fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]>;
+
+    fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool;
+
+    /// Only called with entities that match.
+    fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: Entity) -> Self::Item<'f>;
+}
+
+/// Query data without mutable borrows, which can be iterated normally.
+pub trait ReadOnlyQueryData: QueryData {
+    fn get_ref<'f>(fetch: &'f Self::Fetch<'_>, entity: Entity) -> Self::Item<'f>;
+}
+
+/// Narrows a query without fetching anything: [`With`], [`Without`], or tuples of those.
+pub trait QueryFilter {
+    type Fetch<'w>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>>;
+    fn candidates<'f>(fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]>;
+    fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool;
+}
+
+/// Only entities that have a `T`.
+pub struct With<T>(PhantomData<T>);
+
+/// Only entities that don't have a `T`.
+pub struct Without<T>(PhantomData<T>);
+
+const MISSING: &str = "Query matched an entity without the component";
+
+impl<T: 'static> QueryData for &T {
+    type Fetch<'w> = Ref<'w, SparseSet<T>>;
+    type Item<'f> = &'f T;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage::<T>()
+    }
+
+    fn candidates<'f>(fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+        Some(fetch.entities())
+    }
+
+    fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool {
+        fetch.contains(entity)
+    }
+
+    fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+        fetch.get(entity).expect(MISSING)
+    }
+}
+
+impl<T: 'static> ReadOnlyQueryData for &T {
+    fn get_ref<'f>(fetch: &'f Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+        fetch.get(entity).expect(MISSING)
+    }
+}
+
+impl<T: 'static> QueryData for &mut T {
+    type Fetch<'w> = RefMut<'w, SparseSet<T>>;
+    type Item<'f> = &'f mut T;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage_mut::<T>()
+    }
+
+    fn candidates<'f>(fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+        Some(fetch.entities())
+    }
+
+    fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool {
+        fetch.contains(entity)
+    }
+
+    fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+        fetch.get_mut(entity).expect(MISSING)
+    }
+}
+
+impl<T: 'static> QueryData for Option<&T> {
+    type Fetch<'w> = Option<Ref<'w, SparseSet<T>>>;
+    type Item<'f> = Option<&'f T>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        Some(world.storage::<T>())
+    }
+
+    fn candidates<'f>(_fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+        None
+    }
+
+    fn matches(_fetch: &Self::Fetch<'_>, _entity: Entity) -> bool {
+        true
+    }
+
+    fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+        fetch.as_ref().and_then(|storage| storage.get(entity))
+    }
+}
+
+impl<T: 'static> ReadOnlyQueryData for Option<&T> {
+    fn get_ref<'f>(fetch: &'f Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+        fetch.as_ref().and_then(|storage| storage.get(entity))
+    }
+}
+
+impl<T: 'static> QueryData for Option<&mut T> {
+    type Fetch<'w> = Option<RefMut<'w, SparseSet<T>>>;
+    type Item<'f> = Option<&'f mut T>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        Some(world.storage_mut::<T>())
+    }
+
+    fn candidates<'f>(_fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+        None
+    }
+
+    fn matches(_fetch: &Self::Fetch<'_>, _entity: Entity) -> bool {
+        true
+    }
+
+    fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+        fetch.as_mut().and_then(|storage| storage.get_mut(entity))
+    }
+}
+
+impl QueryData for Entity {
+    type Fetch<'w> = ();
+    type Item<'f> = Entity;
+
+    fn fetch(_world: &World) -> Option<Self::Fetch<'_>> {
+        Some(())
+    }
+
+    fn candidates<'f>(_fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+        None
+    }
+
+    fn matches(_fetch: &Self::Fetch<'_>, _entity: Entity) -> bool {
+        true
+    }
+
+    fn get<'f>(_fetch: &'f mut Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+        entity
+    }
+}
+
+impl ReadOnlyQueryData for Entity {
+    fn get_ref<'f>(_fetch: &'f Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+        entity
+    }
+}
+
+impl QueryFilter for () {
+    type Fetch<'w> = ();
+
+    fn fetch(_world: &World) -> Option<Self::Fetch<'_>> {
+        Some(())
+    }
+
+    fn candidates<'f>(_fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+        None
+    }
+
+    fn matches(_fetch: &Self::Fetch<'_>, _entity: Entity) -> bool {
+        true
+    }
+}
+
+impl<T: 'static> QueryFilter for With<T> {
+    type Fetch<'w> = Ref<'w, SparseSet<T>>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        world.storage::<T>()
+    }
+
+    fn candidates<'f>(fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+        Some(fetch.entities())
+    }
+
+    fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool {
+        fetch.contains(entity)
+    }
+}
+
+impl<T: 'static> QueryFilter for Without<T> {
+    type Fetch<'w> = Option<Ref<'w, SparseSet<T>>>;
+
+    fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+        Some(world.storage::<T>())
+    }
+
+    fn candidates<'f>(_fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+        None
+    }
+
+    fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool {
+        !fetch
+            .as_ref()
+            .is_some_and(|storage| storage.contains(entity))
+    }
+}
+
+fn smallest<const N: usize>(candidates: [Option<&[Entity]>; N]) -> Option<&[Entity]> {
+    candidates
+        .into_iter()
+        .flatten()
+        .min_by_key(|entities| entities.len())
+}
+
+macro_rules! impl_tuples {
+    ($($name:ident $var:ident),+) => {
+        impl<$($name: QueryData),+> QueryData for ($($name,)+) {
+            type Fetch<'w> = ($($name::Fetch<'w>,)+);
+            type Item<'f> = ($($name::Item<'f>,)+);
+
+            fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+                Some(($($name::fetch(world)?,)+))
+            }
+
+            fn candidates<'f>(fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+                let ($($var,)+) = fetch;
+                smallest([$($name::candidates($var)),+])
+            }
+
+            fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool {
+                let ($($var,)+) = fetch;
+                $($name::matches($var, entity))&&+
+            }
+
+            fn get<'f>(fetch: &'f mut Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+                let ($($var,)+) = fetch;
+                ($($name::get($var, entity),)+)
+            }
+        }
+
+        impl<$($name: ReadOnlyQueryData),+> ReadOnlyQueryData for ($($name,)+) {
+            fn get_ref<'f>(fetch: &'f Self::Fetch<'_>, entity: Entity) -> Self::Item<'f> {
+                let ($($var,)+) = fetch;
+                ($($name::get_ref($var, entity),)+)
+            }
+        }
+
+        impl<$($name: QueryFilter),+> QueryFilter for ($($name,)+) {
+            type Fetch<'w> = ($($name::Fetch<'w>,)+);
+
+            fn fetch(world: &World) -> Option<Self::Fetch<'_>> {
+                Some(($($name::fetch(world)?,)+))
+            }
+
+            fn candidates<'f>(fetch: &'f Self::Fetch<'_>) -> Option<&'f [Entity]> {
+                let ($($var,)+) = fetch;
+                smallest([$($name::candidates($var)),+])
+            }
+
+            fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool {
+                let ($($var,)+) = fetch;
+                $($name::matches($var, entity))&&+
+            }
+        }
+    };
+}
+
+impl_tuples!(A a);
+impl_tuples!(A a, B b);
+impl_tuples!(A a, B b, C c);
+impl_tuples!(A a, B b, C c, D d);
+impl_tuples!(A a, B b, C c, D d, E e);
+impl_tuples!(A a, B b, C c, D d, E e, F f);
+impl_tuples!(A a, B b, C c, D d, E e, F f, G g);
+impl_tuples!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+/// Borrowed components of every entity matching `Q` and `F`, from [`World::query`].
+///
+/// The entities are collected when the query is made, so the query holds borrows of the
+/// component storages until it's dropped.
+pub struct Query<'w, Q: QueryData, F: QueryFilter = ()> {
+    world: &'w World,
+    fetch: Option<(Q::Fetch<'w>, F::Fetch<'w>)>,
+    entities: Vec<Entity>,
+}
+
+impl<'w, Q: QueryData, F: QueryFilter> Query<'w, Q, F> {
+    pub(crate) fn new(world: &'w World) -> Self {
+        let fetch = Q::fetch(world).zip(F::fetch(world));
+
+        let entities = match &fetch {
+            Some((data, filter)) => smallest([Q::candidates(data), F::candidates(filter)])
+                .unwrap_or(world.entities())
+                .iter()
+                .copied()
+                .filter(|&entity| Q::matches(data, entity) && F::matches(filter, entity))
+                .collect(),
+
+            None => Vec::new(),
+        };
+
+        Self {
+            world,
+            fetch,
+            entities,
+        }
+    }
+
+    /// The matching entities.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.world.is_alive(entity)
+            && self.fetch.as_ref().is_some_and(|(data, filter)| {
+                Q::matches(data, entity) && F::matches(filter, entity)
+            })
+    }
+
+    pub fn get(&mut self, entity: Entity) -> Option<Q::Item<'_>> {
+        if !self.contains(entity) {
+            return None;
+        }
+
+        let (data, _) = self.fetch.as_mut()?;
+        Some(Q::get(data, entity))
+    }
+
+    pub fn for_each(&mut self, mut f: impl FnMut(Q::Item<'_>)) {
+        let Some((data, _)) = self.fetch.as_mut() else {
+            return;
+        };
+
+        for &entity in &self.entities {
+            f(Q::get(data, entity));
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Q::Item<'_>>
+    where
+        Q: ReadOnlyQueryData,
+    {
+        let data = self.fetch.as_ref().map(|(data, _)| data);
+
+        self.entities
+            .iter()
+            .filter_map(move |&entity| data.map(|data| Q::get_ref(data, entity)))
+    }
+}
@@ -0,0 +1,74 @@
+use crate::EngineContext;
+
+/// When a system runs.
+///
+/// The update stages run once per fixed update, in order, around
+/// [`crate::AppHandler::on_update`], which runs between [`Stage::Update`] and
+/// [`Stage::PostUpdate`]. [`Stage::Render`] runs once per frame, right before
+/// [`crate::AppHandler::on_render`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Stage {
+    PreUpdate,
+    Update,
+    PostUpdate,
+    /// Prepares the world for drawing, like moving the camera or picking what's visible.
+    /// Systems don't get the [`crate::renderer::Renderer`], so drawing belongs in
+    /// [`crate::AppHandler::on_render`].
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::PreUpdate,
+        Stage::Update,
+        Stage::PostUpdate,
+        Stage::Render,
+    ];
+}
+
+pub trait System {
+    fn run(&mut self, ctx: &mut EngineContext);
+}
+
+impl<F: FnMut(&mut EngineContext)> System for F {
+    fn run(&mut self, ctx: &mut EngineContext) {
+        self(ctx)
+    }
+}
+
+/// Systems of every stage, in the order they were added.
+#[derive(Default)]
+pub struct Schedule {
+    stages: [Vec<Box<dyn System>>; Stage::ALL.len()],
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_system(&mut self, stage: Stage, system: impl System + 'static) {
+        self.stages[stage as usize].push(Box::new(system));
+    }
+
+    /// Number of systems in `stage`.
+    pub fn len(&self, stage: Stage) -> usize {
+        self.stages[stage as usize].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.iter().all(Vec::is_empty)
+    }
+
+    pub(crate) fn take(&mut self, stage: Stage) -> Vec<Box<dyn System>> {
+        std::mem::take(&mut self.stages[stage as usize])
+    }
+
+    /// Puts back systems from [`Self::take`], before any added while they ran.
+    pub(crate) fn restore(&mut self, stage: Stage, mut systems: Vec<Box<dyn System>>) {
+        let added = std::mem::take(&mut self.stages[stage as usize]);
+        systems.extend(added);
+
+        self.stages[stage as usize] = systems;
+    }
+}
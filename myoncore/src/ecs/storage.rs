@@ -0,0 +1,106 @@
+use std::any::Any;
+
+use super::Entity;
+
+/// Components of one type, packed densely with a sparse lookup by entity index.
+pub struct SparseSet<T> {
+    sparse: Vec<Option<u32>>,
+    entities: Vec<Entity>,
+    dense: Vec<T>,
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            dense: Vec::new(),
+        }
+    }
+}
+
+impl<T> SparseSet<T> {
+    /// Returns the component `entity` had before, if any.
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        if let Some(position) = self.position(entity) {
+            return Some(std::mem::replace(&mut self.dense[position], value));
+        }
+
+        let index = entity.index() as usize;
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, None);
+        }
+
+        self.sparse[index] = Some(self.dense.len() as u32);
+        self.entities.push(entity);
+        self.dense.push(value);
+
+        None
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let position = self.position(entity)?;
+
+        self.sparse[entity.index() as usize] = None;
+        self.entities.swap_remove(position);
+        let value = self.dense.swap_remove(position);
+
+        if let Some(moved) = self.entities.get(position) {
+            self.sparse[moved.index() as usize] = Some(position as u32);
+        }
+
+        Some(value)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.position(entity).map(|position| &self.dense[position])
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.position(entity)
+            .map(|position| &mut self.dense[position])
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.position(entity).is_some()
+    }
+
+    /// Entities with a component, in storage order.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(&self.dense)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.entities.iter().copied().zip(&mut self.dense)
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    fn position(&self, entity: Entity) -> Option<usize> {
+        let position = (*self.sparse.get(entity.index() as usize)?)? as usize;
+
+        // The slot may belong to an older entity with the same index.
+        (self.entities[position] == entity).then_some(position)
+    }
+}
+
+/// A [`SparseSet`] of any type, so the world can remove despawned entities from all of them.
+pub(crate) trait AnyStorage: Any {
+    fn remove_entity(&mut self, entity: Entity);
+}
+
+impl<T: 'static> AnyStorage for SparseSet<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+}
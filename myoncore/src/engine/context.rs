@@ -0,0 +1,255 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::{
+    assets::AssetServer,
+    ecs::{Schedule, Stage, System, World},
+    graphics::PresentSettings,
+    input::{Input, InputEvent, InputRecording},
+    scene::SceneRegistry,
+    shader::ShaderLibrary,
+    utils::FixedTimestep,
+};
+
+use super::{AppHandler, EngineConfig};
+
+/// Asks for a frame in [`crate::RunMode::Reactive`] from anywhere, including
+/// [`AppHandler::on_event`] and other threads. Picked up within a few milliseconds while the
+/// engine is idle.
+#[derive(Clone, Default)]
+pub struct RedrawHandle(Arc<AtomicBool>);
+
+impl RedrawHandle {
+    pub fn request(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn take(&self) -> bool {
+        self.0.swap(false, Ordering::Relaxed)
+    }
+
+    fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.0) > 1
+    }
+}
+
+/// Engine-owned state that apps reach through [`super::AppHandler`] callbacks.
+pub struct EngineContext {
+    pub assets: AssetServer,
+    pub shaders: ShaderLibrary,
+    pub timestep: FixedTimestep,
+    pub input: Input,
+    pub world: World,
+    pub scenes: SceneRegistry,
+    /// Changes are applied to the window surface before the next frame.
+    pub present: PresentSettings,
+    schedule: Schedule,
+    recording: Option<InputRecording>,
+    redraw: RedrawHandle,
+}
+
+impl EngineContext {
+    pub(crate) fn new(config: &EngineConfig) -> Self {
+        let mut assets = AssetServer::new(&config.asset_root);
+
+        if config.hot_reload
+            && let Err(e) = assets.watch()
+        {
+            tracing::error!("Asset hot-reload is disabled: {e:#}");
+        }
+
+        #[allow(unused_mut)]
+        let mut input = Input::new();
+
+        #[cfg(feature = "gilrs")]
+        if config.gamepads {
+            match crate::input::GilrsBackend::new() {
+                Ok(backend) => input.gamepads.set_backend(backend),
+                Err(e) => tracing::error!("Gamepads are disabled: {e:#}"),
+            }
+        }
+
+        #[cfg(not(feature = "gilrs"))]
+        if config.gamepads {
+            tracing::warn!(
+                "Gamepads are disabled: myoncore was built without the `gilrs` feature, \
+                 enable it or turn them off with `EngineConfig::gamepads(false)`"
+            );
+        }
+
+        let mut context = Self {
+            assets,
+            shaders: ShaderLibrary::new(&config.asset_root),
+            timestep: FixedTimestep::new(config.update_rate, config.max_updates_per_frame),
+            input,
+            world: World::new(),
+            scenes: SceneRegistry::new(),
+            present: config.present,
+            schedule: Schedule::new(),
+            recording: None,
+            redraw: RedrawHandle::default(),
+        };
+
+        if config.record_input.is_some() {
+            context.start_recording();
+        }
+
+        context
+    }
+
+    /// Starts recording input, replacing any recording in progress. Buttons that are already
+    /// held are recorded as pressed in the first frame.
+    pub fn start_recording(&mut self) {
+        let mut recording = InputRecording::new(self.timestep.rate());
+        for event in self.input.state_events() {
+            recording.push_event(event);
+        }
+
+        self.recording = Some(recording);
+    }
+
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recording.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Draws another frame even if nothing changed, only needed in [`crate::RunMode::Reactive`].
+    pub fn request_redraw(&mut self) {
+        self.redraw.request();
+    }
+
+    /// A handle for requesting frames from outside of the engine's callbacks.
+    pub fn redraw_handle(&self) -> RedrawHandle {
+        self.redraw.clone()
+    }
+
+    pub(crate) fn take_redraw_request(&mut self) -> bool {
+        self.redraw.take()
+    }
+
+    /// Checks gamepads, hot-reloaded files and redraw requests while no frames are drawn.
+    /// Returns whether any of them needs a new frame.
+    pub(crate) fn poll_idle(&mut self) -> bool {
+        let mut redraw = self.take_redraw_request();
+
+        for event in self.input.gamepads.poll_backend() {
+            self.handle_input_event(InputEvent::Gamepad(event));
+            redraw = true;
+        }
+
+        redraw |= self.poll_assets();
+
+        redraw || self.assets.pending() > 0 || self.assets.has_events()
+    }
+
+    /// Whether anything [`Self::poll_idle`] checks can change without a window event.
+    pub(crate) fn needs_polling(&self) -> bool {
+        self.input.gamepads.has_backend() || self.assets.is_watching() || self.redraw.is_shared()
+    }
+
+    /// Adds a system that runs in `stage` from now on, after the systems already in it.
+    pub fn add_system(&mut self, stage: Stage, system: impl System + 'static) {
+        self.schedule.add_system(stage, system);
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub(crate) fn run_stage(&mut self, stage: Stage) {
+        let mut systems = self.schedule.take(stage);
+        for system in &mut systems {
+            system.run(self);
+        }
+
+        self.schedule.restore(stage, systems);
+    }
+
+    pub(crate) fn handle_input_event(&mut self, event: InputEvent) {
+        self.input.handle_event(&event);
+
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push_event(event);
+        }
+    }
+
+    /// Finishes pending asset loads, then runs as many fixed updates as `frame_time` covers.
+    /// Returns the interpolation alpha for rendering.
+    pub(crate) fn update<A: AppHandler>(&mut self, app: &mut A, frame_time: Duration) -> f32 {
+        for event in self.input.gamepads.poll_backend() {
+            self.handle_input_event(InputEvent::Gamepad(event));
+        }
+
+        self.update_assets(app);
+
+        let steps = self.timestep.advance(frame_time);
+        self.run_updates(app, steps);
+
+        self.timestep.alpha()
+    }
+
+    /// Like [`Self::update`], but takes the input and number of updates from `frame` of
+    /// `recording` instead of the backends and the clock.
+    pub(crate) fn replay<A: AppHandler>(
+        &mut self,
+        app: &mut A,
+        recording: &InputRecording,
+        frame: usize,
+    ) {
+        for event in recording.events_in(frame) {
+            self.handle_input_event(event.clone());
+        }
+
+        self.update_assets(app);
+        self.run_updates(app, recording.updates[frame]);
+    }
+
+    fn update_assets<A: AppHandler>(&mut self, app: &mut A) {
+        self.poll_assets();
+
+        for event in self.assets.take_events() {
+            app.on_asset_event(self, &event);
+        }
+    }
+
+    /// Collects finished loads and changed files. Returns whether any file changed.
+    fn poll_assets(&mut self) -> bool {
+        self.assets.update();
+
+        // Shaders aren't assets, but they live under the same root.
+        let changed = self.assets.take_changed_paths();
+        for path in &changed {
+            self.shaders.invalidate(path);
+        }
+
+        !changed.is_empty()
+    }
+
+    fn run_updates<A: AppHandler>(&mut self, app: &mut A, steps: u32) {
+        let dt = self.timestep.delta_time();
+        for step in 0..steps {
+            self.run_stage(Stage::PreUpdate);
+            self.run_stage(Stage::Update);
+            app.on_update(self, dt);
+            self.run_stage(Stage::PostUpdate);
+
+            // Presses and deltas belong to the first update that sees them, catch-up
+            // updates after it would otherwise act on a single press several times.
+            if step == 0 {
+                self.input.end_frame();
+            }
+        }
+
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push_frame(steps);
+        }
+    }
+}
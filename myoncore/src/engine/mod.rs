@@ -1,26 +1,74 @@
-use egui::Context;
+pub mod context;
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use wgpu::TextureFormat;
 use winit::{
     application::ApplicationHandler,
     dpi::LogicalSize,
     event::WindowEvent,
-    event_loop::ActiveEventLoop,
+    event_loop::{ActiveEventLoop, ControlFlow},
     window::{Window, WindowAttributes},
 };
 
-use std::ptr;
+pub use context::{EngineContext, RedrawHandle};
 
 use crate::{
-    graphics::Graphics, gui::Gui, logger::Logger, renderer::Renderer, utils::FrameTimer,
+    assets::{AssetContext, AssetEvent},
+    ecs::Stage,
+    graphics::{AdapterSettings, Graphics, PresentSettings},
+    gui::Gui,
+    input::{InputEvent, InputRecording},
+    logger::Logger,
+    renderer::{DEPTH_FORMAT, Renderer, camera},
+    scene,
+    utils::{FrameLimiter, FrameTimer, RedrawSchedule},
     window::WindowSystem,
 };
 
-#[derive(Default)]
+/// When the engine draws a new frame.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum RunMode {
+    /// Draw as soon as the previous frame is presented, only held back by vsync.
+    #[default]
+    Continuous,
+    /// Draw at most `fps` frames per second, `fps` must be positive.
+    Capped { fps: f64 },
+    /// Only draw on input, when egui asks for a repaint, or after
+    /// [`EngineContext::request_redraw`]. Gamepads, hot-reloaded files and
+    /// [`RedrawHandle`]s are polled every [`IDLE_POLL_INTERVAL`] while idle. Meant for tools
+    /// that sit idle most of the time.
+    Reactive,
+}
+
+/// How often [`RunMode::Reactive`] checks for activity that can't wake the event loop.
+pub const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
 pub struct EngineConfig {
     title: String,
     width: u32,
     height: u32,
     resizable: bool,
     without_titlebar: bool,
+    asset_root: PathBuf,
+    hot_reload: bool,
+    update_rate: f64,
+    max_updates_per_frame: u32,
+    gamepads: bool,
+    record_input: Option<PathBuf>,
+    depth_format: Option<TextureFormat>,
+    hdr: bool,
+    msaa_samples: u32,
+    present: PresentSettings,
+    run_mode: RunMode,
+    adapter: AdapterSettings,
+    // The defaults of these differ between windowed and headless runs, see
+    // `adapter_settings`.
+    backends: Option<wgpu::Backends>,
+    force_fallback_adapter: Option<bool>,
 }
 
 impl EngineConfig {
@@ -31,6 +79,20 @@ impl EngineConfig {
             height: 0,
             resizable: false,
             without_titlebar: false,
+            asset_root: PathBuf::from("assets"),
+            hot_reload: false,
+            update_rate: 60.0,
+            max_updates_per_frame: 5,
+            gamepads: true,
+            record_input: None,
+            depth_format: Some(DEPTH_FORMAT),
+            hdr: false,
+            msaa_samples: 1,
+            present: PresentSettings::default(),
+            run_mode: RunMode::Continuous,
+            adapter: AdapterSettings::default(),
+            backends: None,
+            force_fallback_adapter: None,
         }
     }
 
@@ -58,29 +120,300 @@ impl EngineConfig {
         self.without_titlebar = without_titlebar;
         self
     }
+
+    /// Directory that [`crate::assets::AssetServer::load`] paths are relative to.
+    pub fn asset_root(mut self, asset_root: impl Into<PathBuf>) -> Self {
+        self.asset_root = asset_root.into();
+        self
+    }
+
+    /// Reload assets automatically when their files change on disk.
+    pub fn hot_reload(mut self, hot_reload: bool) -> Self {
+        self.hot_reload = hot_reload;
+        self
+    }
+
+    /// How many times per second [`AppHandler::on_update`] runs. Rates that aren't positive
+    /// and finite are ignored with a warning.
+    pub fn update_rate(mut self, update_rate: f64) -> Self {
+        if update_rate > 0.0 && update_rate.is_finite() {
+            self.update_rate = update_rate;
+        } else {
+            tracing::warn!("Ignoring update rate {update_rate}, it must be positive and finite");
+        }
+
+        self
+    }
+
+    /// Upper bound on catch-up updates after a slow frame, the rest of the time is dropped.
+    pub fn max_updates_per_frame(mut self, max_updates_per_frame: u32) -> Self {
+        self.max_updates_per_frame = max_updates_per_frame;
+        self
+    }
+
+    /// Read real gamepads. Needs the `gilrs` feature, a warning is logged at startup if it's
+    /// enabled without it.
+    pub fn gamepads(mut self, gamepads: bool) -> Self {
+        self.gamepads = gamepads;
+        self
+    }
+
+    /// Record all input from startup and save it to `path` when the event loop exits,
+    /// for [`Engine::replay`].
+    pub fn record_input(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record_input = Some(path.into());
+        self
+    }
+
+    /// Format of the depth/stencil texture behind [`Renderer::depth_texture_view`], or
+    /// `None` for no depth texture.
+    pub fn depth_format(mut self, depth_format: Option<TextureFormat>) -> Self {
+        self.depth_format = depth_format;
+        self
+    }
+
+    /// Draw the scene into an HDR target and resolve it with [`Renderer::post_process`].
+    pub fn hdr(mut self, hdr: bool) -> Self {
+        self.hdr = hdr;
+        self
+    }
+
+    /// Samples per pixel of the scene, see [`Renderer::msaa_samples`]. Pipelines drawing
+    /// into [`Renderer::texture_view`] need [`Renderer::sample_count`].
+    pub fn msaa_samples(mut self, msaa_samples: u32) -> Self {
+        self.msaa_samples = msaa_samples;
+        self
+    }
+
+    /// Wait for the display to refresh. Ignored if a supported [`Self::present_mode`] is set.
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.present.vsync = vsync;
+        self
+    }
+
+    /// Present mode to use when the surface supports it, [`Self::vsync`] picks one otherwise.
+    pub fn present_mode(mut self, present_mode: wgpu::PresentMode) -> Self {
+        self.present.present_mode = Some(present_mode);
+        self
+    }
+
+    pub fn alpha_mode(mut self, alpha_mode: wgpu::CompositeAlphaMode) -> Self {
+        self.present.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// Frames the GPU may queue before rendering blocks.
+    pub fn frame_latency(mut self, frame_latency: u32) -> Self {
+        self.present.frame_latency = frame_latency;
+        self
+    }
+
+    pub fn run_mode(mut self, run_mode: RunMode) -> Self {
+        self.run_mode = run_mode;
+        self
+    }
+
+    /// Backends to pick an adapter from, [`wgpu::Backends::PRIMARY`] by default, or all of
+    /// them when headless. Add [`wgpu::Backends::GL`] to allow OpenGL/GLES.
+    pub fn backends(mut self, backends: wgpu::Backends) -> Self {
+        self.backends = Some(backends);
+        self
+    }
+
+    pub fn power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.adapter.power_preference = power_preference;
+        self
+    }
+
+    /// Prefer a software adapter, the default when headless.
+    pub fn force_fallback_adapter(mut self, force_fallback_adapter: bool) -> Self {
+        self.force_fallback_adapter = Some(force_fallback_adapter);
+        self
+    }
+
+    /// Use the adapter with this name and backend from [`crate::graphics::list_adapters`]
+    /// if it's there.
+    pub fn preferred_adapter(mut self, name: impl Into<String>, backend: wgpu::Backend) -> Self {
+        self.adapter.preferred_adapter = Some((name.into(), backend));
+        self
+    }
+
+    /// Features the device must have, startup fails without them.
+    pub fn required_features(mut self, required_features: wgpu::Features) -> Self {
+        self.adapter.required_features = required_features;
+        self
+    }
+
+    /// Features to enable when the adapter has them, see [`Graphics::features`].
+    pub fn optional_features(mut self, optional_features: wgpu::Features) -> Self {
+        self.adapter.optional_features = optional_features;
+        self
+    }
+
+    /// Limits the device must support. By default, the best of wgpu's default limits
+    /// that the adapter supports.
+    pub fn limits(mut self, limits: wgpu::Limits) -> Self {
+        self.adapter.limits = Some(limits);
+        self
+    }
+
+    /// The adapter settings for a windowed or headless run, with everything that was set
+    /// on top of that run's defaults.
+    pub fn adapter_settings(&self, headless: bool) -> AdapterSettings {
+        let defaults = if headless {
+            AdapterSettings::headless()
+        } else {
+            AdapterSettings::default()
+        };
+
+        AdapterSettings {
+            backends: self.backends.unwrap_or(defaults.backends),
+            force_fallback_adapter: self
+                .force_fallback_adapter
+                .unwrap_or(defaults.force_fallback_adapter),
+            ..self.adapter.clone()
+        }
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub trait AppHandler {
     fn on_event(&mut self, event_loop: &ActiveEventLoop, event: &WindowEvent);
-    fn on_update(&mut self);
-    fn on_render(&mut self, renderer: &mut Renderer);
+    fn on_update(&mut self, ctx: &mut EngineContext, dt: f32);
+    fn on_render(&mut self, ctx: &mut EngineContext, renderer: &mut Renderer, alpha: f32);
+    fn on_asset_event(&mut self, _ctx: &mut EngineContext, _event: &AssetEvent) {}
     fn on_gui(
         &mut self,
-        ctx: &mut Context,
+        gui: &mut Gui,
         frametimer: &FrameTimer,
-        window: &Window,
-        event_loop: &ActiveEventLoop,
+        window: Option<&Window>,
+        event_loop: Option<&ActiveEventLoop>,
     );
 }
 
+// Fields are dropped in declaration order, so everything that borrows GPU
+// objects goes away before the device, and the surface before its window.
+struct EngineState {
+    gui: Gui,
+    renderer: Renderer,
+    graphics: Graphics,
+    windowsys: Option<WindowSystem>,
+}
+
+impl EngineState {
+    fn new(config: &EngineConfig, event_loop: &ActiveEventLoop) -> Self {
+        let window_attributes = WindowAttributes::default()
+            .with_title(&config.title)
+            .with_inner_size(LogicalSize::new(config.width, config.height))
+            .with_resizable(config.resizable)
+            .with_decorations(!config.without_titlebar);
+
+        let windowsys = WindowSystem::new(window_attributes, event_loop);
+        tracing::info!("Window created!");
+
+        let mut graphics = Graphics::new(windowsys.window.clone(), &config.adapter_settings(false));
+        graphics.set_present_settings(config.present);
+
+        let size = windowsys.window.inner_size();
+        graphics.configure(size.width, size.height);
+        tracing::info!("Graphics API created!");
+
+        let renderer = Renderer::new(&graphics, config.depth_format)
+            .hdr(config.hdr)
+            .msaa_samples(config.msaa_samples);
+        tracing::info!("Renderer created!");
+
+        let gui = Gui::new(windowsys.window.clone(), &graphics);
+        tracing::info!("Created GUI!");
+
+        Self {
+            gui,
+            renderer,
+            graphics,
+            windowsys: Some(windowsys),
+        }
+    }
+
+    fn new_headless(config: &EngineConfig) -> Self {
+        let graphics = Graphics::new_headless_with(
+            config.width,
+            config.height,
+            &config.adapter_settings(true),
+        );
+        tracing::info!("Headless Graphics API created!");
+
+        let renderer = Renderer::new(&graphics, config.depth_format)
+            .hdr(config.hdr)
+            .msaa_samples(config.msaa_samples);
+        tracing::info!("Renderer created!");
+
+        let gui = Gui::new_headless(&graphics);
+        tracing::info!("Created GUI!");
+
+        Self {
+            gui,
+            renderer,
+            graphics,
+            windowsys: None,
+        }
+    }
+
+    fn window(&self) -> Option<&Window> {
+        self.windowsys
+            .as_ref()
+            .map(|windowsys| windowsys.window.as_ref())
+    }
+
+    fn render_frame<A: AppHandler>(
+        &mut self,
+        app: &mut A,
+        ctx: &mut EngineContext,
+        frame_timer: &FrameTimer,
+        event_loop: Option<&ActiveEventLoop>,
+        alpha: f32,
+    ) -> Result<(), wgpu::SurfaceError> {
+        self.graphics.set_present_settings(ctx.present);
+        self.renderer.begin_frame(&self.graphics)?;
+
+        ctx.run_stage(Stage::Render);
+        scene::propagate_transforms(&mut ctx.world);
+        if let Some((_, camera, transform)) = camera::active_camera(&ctx.world) {
+            self.renderer.set_camera(&camera, &transform);
+        }
+        app.on_render(ctx, &mut self.renderer, alpha);
+
+        self.gui.begin_frame(&self.graphics);
+
+        let window = self
+            .windowsys
+            .as_ref()
+            .map(|windowsys| windowsys.window.as_ref());
+        app.on_gui(&mut self.gui, frame_timer, window, event_loop);
+
+        self.gui.end_frame(&self.graphics, &mut self.renderer);
+
+        if let Err(e) = self.renderer.execute_graph() {
+            tracing::error!("{e:#}");
+        }
+        self.renderer.end_frame();
+
+        Ok(())
+    }
+}
+
 pub struct Engine<A: AppHandler> {
     config: EngineConfig,
     frame_timer: FrameTimer,
-    logger: Logger,
-    windowsys: *mut WindowSystem,
-    graphics: *mut Graphics,
-    renderer: *mut Renderer,
-    gui: *mut Gui,
+    limiter: Option<FrameLimiter>,
+    redraw_schedule: RedrawSchedule,
+    _logger: Logger,
+    context: EngineContext,
+    state: Option<EngineState>,
     app: A,
 }
 
@@ -88,161 +421,266 @@ impl<A: AppHandler> Engine<A> {
     pub fn new(config: EngineConfig, app: A) -> Self {
         let frame_timer = FrameTimer::new();
         let logger = Logger::new();
+        let context = EngineContext::new(&config);
+        let limiter = match config.run_mode {
+            RunMode::Capped { fps } => Some(FrameLimiter::new(fps)),
+            _ => None,
+        };
 
         Self {
-	    config,
-	    frame_timer,
-	    logger,
-	    windowsys: ptr::null_mut(),
-	    graphics: ptr::null_mut(),
-	    renderer: ptr::null_mut(),
-	    gui: ptr::null_mut(),
-	    app,
+            config,
+            frame_timer,
+            limiter,
+            redraw_schedule: RedrawSchedule::new(),
+            _logger: logger,
+            context,
+            state: None,
+            app,
         }
     }
 
-    pub unsafe fn unsafe_resumed(&mut self, event_loop: &ActiveEventLoop) {
-	unsafe {
-            let window_attributes = WindowAttributes::default()
-		.with_title(&self.config.title)
-		.with_inner_size(LogicalSize::new(self.config.width, self.config.height))
-		.with_resizable(self.config.resizable)
-		.with_decorations(!self.config.without_titlebar);
+    pub fn app(&self) -> &A {
+        &self.app
+    }
 
-            let windowsys_box = Box::new(WindowSystem::new(window_attributes, event_loop));
-	    self.windowsys = Box::into_raw(windowsys_box) as *mut WindowSystem;
-            tracing::info!("Window created!");
+    pub fn app_mut(&mut self) -> &mut A {
+        &mut self.app
+    }
 
-            let window = &(*self.windowsys)
-		.window as *const Window;
+    pub fn context(&mut self) -> &mut EngineContext {
+        &mut self.context
+    }
 
-            let mut graphics = Graphics::new(window);
+    pub fn renderer(&mut self) -> Option<&mut Renderer> {
+        self.state.as_mut().map(|state| &mut state.renderer)
+    }
 
-            let size = (*window).inner_size();
-            let width = size.width;
-            let height = size.height;
+    /// Renders `frames` frames offscreen. Every frame advances time by exactly one update
+    /// step, so the result doesn't depend on how fast the machine is.
+    pub fn run_headless(&mut self, frames: u32) {
+        self.create_headless_state();
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
 
-            graphics.configure(width, height);
+        for _ in 0..frames {
+            self.frame_timer.update();
 
-	    let graphics_box = Box::new(graphics);
-	    self.graphics = Box::into_raw(graphics_box) as *mut Graphics;
-            tracing::info!("Graphics API created!");
+            let step = self.context.timestep.step();
+            let alpha = self.context.update(&mut self.app, step);
+
+            state
+                .render_frame(
+                    &mut self.app,
+                    &mut self.context,
+                    &self.frame_timer,
+                    None,
+                    alpha,
+                )
+                .expect("Offscreen rendering can't lose its surface");
+        }
+    }
+
+    /// Renders every frame of `recording` offscreen, feeding it the recorded input and running
+    /// as many updates per frame as the recorded session did. Real gamepads are ignored.
+    pub fn replay(&mut self, recording: &InputRecording) {
+        self.context.timestep.set_rate(recording.update_rate);
+        self.context.timestep.reset();
 
-            let renderer_box = Box::new(Renderer::new(self.graphics));
-	    self.renderer = Box::into_raw(renderer_box) as *mut Renderer;
-            tracing::info!("Renderer created!");
+        self.create_headless_state();
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+
+        for frame in 0..recording.frames() {
+            self.frame_timer.update();
 
-            let gui_box = Box::new(Gui::new(
-		window,
-		self.graphics,
-		self.renderer,
-            ));
-	    self.gui = Box::into_raw(gui_box) as *mut Gui;
-            tracing::info!("Created GUI!");
+            self.context.replay(&mut self.app, recording, frame);
+
+            state
+                .render_frame(
+                    &mut self.app,
+                    &mut self.context,
+                    &self.frame_timer,
+                    None,
+                    0.0,
+                )
+                .expect("Offscreen rendering can't lose its surface");
+        }
+    }
 
-            self.app.on_update();
-	}
+    fn create_headless_state(&mut self) {
+        if self.state.is_none() {
+            let state = EngineState::new_headless(&self.config);
+            self.attach(state);
+        }
     }
 
-    pub unsafe fn unsafe_window_event(
+    fn attach(&mut self, state: EngineState) {
+        self.context.assets.attach(AssetContext::new(
+            &state.graphics.device,
+            &state.graphics.queue,
+        ));
+        self.context.shaders.attach(&state.graphics.device);
+
+        self.state = Some(state);
+    }
+}
+
+impl<A: AppHandler> ApplicationHandler for Engine<A> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.state.is_some() {
+            return;
+        }
+
+        let state = EngineState::new(&self.config, event_loop);
+        self.attach(state);
+
+        self.frame_timer.update();
+    }
+
+    fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
         _id: winit::window::WindowId,
         event: WindowEvent,
     ) {
-	unsafe {
-            let windowsys = &mut (*self.windowsys);
-            let graphics = &mut (*self.graphics);
-            let renderer = &mut (*self.renderer);
-            let gui = &mut (*self.gui);
-
-	    let window = &(*windowsys).window
-		as *const Window;
+        let Some(state) = self.state.as_mut() else {
+            return;
+        };
+
+        let response = state.gui.handle_event(&event);
+        let mut redraw = response.repaint;
+        if let Some(input_event) = InputEvent::from_window_event(&event)
+            && !(response.consumed && input_event.is_press())
+        {
+            self.context.handle_input_event(input_event);
+            redraw = true;
+        }
 
-            self.frame_timer.update();
+        if redraw
+            && self.config.run_mode == RunMode::Reactive
+            && let Some(window) = state.window()
+        {
+            window.request_redraw();
+        }
 
-            gui.handle_event(&event);
+        match event {
+            WindowEvent::CloseRequested => {
+                tracing::info!("Closing...");
+                event_loop.exit();
+            }
 
-            match event {
-		WindowEvent::CloseRequested => {
-                    tracing::info!("Closing...");
-                    event_loop.exit();
-		}
+            WindowEvent::RedrawRequested => {
+                self.frame_timer.update();
+                let alpha = self
+                    .context
+                    .update(&mut self.app, self.frame_timer.frame_time());
+
+                match state.render_frame(
+                    &mut self.app,
+                    &mut self.context,
+                    &self.frame_timer,
+                    Some(event_loop),
+                    alpha,
+                ) {
+                    Ok(_) => {}
+
+                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                        if let Some(window) = state.window() {
+                            let size = window.inner_size();
+                            state.graphics.resize(size.width, size.height);
+                        }
+                    }
 
-		WindowEvent::RedrawRequested => {
-                    match renderer.begin_frame() {
-			Ok(_) => {}
+                    Err(e) => {
+                        panic!("Unable to render, reason: {e}")
+                    }
+                }
 
-			Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
-                            let size = windowsys.window.inner_size();
+                // Reactive mode schedules its next frame in `about_to_wait` instead.
+                let redraw_now = match self.config.run_mode {
+                    RunMode::Continuous => true,
 
-                            graphics.resize(size.width, size.height);
+                    RunMode::Capped { .. } => {
+                        if let Some(limiter) = self.limiter.as_mut() {
+                            limiter.wait();
+                        }
 
-                            windowsys.window.request_redraw();
-			}
+                        true
+                    }
 
-			Err(e) => {
-                            panic!("Unable to render, reason: {e}")
-			}
+                    RunMode::Reactive => {
+                        let delay = if self.context.take_redraw_request()
+                            || self.context.assets.pending() > 0
+                        {
+                            Duration::ZERO
+                        } else {
+                            state.gui.repaint_delay()
+                        };
+
+                        self.redraw_schedule.after_frame(Instant::now(), delay);
+                        false
                     }
+                };
 
-                    self.app.on_render(renderer);
+                if redraw_now && let Some(window) = state.window() {
+                    window.request_redraw();
+                }
+            }
 
-                    gui.begin_frame();
+            WindowEvent::Resized(size) => {
+                state.graphics.resize(size.width, size.height);
 
-                    self.app.on_gui(
-			&mut gui.ctx,
-			&self.frame_timer,
-			&*window,
-			event_loop,
-                    );
+                if let Some(window) = state.window() {
+                    window.request_redraw();
+                }
+            }
 
-                    gui.end_frame();
-                    renderer.end_frame();
+            _ => {}
+        }
 
-                    windowsys.window.request_redraw();
-		}
+        self.app.on_event(event_loop, &event);
+    }
 
-		WindowEvent::Resized(size) => {
-                    graphics.resize(size.width, size.height);
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.config.run_mode != RunMode::Reactive {
+            return;
+        }
 
-                    windowsys.window.request_redraw();
-		}
+        let Some(window) = self.state.as_ref().and_then(|state| state.window()) else {
+            return;
+        };
 
-		_ => {}
-            }
+        let now = Instant::now();
+        if self.context.poll_idle() {
+            self.redraw_schedule.redraw_after(now, Duration::ZERO);
+        }
 
-            self.app.on_event(event_loop, &event);
-	}
-    }
-}
+        if self.redraw_schedule.take_due(now) {
+            window.request_redraw();
+            event_loop.set_control_flow(ControlFlow::Wait);
+            return;
+        }
 
-impl<A: AppHandler> ApplicationHandler for Engine<A> {
-    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-	unsafe {
-	    self.unsafe_resumed(event_loop);
-	}
-    }
+        let poll = self.context.needs_polling().then_some(IDLE_POLL_INTERVAL);
 
-    fn window_event(
-        &mut self,
-        event_loop: &ActiveEventLoop,
-        id: winit::window::WindowId,
-        event: WindowEvent,
-    ) {
-	unsafe {
-	    self.unsafe_window_event(event_loop, id, event);
-	}
+        match self.redraw_schedule.wake_at(now, poll) {
+            Some(at) => event_loop.set_control_flow(ControlFlow::WaitUntil(at)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
+        }
     }
-}
 
-impl<A: AppHandler> Drop for Engine<A> {
-    fn drop(&mut self) {
-	unsafe {
-	    let _ = Box::from_raw(self.windowsys);
-	    let _ = Box::from_raw(self.graphics);
-	    let _ = Box::from_raw(self.renderer);
-	    let _ = Box::from_raw(self.gui);
-	}
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        let Some(path) = self.config.record_input.as_ref() else {
+            return;
+        };
+
+        if let Some(recording) = self.context.stop_recording() {
+            match recording.save(path) {
+                Ok(()) => tracing::info!("Saved input recording to {}", path.display()),
+                Err(e) => tracing::error!("{e:#}"),
+            }
+        }
     }
 }
@@ -0,0 +1,148 @@
+use wgpu::{
+    Adapter, AdapterInfo, Backend, Backends, Features, Instance, Limits, PowerPreference, Surface,
+};
+
+/// Which GPU to run on and what to ask of it, set through [`crate::EngineConfig`].
+#[derive(Clone, Debug)]
+pub struct AdapterSettings {
+    /// Backends to look for adapters on, add [`Backends::GL`] for OpenGL/GLES.
+    pub backends: Backends,
+    pub power_preference: PowerPreference,
+    /// Use a software adapter, falling back to a hardware one if there's none.
+    pub force_fallback_adapter: bool,
+    /// Name and backend of the adapter to use, as listed by [`list_adapters`]. The same GPU
+    /// usually shows up once per backend. Falls back to the usual selection if it isn't found.
+    pub preferred_adapter: Option<(String, Backend)>,
+    /// Device creation fails if the adapter lacks any of these.
+    pub required_features: Features,
+    /// Enabled when the adapter supports them, check [`crate::graphics::Graphics::features`].
+    pub optional_features: Features,
+    /// Limits the device must support, `None` for the best of [`Limits::default`],
+    /// [`Limits::downlevel_defaults`] and [`Limits::downlevel_webgl2_defaults`] that the
+    /// adapter supports, so GL adapters work out of the box.
+    pub limits: Option<Limits>,
+}
+
+impl Default for AdapterSettings {
+    fn default() -> Self {
+        Self {
+            backends: Backends::PRIMARY,
+            power_preference: PowerPreference::default(),
+            force_fallback_adapter: false,
+            preferred_adapter: None,
+            required_features: Features::empty(),
+            optional_features: Features::empty(),
+            limits: None,
+        }
+    }
+}
+
+impl AdapterSettings {
+    /// What headless graphics use by default: any backend, preferring a software adapter.
+    pub fn headless() -> Self {
+        Self {
+            backends: Backends::all(),
+            force_fallback_adapter: true,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn create_instance(&self) -> Instance {
+        Instance::new(&wgpu::InstanceDescriptor {
+            backends: self.backends,
+            ..Default::default()
+        })
+    }
+
+    pub(crate) fn request_adapter(
+        &self,
+        instance: &Instance,
+        surface: Option<&Surface<'static>>,
+    ) -> Adapter {
+        if let Some((name, backend)) = self.preferred_adapter.as_ref() {
+            let adapter = instance
+                .enumerate_adapters(self.backends)
+                .into_iter()
+                .filter(|adapter| surface.is_none_or(|s| adapter.is_surface_supported(s)))
+                .find(|adapter| {
+                    let info = adapter.get_info();
+                    info.name == *name && info.backend == *backend
+                });
+
+            if let Some(adapter) = adapter {
+                return adapter;
+            }
+
+            tracing::warn!("Adapter {name:?} ({backend}) isn't available, picking another one");
+        }
+
+        let options = wgpu::RequestAdapterOptions {
+            power_preference: self.power_preference,
+            compatible_surface: surface,
+            force_fallback_adapter: self.force_fallback_adapter,
+        };
+
+        pollster::block_on(instance.request_adapter(&options))
+            .or_else(|e| {
+                if !self.force_fallback_adapter {
+                    return Err(e);
+                }
+
+                tracing::warn!("No fallback adapter available ({e}), using default adapter");
+
+                pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                    force_fallback_adapter: false,
+                    ..options
+                }))
+            })
+            .expect("Failed to request adapter!")
+    }
+
+    /// The required features plus whichever optional ones `adapter` supports.
+    pub fn features_for(&self, adapter: &Adapter) -> Features {
+        let optional = self.optional_features & adapter.features();
+
+        if optional != self.optional_features {
+            tracing::debug!(
+                "Optional features not supported: {:?}",
+                self.optional_features - optional
+            );
+        }
+
+        self.required_features | optional
+    }
+
+    /// [`Self::limits`], or the best default limits `adapter` supports.
+    pub fn limits_for(&self, adapter: &Adapter) -> Limits {
+        if let Some(limits) = self.limits.as_ref() {
+            return limits.clone();
+        }
+
+        let supported = adapter.limits();
+
+        [
+            Limits::default(),
+            Limits::downlevel_defaults(),
+            Limits::downlevel_webgl2_defaults(),
+        ]
+        .into_iter()
+        .find(|limits| limits.check_limits(&supported))
+        .unwrap_or_else(Limits::downlevel_webgl2_defaults)
+        .using_resolution(supported)
+    }
+}
+
+/// Every adapter on `backends`, for letting the user pick a GPU.
+pub fn list_adapters(backends: Backends) -> Vec<AdapterInfo> {
+    let instance = AdapterSettings {
+        backends,
+        ..Default::default()
+    }
+    .create_instance();
+
+    instance
+        .enumerate_adapters(backends)
+        .iter()
+        .map(Adapter::get_info)
+        .collect()
+}
@@ -1,25 +1,42 @@
+pub mod adapter;
+pub mod present;
+
+use std::sync::Arc;
+
 use wgpu::{
-    Adapter, Device, Instance, Queue, Surface, SurfaceCapabilities, SurfaceConfiguration,
-    TextureFormat,
+    Adapter, Device, Features, Instance, Queue, Surface, SurfaceCapabilities, SurfaceConfiguration,
+    Texture, TextureFormat,
 };
 use winit::window::Window;
 
+pub use adapter::{AdapterSettings, list_adapters};
+pub use present::PresentSettings;
+
+pub const OFFSCREEN_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
 pub struct Graphics {
     pub instance: Instance,
-    pub surface: Surface<'static>,
+    pub surface: Option<Surface<'static>>,
     pub adapter: Adapter,
     pub device: Device,
     pub queue: Queue,
     pub surface_format: Option<TextureFormat>,
+    pub offscreen_texture: Option<Texture>,
     surface_caps: Option<SurfaceCapabilities>,
     surface_config: Option<SurfaceConfiguration>,
+    present: PresentSettings,
 }
 
 impl Graphics {
     pub fn configure(&mut self, width: u32, height: u32) {
+        let Some(surface) = self.surface.as_ref() else {
+            self.resize(width, height);
+            return;
+        };
+
         tracing::info!("Configuring surface...");
 
-        let surface_caps = self.surface.get_capabilities(&self.adapter);
+        let surface_caps = surface.get_capabilities(&self.adapter);
 
         let surface_format = surface_caps
             .formats
@@ -28,22 +45,33 @@ impl Graphics {
             .find(|f| f.is_srgb())
             .unwrap_or(surface_caps.formats[0]);
 
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC);
+
         let config = SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width,
             height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            desired_maximum_frame_latency: 2,
+            present_mode: self
+                .present
+                .choose_present_mode(&surface_caps.present_modes),
+            alpha_mode: self.present.choose_alpha_mode(&surface_caps.alpha_modes),
+            desired_maximum_frame_latency: self.present.frame_latency.max(1),
             view_formats: vec![],
         };
 
+        tracing::debug!(
+            "Presenting with {:?}, alpha mode {:?}",
+            config.present_mode,
+            config.alpha_mode
+        );
+
         self.surface_caps = Some(surface_caps);
         self.surface_format = Some(surface_format);
         self.surface_config = Some(config);
 
-        self.surface.configure(
+        surface.configure(
             &self.device,
             self.surface_config
                 .as_ref()
@@ -52,6 +80,11 @@ impl Graphics {
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
+        let Some(surface) = self.surface.as_ref() else {
+            self.offscreen_texture = Some(self.create_offscreen_texture(width, height));
+            return;
+        };
+
         let surface_config = self
             .surface_config
             .as_mut()
@@ -60,7 +93,7 @@ impl Graphics {
         surface_config.width = width;
         surface_config.height = height;
 
-        self.surface.configure(
+        surface.configure(
             &self.device,
             self.surface_config
                 .as_ref()
@@ -68,59 +101,156 @@ impl Graphics {
         );
     }
 
-    pub unsafe fn new(window: *const Window) -> Self {
-	unsafe {
-            tracing::info!("Creating WebGPU backend...");
+    pub fn present_settings(&self) -> &PresentSettings {
+        &self.present
+    }
 
-            tracing::debug!("Creating Instance...");
+    /// Reconfigures the surface if `present` differs from the current settings.
+    pub fn set_present_settings(&mut self, present: PresentSettings) {
+        if self.present == present {
+            return;
+        }
 
-            let instancedescriptor = wgpu::InstanceDescriptor {
-		backends: wgpu::Backends::PRIMARY,
-		..Default::default()
-            };
+        self.present = present;
 
-            let instance = Instance::new(&instancedescriptor);
+        if let Some((width, height)) = self.surface_config.as_ref().map(|c| (c.width, c.height)) {
+            self.configure(width, height);
+        }
+    }
 
-            tracing::debug!("Creating surface...");
+    /// The present mode the surface was configured with, which may differ from the
+    /// requested one. `None` when headless.
+    pub fn present_mode(&self) -> Option<wgpu::PresentMode> {
+        self.surface_config
+            .as_ref()
+            .map(|config| config.present_mode)
+    }
 
-            let surface = instance
-		.create_surface(&*window)
-		.expect("Failed to create surface!");
+    pub fn size(&self) -> (u32, u32) {
+        if let Some(config) = self.surface_config.as_ref() {
+            return (config.width, config.height);
+        }
 
-            tracing::debug!("Requesting adapter...");
+        self.offscreen_texture
+            .as_ref()
+            .map(|texture| (texture.width(), texture.height()))
+            .unwrap_or((0, 0))
+    }
 
-            let request_adapter_options = wgpu::RequestAdapterOptions {
-		power_preference: wgpu::PowerPreference::default(),
-		compatible_surface: Some(&surface),
-		force_fallback_adapter: false,
-            };
+    pub fn is_headless(&self) -> bool {
+        self.surface.is_none()
+    }
 
-            let adapter = pollster::block_on(instance.request_adapter(&request_adapter_options))
-		.expect("Failed to request adapter!");
+    /// The features the device was created with, including the supported optional ones.
+    pub fn features(&self) -> Features {
+        self.device.features()
+    }
 
-            tracing::debug!("Creating device...");
+    fn create_offscreen_texture(&self, width: u32, height: u32) -> Texture {
+        tracing::debug!("Creating offscreen texture ({width}x{height})...");
 
-            let descriptor = wgpu::DeviceDescriptor {
-		label: None,
-		required_features: wgpu::Features::empty(),
-		required_limits: wgpu::Limits::default(),
-		memory_hints: Default::default(),
-		trace: wgpu::Trace::Off,
-            };
+        self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Texture"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: OFFSCREEN_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+    }
+
+    fn request_device(adapter: &Adapter, settings: &AdapterSettings) -> (Device, Queue) {
+        tracing::debug!("Creating device...");
+
+        let descriptor = wgpu::DeviceDescriptor {
+            label: None,
+            required_features: settings.features_for(adapter),
+            required_limits: settings.limits_for(adapter),
+            memory_hints: Default::default(),
+            trace: wgpu::Trace::Off,
+        };
+
+        pollster::block_on(adapter.request_device(&descriptor))
+            .expect("Failed to create device/queue!")
+    }
+
+    pub fn new(window: Arc<Window>, settings: &AdapterSettings) -> Self {
+        tracing::info!("Creating WebGPU backend...");
+
+        tracing::debug!("Creating Instance...");
+
+        let instance = settings.create_instance();
+
+        tracing::debug!("Creating surface...");
+
+        let surface = instance
+            .create_surface(window)
+            .expect("Failed to create surface!");
+
+        tracing::debug!("Requesting adapter...");
+
+        let adapter = settings.request_adapter(&instance, Some(&surface));
+
+        tracing::debug!("Using adapter {:?}", adapter.get_info());
+
+        let (device, queue) = Self::request_device(&adapter, settings);
+
+        Self {
+            instance,
+            surface: Some(surface),
+            adapter,
+            device,
+            queue,
+            surface_caps: None,
+            surface_format: None,
+            offscreen_texture: None,
+            surface_config: None,
+            present: PresentSettings::default(),
+        }
+    }
+
+    pub fn new_headless(width: u32, height: u32) -> Self {
+        Self::new_headless_with(width, height, &AdapterSettings::headless())
+    }
+
+    pub fn new_headless_with(width: u32, height: u32, settings: &AdapterSettings) -> Self {
+        tracing::info!("Creating headless WebGPU backend...");
+
+        tracing::debug!("Creating Instance...");
+
+        let instance = settings.create_instance();
+
+        tracing::debug!("Requesting adapter...");
+
+        let adapter = settings.request_adapter(&instance, None);
+
+        tracing::debug!("Using adapter {:?}", adapter.get_info());
+
+        let (device, queue) = Self::request_device(&adapter, settings);
+
+        let mut graphics = Self {
+            instance,
+            surface: None,
+            adapter,
+            device,
+            queue,
+            surface_caps: None,
+            surface_format: Some(OFFSCREEN_FORMAT),
+            offscreen_texture: None,
+            surface_config: None,
+            present: PresentSettings::default(),
+        };
 
-            let (device, queue) = pollster::block_on(adapter.request_device(&descriptor))
-		.expect("Failed to create device/queue!");
+        graphics.resize(width, height);
 
-            Self {
-		instance,
-		surface,
-		adapter,
-		device,
-		queue,
-		surface_caps: None,
-		surface_format: None,
-		surface_config: None,
-            }
-	}
+        graphics
     }
 }
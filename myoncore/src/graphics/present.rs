@@ -0,0 +1,71 @@
+use wgpu::{CompositeAlphaMode, PresentMode};
+
+/// How frames are presented to the window.
+///
+/// Set through [`crate::EngineConfig`] and changed at runtime through
+/// [`crate::EngineContext::present`], the surface is reconfigured before the next frame.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PresentSettings {
+    /// Wait for the display to refresh, used to pick a mode when `present_mode` is `None`
+    /// or unsupported.
+    pub vsync: bool,
+    pub present_mode: Option<PresentMode>,
+    pub alpha_mode: CompositeAlphaMode,
+    /// How many frames the GPU may queue before rendering blocks. Lower means less input
+    /// latency, at the cost of throughput.
+    pub frame_latency: u32,
+}
+
+impl Default for PresentSettings {
+    fn default() -> Self {
+        Self {
+            vsync: true,
+            present_mode: None,
+            alpha_mode: CompositeAlphaMode::Auto,
+            frame_latency: 2,
+        }
+    }
+}
+
+impl PresentSettings {
+    /// Picks `present_mode` if the surface supports it, otherwise the best supported mode
+    /// for `vsync`.
+    pub fn choose_present_mode(&self, supported: &[PresentMode]) -> PresentMode {
+        if let Some(mode) = self.present_mode {
+            if supported.contains(&mode) {
+                return mode;
+            }
+
+            tracing::warn!("Present mode {mode:?} isn't supported, falling back");
+        }
+
+        let preferred: &[PresentMode] = if self.vsync {
+            &[PresentMode::Fifo]
+        } else {
+            &[
+                PresentMode::Immediate,
+                PresentMode::Mailbox,
+                PresentMode::Fifo,
+            ]
+        };
+
+        preferred
+            .iter()
+            .copied()
+            .find(|mode| supported.contains(mode))
+            .unwrap_or(PresentMode::Fifo)
+    }
+
+    pub fn choose_alpha_mode(&self, supported: &[CompositeAlphaMode]) -> CompositeAlphaMode {
+        if self.alpha_mode == CompositeAlphaMode::Auto || supported.contains(&self.alpha_mode) {
+            return self.alpha_mode;
+        }
+
+        tracing::warn!(
+            "Alpha mode {:?} isn't supported, falling back",
+            self.alpha_mode
+        );
+
+        CompositeAlphaMode::Auto
+    }
+}
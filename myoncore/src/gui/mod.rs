@@ -1,136 +1,218 @@
+use std::{cell::RefCell, rc::Rc, sync::Arc, time::Duration};
+
 use egui::ViewportId;
 
 use egui::Context as EguiContext;
 use egui_wgpu::Renderer as EguiRenderer;
 use egui_wgpu::ScreenDescriptor;
+use egui_winit::EventResponse;
 use egui_winit::State as EguiWinitState;
 use winit::window::Theme;
 use winit::window::Window;
 
 use crate::graphics::Graphics;
-use crate::renderer::Renderer;
+use crate::renderer::{RenderGraph, Renderer, Texture};
 
+/// Draws egui on top of [`RenderGraph::OUTPUT`] as the renderer's overlay pass, after the
+/// scene was resolved from its MSAA or HDR target, so its pipeline is always single-sampled
+/// in the surface format.
 pub struct Gui {
     pub ctx: EguiContext,
-    state: EguiWinitState,
-    egui_renderer: EguiRenderer,
-    window: *const Window,
-    graphics: *const Graphics,
-    renderer: *mut Renderer,
+    state: Option<EguiWinitState>,
+    // Shared with the overlay pass, which runs when the renderer executes its graph.
+    egui_renderer: Rc<RefCell<EguiRenderer>>,
+    // Freed at the next end_frame, once the frame that last used them was drawn.
+    textures_to_free: Vec<egui::TextureId>,
+    window: Option<Arc<Window>>,
+    device: wgpu::Device,
+    repaint_delay: Duration,
 }
 
 impl Gui {
-    pub unsafe fn new(window: *const Window, graphics: *const Graphics, renderer: *mut Renderer) -> Self {
-	unsafe {
-            let ctx = EguiContext::default();
-            let state = EguiWinitState::new(
-		ctx.clone(),
-		ViewportId::ROOT,
-		&*window,
-		Some((*window).scale_factor() as f32),
-		Some(Theme::Dark),
-		None,
-            );
+    pub fn new(window: Arc<Window>, graphics: &Graphics) -> Self {
+        let ctx = EguiContext::default();
+        let state = EguiWinitState::new(
+            ctx.clone(),
+            ViewportId::ROOT,
+            &window,
+            Some(window.scale_factor() as f32),
+            Some(Theme::Dark),
+            None,
+        );
+
+        let surface_format = graphics
+            .surface_format
+            .expect("Failed to get surface_format!");
+
+        let egui_renderer = Rc::new(RefCell::new(EguiRenderer::new(
+            &graphics.device,
+            surface_format,
+            None,
+            1,
+            false,
+        )));
+
+        Self {
+            ctx,
+            state: Some(state),
+            egui_renderer,
+            window: Some(window),
+            device: graphics.device.clone(),
+            repaint_delay: Duration::ZERO,
+            textures_to_free: Vec::new(),
+        }
+    }
 
-            let surface_format = (*graphics)
-		.surface_format
-		.expect("Failed to get surface_format!");
+    pub fn new_headless(graphics: &Graphics) -> Self {
+        let ctx = EguiContext::default();
+        ctx.set_visuals(egui::Visuals::dark());
+
+        let surface_format = graphics
+            .surface_format
+            .expect("Failed to get surface_format!");
+
+        let egui_renderer = Rc::new(RefCell::new(EguiRenderer::new(
+            &graphics.device,
+            surface_format,
+            None,
+            1,
+            false,
+        )));
+
+        Self {
+            ctx,
+            state: None,
+            egui_renderer,
+            window: None,
+            device: graphics.device.clone(),
+            repaint_delay: Duration::ZERO,
+            textures_to_free: Vec::new(),
+        }
+    }
 
-            let egui_renderer = EguiRenderer::new(&(*graphics).device, surface_format, None, 1, false);
+    /// Makes `texture` usable in egui widgets such as `egui::Image`.
+    pub fn register_texture(
+        &mut self,
+        texture: &Texture,
+        filter: wgpu::FilterMode,
+    ) -> egui::TextureId {
+        self.egui_renderer
+            .borrow_mut()
+            .register_native_texture(&self.device, &texture.view, filter)
+    }
 
-            Self {
-		ctx,
-		state,
-		egui_renderer,
-		window,
-		graphics,
-		renderer
-            }
-	}
+    pub fn unregister_texture(&mut self, id: egui::TextureId) {
+        self.egui_renderer.borrow_mut().free_texture(&id);
     }
 
-    pub unsafe fn handle_event(&mut self, event: &winit::event::WindowEvent) {
-	unsafe {
-            let _ = self.state.on_window_event(&(*self.window), event);
-	}
+    /// Returns whether egui used the event, for example typing into a text field, and
+    /// whether it needs to repaint because of it.
+    pub fn handle_event(&mut self, event: &winit::event::WindowEvent) -> EventResponse {
+        match (self.state.as_mut(), self.window.as_ref()) {
+            (Some(state), Some(window)) => state.on_window_event(window, event),
+            _ => EventResponse::default(),
+        }
     }
 
-    pub unsafe fn begin_frame(&mut self) {
-	unsafe {
-            let raw_input = self.state.take_egui_input(&(*self.window));
-            self.ctx.begin_pass(raw_input);
-	}
+    /// How long egui can wait before it needs to be drawn again, as of the last frame.
+    /// `Duration::MAX` if it doesn't need to be drawn until something happens.
+    pub fn repaint_delay(&self) -> Duration {
+        self.repaint_delay
     }
 
-    pub fn end_frame(&mut self) {
-	unsafe {
-            let texture_view = (*self.renderer).texture_view
-		.as_ref()
-		.expect("TextureView missing");
-
-            let encoder = (*self.renderer).command_encoder
-		.as_mut()
-		.expect("CommandEncoder missing");
-
-            let full_output = self.ctx.end_pass();
-            let paint_jobs = self
-		.ctx
-		.tessellate(full_output.shapes, self.ctx.pixels_per_point());
-
-            let window_size = (*self.window).inner_size();
-            let screen_descriptor = ScreenDescriptor {
-		size_in_pixels: [window_size.width, window_size.height],
-		pixels_per_point: self.ctx.pixels_per_point(),
-            };
+    pub fn begin_frame(&mut self, graphics: &Graphics) {
+        let raw_input = match (self.state.as_mut(), self.window.as_ref()) {
+            (Some(state), Some(window)) => state.take_egui_input(window),
 
-            self.state
-		.handle_platform_output(&(*self.window), full_output.platform_output);
+            _ => {
+                let (width, height) = graphics.size();
+
+                egui::RawInput {
+                    screen_rect: Some(egui::Rect::from_min_size(
+                        egui::Pos2::ZERO,
+                        egui::vec2(width as f32, height as f32),
+                    )),
+                    ..Default::default()
+                }
+            }
+        };
+
+        self.ctx.begin_pass(raw_input);
+    }
+
+    /// Finishes the egui frame and hands its drawing to `renderer` as the overlay pass, so
+    /// it has to be called before [`Renderer::execute_graph`].
+    pub fn end_frame(&mut self, graphics: &Graphics, renderer: &mut Renderer) {
+        let full_output = self.ctx.end_pass();
+        self.repaint_delay = full_output
+            .viewport_output
+            .get(&ViewportId::ROOT)
+            .map_or(Duration::MAX, |viewport| viewport.repaint_delay);
+        let paint_jobs = self
+            .ctx
+            .tessellate(full_output.shapes, self.ctx.pixels_per_point());
+
+        let (width, height) = graphics.size();
+        let screen_descriptor = ScreenDescriptor {
+            size_in_pixels: [width, height],
+            pixels_per_point: self.ctx.pixels_per_point(),
+        };
+
+        if let (Some(state), Some(window)) = (self.state.as_mut(), self.window.as_ref()) {
+            state.handle_platform_output(window, full_output.platform_output);
+        }
+
+        // Textures are updated outside the overlay pass, so they aren't lost if the frame
+        // doesn't get drawn.
+        {
+            let mut egui_renderer = self.egui_renderer.borrow_mut();
+
+            for id in self.textures_to_free.drain(..) {
+                egui_renderer.free_texture(&id);
+            }
 
             for (id, image_delta) in &full_output.textures_delta.set {
-		self.egui_renderer.update_texture(
-                    &(*self.graphics).device,
-                    &(*self.graphics).queue,
-                    *id,
-                    image_delta,
-		);
+                egui_renderer.update_texture(&graphics.device, &graphics.queue, *id, image_delta);
             }
+        }
+        self.textures_to_free = full_output.textures_delta.free;
 
-            self.egui_renderer.update_buffers(
-		&(*self.graphics).device,
-		&(*self.graphics).queue,
-		encoder,
-		&paint_jobs,
-		&screen_descriptor,
+        let egui_renderer = self.egui_renderer.clone();
+
+        renderer.set_overlay(move |ctx| {
+            let mut egui_renderer = egui_renderer.borrow_mut();
+
+            egui_renderer.update_buffers(
+                ctx.device,
+                ctx.queue,
+                ctx.encoder,
+                &paint_jobs,
+                &screen_descriptor,
             );
 
             let render_pass_descriptor = wgpu::RenderPassDescriptor {
-		label: None,
-		color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: texture_view,
+                label: Some("GUI Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: ctx.view(RenderGraph::OUTPUT),
                     resolve_target: None,
                     ops: wgpu::Operations {
-			load: wgpu::LoadOp::Load,
-			store: wgpu::StoreOp::Store,
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
                     },
-		})],
-		depth_stencil_attachment: None,
-		occlusion_query_set: None,
-		timestamp_writes: None,
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
             };
 
             {
-		let mut render_pass = encoder.begin_render_pass(&render_pass_descriptor);
-
-		let static_render_pass: &mut wgpu::RenderPass<'static> =
-                    std::mem::transmute(&mut render_pass);
-
-		self.egui_renderer
-                    .render(static_render_pass, &paint_jobs, &screen_descriptor);
+                let mut render_pass = ctx
+                    .encoder
+                    .begin_render_pass(&render_pass_descriptor)
+                    .forget_lifetime();
 
-		for x in &full_output.textures_delta.free {
-                    self.egui_renderer.free_texture(x)
-		}
+                egui_renderer.render(&mut render_pass, &paint_jobs, &screen_descriptor);
             }
-	}
+        });
     }
 }
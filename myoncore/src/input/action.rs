@@ -0,0 +1,89 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+use super::{GamepadAxis, GamepadButton};
+
+/// A physical button that can trigger an action.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// The button on any connected gamepad.
+    Gamepad(GamepadButton),
+}
+
+/// Something that produces a value between -1 and 1 for a named axis.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum AxisBinding {
+    /// -1 while `negative` is held, 1 while `positive` is held, 0 for both or neither.
+    Buttons {
+        negative: Binding,
+        positive: Binding,
+    },
+    /// Horizontal mouse wheel movement this frame, in lines.
+    MouseWheelX,
+    /// Vertical mouse wheel movement this frame, in lines.
+    MouseWheelY,
+    /// The axis on whichever connected gamepad pushes it the furthest, with deadzones applied.
+    GamepadAxis(GamepadAxis),
+}
+
+/// Named actions and axes, so gameplay code can ask for "jump" instead of a key code.
+///
+/// Maps are usually written in RON:
+///
+/// ```ron
+/// (
+///     actions: {
+///         "jump": [Key(Space), Mouse(Left), Gamepad(South)],
+///     },
+///     axes: {
+///         "move_x": [
+///             Buttons(negative: Key(KeyA), positive: Key(KeyD)),
+///             GamepadAxis(LeftStickX),
+///         ],
+///     },
+/// )
+/// ```
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputMap {
+    pub actions: HashMap<String, Vec<Binding>>,
+    pub axes: HashMap<String, Vec<AxisBinding>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind(mut self, action: impl Into<String>, binding: Binding) -> Self {
+        self.actions.entry(action.into()).or_default().push(binding);
+        self
+    }
+
+    pub fn bind_axis(mut self, axis: impl Into<String>, binding: AxisBinding) -> Self {
+        self.axes.entry(axis.into()).or_default().push(binding);
+        self
+    }
+
+    pub fn from_ron(source: &str) -> anyhow::Result<Self> {
+        ron::from_str(source).context("Failed to parse input map")
+    }
+
+    pub fn to_ron(&self) -> anyhow::Result<String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .context("Failed to serialize input map")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Self::from_ron(&source).with_context(|| format!("Failed to load {}", path.display()))
+    }
+}
@@ -0,0 +1,406 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Buttons;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub struct GamepadId(pub usize);
+
+/// Buttons named by position, using the common Xbox-style layout.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GamepadEvent {
+    Connected {
+        id: GamepadId,
+        name: String,
+    },
+    Disconnected(GamepadId),
+    Button {
+        id: GamepadId,
+        button: GamepadButton,
+        pressed: bool,
+    },
+    /// Sticks go from -1 to 1 with positive y pointing up, triggers from 0 to 1.
+    Axis {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// Where gamepad events come from.
+pub trait GamepadBackend {
+    /// Appends every event since the last call.
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>);
+
+    /// Vibrates the gamepad with the given strong (low frequency) and weak (high frequency)
+    /// motor strengths, from 0 to 1.
+    fn rumble(
+        &mut self,
+        id: GamepadId,
+        strong: f32,
+        weak: f32,
+        duration: Duration,
+    ) -> anyhow::Result<()>;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RumbleRequest {
+    pub id: GamepadId,
+    pub strong: f32,
+    pub weak: f32,
+    pub duration: Duration,
+}
+
+#[derive(Default)]
+struct VirtualState {
+    next_id: usize,
+    events: VecDeque<GamepadEvent>,
+    rumbles: Vec<RumbleRequest>,
+}
+
+/// A backend that only sees the events you feed it, for tests and demos.
+///
+/// Clones share the same queue, so one clone can be given to [`Gamepads::set_backend`]
+/// while another keeps feeding events.
+#[derive(Clone, Default)]
+pub struct VirtualGamepads {
+    state: Arc<Mutex<VirtualState>>,
+}
+
+impl VirtualGamepads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect(&self, name: impl Into<String>) -> GamepadId {
+        let mut state = self.state.lock().expect("Virtual gamepads poisoned");
+
+        let id = GamepadId(state.next_id);
+        state.next_id += 1;
+        state.events.push_back(GamepadEvent::Connected {
+            id,
+            name: name.into(),
+        });
+
+        id
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        self.send(GamepadEvent::Disconnected(id));
+    }
+
+    pub fn press(&self, id: GamepadId, button: GamepadButton) {
+        self.send(GamepadEvent::Button {
+            id,
+            button,
+            pressed: true,
+        });
+    }
+
+    pub fn release(&self, id: GamepadId, button: GamepadButton) {
+        self.send(GamepadEvent::Button {
+            id,
+            button,
+            pressed: false,
+        });
+    }
+
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.send(GamepadEvent::Axis { id, axis, value });
+    }
+
+    pub fn send(&self, event: GamepadEvent) {
+        self.state
+            .lock()
+            .expect("Virtual gamepads poisoned")
+            .events
+            .push_back(event);
+    }
+
+    /// Takes the rumble requests made so far.
+    pub fn take_rumbles(&self) -> Vec<RumbleRequest> {
+        std::mem::take(
+            &mut self
+                .state
+                .lock()
+                .expect("Virtual gamepads poisoned")
+                .rumbles,
+        )
+    }
+}
+
+impl GamepadBackend for VirtualGamepads {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        events.extend(
+            self.state
+                .lock()
+                .expect("Virtual gamepads poisoned")
+                .events
+                .drain(..),
+        );
+    }
+
+    fn rumble(
+        &mut self,
+        id: GamepadId,
+        strong: f32,
+        weak: f32,
+        duration: Duration,
+    ) -> anyhow::Result<()> {
+        self.state
+            .lock()
+            .expect("Virtual gamepads poisoned")
+            .rumbles
+            .push(RumbleRequest {
+                id,
+                strong,
+                weak,
+                duration,
+            });
+
+        Ok(())
+    }
+}
+
+pub struct Gamepad {
+    pub name: String,
+    pub buttons: Buttons<GamepadButton>,
+    /// Axis values with an absolute value below this read as 0.
+    pub deadzone: f32,
+    axes: HashMap<GamepadAxis, f32>,
+    connected: bool,
+}
+
+impl Gamepad {
+    /// The axis value with the deadzone applied and the rest of the range rescaled,
+    /// so that values still start at 0 right outside the deadzone.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        let value = self.raw_axis(axis);
+
+        if value.abs() <= self.deadzone {
+            return 0.0;
+        }
+
+        value.signum() * ((value.abs() - self.deadzone) / (1.0 - self.deadzone)).min(1.0)
+    }
+
+    pub fn raw_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or_default()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+}
+
+/// Every known gamepad and the backend that reports them.
+pub struct Gamepads {
+    /// Deadzone given to newly connected gamepads.
+    pub deadzone: f32,
+    backend: Option<Box<dyn GamepadBackend>>,
+    pads: BTreeMap<GamepadId, Gamepad>,
+    events: Vec<GamepadEvent>,
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            backend: None,
+            pads: BTreeMap::new(),
+            events: Vec::new(),
+        }
+    }
+}
+
+impl Gamepads {
+    pub fn set_backend(&mut self, backend: impl GamepadBackend + 'static) {
+        self.backend = Some(Box::new(backend));
+    }
+
+    pub fn has_backend(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    pub fn get(&self, id: GamepadId) -> Option<&Gamepad> {
+        self.pads.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: GamepadId) -> Option<&mut Gamepad> {
+        self.pads.get_mut(&id)
+    }
+
+    /// Connected gamepads, plus ones that disconnected this frame.
+    pub fn iter(&self) -> impl Iterator<Item = (GamepadId, &Gamepad)> {
+        self.pads.iter().map(|(id, pad)| (*id, pad))
+    }
+
+    /// Events received this frame, including connects and disconnects.
+    pub fn events(&self) -> &[GamepadEvent] {
+        &self.events
+    }
+
+    pub fn rumble(
+        &mut self,
+        id: GamepadId,
+        strong: f32,
+        weak: f32,
+        duration: Duration,
+    ) -> anyhow::Result<()> {
+        let backend = self
+            .backend
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("There is no gamepad backend"))?;
+
+        backend.rumble(id, strong.clamp(0.0, 1.0), weak.clamp(0.0, 1.0), duration)
+    }
+
+    pub(crate) fn poll_backend(&mut self) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+
+        if let Some(backend) = self.backend.as_mut() {
+            backend.poll(&mut events);
+        }
+
+        events
+    }
+
+    pub(crate) fn handle_event(&mut self, event: &GamepadEvent) {
+        match event {
+            GamepadEvent::Connected { id, name } => {
+                tracing::info!("Gamepad connected: {name}");
+
+                self.pads.insert(
+                    *id,
+                    Gamepad {
+                        name: name.clone(),
+                        buttons: Buttons::default(),
+                        deadzone: self.deadzone,
+                        axes: HashMap::new(),
+                        connected: true,
+                    },
+                );
+            }
+
+            GamepadEvent::Disconnected(id) => {
+                if let Some(pad) = self.pads.get_mut(id) {
+                    tracing::info!("Gamepad disconnected: {}", pad.name);
+
+                    pad.buttons.release_all();
+                    pad.axes.clear();
+                    pad.connected = false;
+                }
+            }
+
+            GamepadEvent::Button {
+                id,
+                button,
+                pressed,
+            } => {
+                if let Some(pad) = self.pads.get_mut(id) {
+                    if *pressed {
+                        pad.buttons.press(*button);
+                    } else {
+                        pad.buttons.release(*button);
+                    }
+                }
+            }
+
+            GamepadEvent::Axis { id, axis, value } => {
+                if let Some(pad) = self.pads.get_mut(id) {
+                    pad.axes.insert(*axis, value.clamp(-1.0, 1.0));
+                }
+            }
+        }
+
+        self.events.push(event.clone());
+    }
+
+    pub(crate) fn end_frame(&mut self) {
+        self.pads.retain(|_, pad| pad.connected);
+
+        for pad in self.pads.values_mut() {
+            pad.buttons.clear();
+        }
+
+        self.events.clear();
+    }
+
+    /// Events that connect every gamepad and put it in its current state.
+    pub(crate) fn state_events(&self) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+
+        for (&id, pad) in self.pads.iter().filter(|(_, pad)| pad.connected) {
+            events.push(GamepadEvent::Connected {
+                id,
+                name: pad.name.clone(),
+            });
+            events.extend(pad.buttons.iter_held().map(|button| GamepadEvent::Button {
+                id,
+                button,
+                pressed: true,
+            }));
+            events.extend(pad.axes.iter().map(|(&axis, &value)| GamepadEvent::Axis {
+                id,
+                axis,
+                value,
+            }));
+        }
+
+        events
+    }
+
+    pub(crate) fn any(&self, f: impl FnMut(&Gamepad) -> bool) -> bool {
+        self.pads.values().any(f)
+    }
+
+    /// The value of `axis` on the gamepad that pushes it the furthest.
+    pub(crate) fn strongest_axis(&self, axis: GamepadAxis) -> f32 {
+        self.pads
+            .values()
+            .map(|pad| pad.axis(axis))
+            .fold(0.0, |strongest, value| {
+                if value.abs() > f32::abs(strongest) {
+                    value
+                } else {
+                    strongest
+                }
+            })
+    }
+}
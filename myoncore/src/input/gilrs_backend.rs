@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use gilrs::{
+    Axis, Button, EventType, Gilrs,
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Replay, Ticks},
+};
+
+use super::{GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId};
+
+/// Real gamepads through gilrs.
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+    ids: HashMap<GamepadId, gilrs::GamepadId>,
+    // Effects stop playing when dropped, so they're kept until they're done.
+    effects: Vec<(Effect, Instant)>,
+    connected: Vec<GamepadEvent>,
+}
+
+impl GilrsBackend {
+    pub fn new() -> anyhow::Result<Self> {
+        let gilrs = Gilrs::new().map_err(|e| anyhow::anyhow!("Failed to start gilrs: {e}"))?;
+
+        let mut ids = HashMap::new();
+        let connected = gilrs
+            .gamepads()
+            .map(|(gilrs_id, gamepad)| {
+                let id = GamepadId(gilrs_id.into());
+                ids.insert(id, gilrs_id);
+
+                GamepadEvent::Connected {
+                    id,
+                    name: gamepad.name().to_string(),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            gilrs,
+            ids,
+            effects: Vec::new(),
+            connected,
+        })
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        events.append(&mut self.connected);
+
+        let now = Instant::now();
+        self.effects.retain(|(_, until)| *until > now);
+
+        while let Some(gilrs::Event {
+            id: gilrs_id,
+            event,
+            ..
+        }) = self.gilrs.next_event()
+        {
+            let id = GamepadId(gilrs_id.into());
+
+            let event = match event {
+                EventType::Connected => {
+                    self.ids.insert(id, gilrs_id);
+
+                    GamepadEvent::Connected {
+                        id,
+                        name: self.gilrs.gamepad(gilrs_id).name().to_string(),
+                    }
+                }
+
+                EventType::Disconnected => {
+                    self.ids.remove(&id);
+
+                    GamepadEvent::Disconnected(id)
+                }
+
+                EventType::ButtonPressed(button, _) | EventType::ButtonReleased(button, _) => {
+                    let Some(button) = convert_button(button) else {
+                        continue;
+                    };
+
+                    GamepadEvent::Button {
+                        id,
+                        button,
+                        pressed: matches!(event, EventType::ButtonPressed(..)),
+                    }
+                }
+
+                // Analog triggers are reported as buttons with a value.
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => GamepadEvent::Axis {
+                    id,
+                    axis: GamepadAxis::LeftTrigger,
+                    value,
+                },
+
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => GamepadEvent::Axis {
+                    id,
+                    axis: GamepadAxis::RightTrigger,
+                    value,
+                },
+
+                EventType::AxisChanged(axis, value, _) => {
+                    let Some(axis) = convert_axis(axis) else {
+                        continue;
+                    };
+
+                    GamepadEvent::Axis { id, axis, value }
+                }
+
+                _ => continue,
+            };
+
+            events.push(event);
+        }
+    }
+
+    fn rumble(
+        &mut self,
+        id: GamepadId,
+        strong: f32,
+        weak: f32,
+        duration: Duration,
+    ) -> anyhow::Result<()> {
+        let gilrs_id = *self
+            .ids
+            .get(&id)
+            .ok_or_else(|| anyhow::anyhow!("Gamepad {id:?} isn't connected"))?;
+
+        let scheduling = Replay {
+            play_for: Ticks::from_ms(duration.as_millis().min(u32::MAX as u128) as u32),
+            ..Default::default()
+        };
+
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: (strong * u16::MAX as f32) as u16,
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: (weak * u16::MAX as f32) as u16,
+                },
+                scheduling,
+                ..Default::default()
+            })
+            .gamepads(&[gilrs_id])
+            .finish(&mut self.gilrs)
+            .map_err(|e| anyhow::anyhow!("Failed to create rumble effect: {e}"))?;
+
+        effect
+            .play()
+            .map_err(|e| anyhow::anyhow!("Failed to play rumble effect: {e}"))?;
+
+        self.effects.push((effect, Instant::now() + duration));
+
+        Ok(())
+    }
+}
+
+fn convert_button(button: Button) -> Option<GamepadButton> {
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::North => GamepadButton::North,
+        Button::West => GamepadButton::West,
+        Button::LeftTrigger => GamepadButton::LeftBumper,
+        Button::RightTrigger => GamepadButton::RightBumper,
+        Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+        Button::RightTrigger2 => GamepadButton::RightTrigger,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        Button::Mode => GamepadButton::Mode,
+        Button::LeftThumb => GamepadButton::LeftStick,
+        Button::RightThumb => GamepadButton::RightStick,
+        Button::DPadUp => GamepadButton::DPadUp,
+        Button::DPadDown => GamepadButton::DPadDown,
+        Button::DPadLeft => GamepadButton::DPadLeft,
+        Button::DPadRight => GamepadButton::DPadRight,
+        _ => return None,
+    })
+}
+
+fn convert_axis(axis: Axis) -> Option<GamepadAxis> {
+    Some(match axis {
+        Axis::LeftStickX => GamepadAxis::LeftStickX,
+        Axis::LeftStickY => GamepadAxis::LeftStickY,
+        Axis::RightStickX => GamepadAxis::RightStickX,
+        Axis::RightStickY => GamepadAxis::RightStickY,
+        Axis::LeftZ => GamepadAxis::LeftTrigger,
+        Axis::RightZ => GamepadAxis::RightTrigger,
+        _ => return None,
+    })
+}
@@ -0,0 +1,397 @@
+pub mod action;
+pub mod gamepad;
+#[cfg(feature = "gilrs")]
+pub mod gilrs_backend;
+pub mod recording;
+
+use std::{collections::HashSet, hash::Hash};
+
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use winit::{
+    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+pub use action::{AxisBinding, Binding, InputMap};
+pub use gamepad::{
+    Gamepad, GamepadAxis, GamepadBackend, GamepadButton, GamepadEvent, GamepadId, Gamepads,
+    VirtualGamepads,
+};
+#[cfg(feature = "gilrs")]
+pub use gilrs_backend::GilrsBackend;
+pub use recording::{InputRecording, RecordedEvent};
+
+/// Pixel-based wheel deltas (touchpads) are converted to lines at this rate.
+const PIXELS_PER_LINE: f32 = 20.0;
+
+/// Everything that can change [`Input`]: the relevant window events plus gamepad events.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key {
+        code: KeyCode,
+        pressed: bool,
+    },
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    /// New cursor position in physical pixels.
+    CursorMoved(Vec2),
+    /// The cursor left the window, so the next move doesn't count towards the delta.
+    CursorLeft,
+    /// Wheel movement in lines.
+    MouseWheel(Vec2),
+    /// The window lost focus, so every held button counts as released.
+    FocusLost,
+    Gamepad(GamepadEvent),
+}
+
+impl InputEvent {
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let PhysicalKey::Code(code) = event.physical_key else {
+                    return None;
+                };
+
+                if event.repeat {
+                    return None;
+                }
+
+                Some(Self::Key {
+                    code,
+                    pressed: event.state == ElementState::Pressed,
+                })
+            }
+
+            WindowEvent::MouseInput { state, button, .. } => Some(Self::MouseButton {
+                button: *button,
+                pressed: *state == ElementState::Pressed,
+            }),
+
+            WindowEvent::CursorMoved { position, .. } => Some(Self::CursorMoved(Vec2::new(
+                position.x as f32,
+                position.y as f32,
+            ))),
+
+            WindowEvent::CursorLeft { .. } => Some(Self::CursorLeft),
+
+            WindowEvent::MouseWheel { delta, .. } => Some(Self::MouseWheel(match delta {
+                MouseScrollDelta::LineDelta(x, y) => Vec2::new(*x, *y),
+                MouseScrollDelta::PixelDelta(position) => {
+                    Vec2::new(position.x as f32, position.y as f32) / PIXELS_PER_LINE
+                }
+            })),
+
+            WindowEvent::Focused(false) => Some(Self::FocusLost),
+
+            _ => None,
+        }
+    }
+
+    /// Presses and wheel movement, which the game should ignore when the GUI used them.
+    /// Releases always go through so buttons held before can't get stuck.
+    pub fn is_press(&self) -> bool {
+        matches!(
+            self,
+            Self::Key { pressed: true, .. }
+                | Self::MouseButton { pressed: true, .. }
+                | Self::MouseWheel(_)
+        )
+    }
+}
+
+/// Held buttons plus the ones that changed since the last [`Buttons::clear`].
+#[derive(Clone, Debug)]
+pub struct Buttons<T> {
+    held: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+}
+
+impl<T> Default for Buttons<T> {
+    fn default() -> Self {
+        Self {
+            held: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> Buttons<T> {
+    pub fn press(&mut self, button: T) {
+        if self.held.insert(button) {
+            self.pressed.insert(button);
+        }
+    }
+
+    pub fn release(&mut self, button: T) {
+        if self.held.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        self.released.extend(self.held.drain());
+    }
+
+    /// Forgets which buttons were pressed or released, keeping the held ones.
+    pub fn clear(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    /// Went down this frame.
+    pub fn pressed(&self, button: T) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn held(&self, button: T) -> bool {
+        self.held.contains(&button)
+    }
+
+    /// Went up this frame.
+    pub fn released(&self, button: T) -> bool {
+        self.released.contains(&button)
+    }
+
+    pub fn iter_held(&self) -> impl Iterator<Item = T> + '_ {
+        self.held.iter().copied()
+    }
+}
+
+/// Keyboard, mouse and gamepad state, updated by the engine every frame.
+///
+/// "This frame" means since the last [`crate::AppHandler::on_update`], so every press is
+/// seen by exactly one fixed update, even when a frame runs none or several of them.
+#[derive(Default)]
+pub struct Input {
+    pub keys: Buttons<KeyCode>,
+    pub mouse_buttons: Buttons<MouseButton>,
+    pub gamepads: Gamepads,
+    pub map: InputMap,
+    mouse_position: Vec2,
+    // Whether `mouse_position` is where the cursor is now, and not stale or never set.
+    cursor_known: bool,
+    mouse_delta: Vec2,
+    wheel_delta: Vec2,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle_event(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::Key { code, pressed } => {
+                let (code, pressed) = (*code, *pressed);
+                if pressed {
+                    self.keys.press(code);
+                } else {
+                    self.keys.release(code);
+                }
+            }
+
+            InputEvent::MouseButton { button, pressed } => {
+                let (button, pressed) = (*button, *pressed);
+                if pressed {
+                    self.mouse_buttons.press(button);
+                } else {
+                    self.mouse_buttons.release(button);
+                }
+            }
+
+            InputEvent::CursorMoved(position) => {
+                if self.cursor_known {
+                    self.mouse_delta += *position - self.mouse_position;
+                }
+
+                self.mouse_position = *position;
+                self.cursor_known = true;
+            }
+
+            InputEvent::CursorLeft => self.cursor_known = false,
+
+            InputEvent::MouseWheel(delta) => self.wheel_delta += *delta,
+
+            InputEvent::FocusLost => {
+                self.cursor_known = false;
+                self.keys.release_all();
+                self.mouse_buttons.release_all();
+            }
+
+            InputEvent::Gamepad(event) => self.gamepads.handle_event(event),
+        }
+    }
+
+    /// Feeds everything the gamepad backend reported since the last call into the input state.
+    pub fn poll_gamepads(&mut self) {
+        for event in self.gamepads.poll_backend() {
+            self.handle_event(&InputEvent::Gamepad(event));
+        }
+    }
+
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        if let Some(event) = InputEvent::from_window_event(event) {
+            self.handle_event(&event);
+        }
+    }
+
+    /// Events that bring a fresh [`Input`] to the current state, minus this frame's deltas.
+    pub(crate) fn state_events(&self) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+        if self.cursor_known {
+            events.push(InputEvent::CursorMoved(self.mouse_position));
+        }
+
+        events.extend(
+            self.keys
+                .iter_held()
+                .map(|code| InputEvent::Key {
+                    code,
+                    pressed: true,
+                })
+                .chain(
+                    self.mouse_buttons
+                        .iter_held()
+                        .map(|button| InputEvent::MouseButton {
+                            button,
+                            pressed: true,
+                        }),
+                )
+                .chain(
+                    self.gamepads
+                        .state_events()
+                        .into_iter()
+                        .map(InputEvent::Gamepad),
+                ),
+        );
+
+        events
+    }
+
+    /// Starts a new frame: clears presses, releases and deltas.
+    pub fn end_frame(&mut self) {
+        self.keys.clear();
+        self.mouse_buttons.clear();
+        self.gamepads.end_frame();
+        self.mouse_delta = Vec2::ZERO;
+        self.wheel_delta = Vec2::ZERO;
+    }
+
+    pub fn key_pressed(&self, key: KeyCode) -> bool {
+        self.keys.pressed(key)
+    }
+
+    pub fn key_held(&self, key: KeyCode) -> bool {
+        self.keys.held(key)
+    }
+
+    pub fn key_released(&self, key: KeyCode) -> bool {
+        self.keys.released(key)
+    }
+
+    pub fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.pressed(button)
+    }
+
+    pub fn mouse_held(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.held(button)
+    }
+
+    pub fn mouse_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.released(button)
+    }
+
+    /// Cursor position in physical pixels, relative to the top-left of the window. Where it
+    /// was last seen if it's outside the window.
+    pub fn mouse_position(&self) -> Vec2 {
+        self.mouse_position
+    }
+
+    /// Cursor movement this frame, not counting the jump when it enters the window.
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+
+    /// Wheel movement this frame in lines, positive y scrolls up.
+    pub fn wheel_delta(&self) -> Vec2 {
+        self.wheel_delta
+    }
+
+    pub fn binding_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.pressed(key),
+            Binding::Mouse(button) => self.mouse_buttons.pressed(button),
+            Binding::Gamepad(button) => self.gamepads.any(|pad| pad.buttons.pressed(button)),
+        }
+    }
+
+    pub fn binding_held(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.held(key),
+            Binding::Mouse(button) => self.mouse_buttons.held(button),
+            Binding::Gamepad(button) => self.gamepads.any(|pad| pad.buttons.held(button)),
+        }
+    }
+
+    pub fn binding_released(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.released(key),
+            Binding::Mouse(button) => self.mouse_buttons.released(button),
+            Binding::Gamepad(button) => self.gamepads.any(|pad| pad.buttons.released(button)),
+        }
+    }
+
+    /// Whether any binding of `action` went down this frame.
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| self.binding_pressed(*binding))
+    }
+
+    pub fn action_held(&self, action: &str) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| self.binding_held(*binding))
+    }
+
+    /// Whether a binding of `action` went up this frame and no other one is still held.
+    pub fn action_released(&self, action: &str) -> bool {
+        let bindings = self.bindings(action);
+
+        bindings
+            .iter()
+            .any(|binding| self.binding_released(*binding))
+            && !bindings.iter().any(|binding| self.binding_held(*binding))
+    }
+
+    /// Sum of all bindings of `axis`, clamped to -1..=1. Unknown axes are 0.
+    pub fn axis(&self, axis: &str) -> f32 {
+        let Some(bindings) = self.map.axes.get(axis) else {
+            return 0.0;
+        };
+
+        bindings
+            .iter()
+            .map(|binding| match *binding {
+                AxisBinding::Buttons { negative, positive } => {
+                    self.binding_held(positive) as i32 as f32
+                        - self.binding_held(negative) as i32 as f32
+                }
+
+                AxisBinding::MouseWheelX => self.wheel_delta.x,
+                AxisBinding::MouseWheelY => self.wheel_delta.y,
+                AxisBinding::GamepadAxis(axis) => self.gamepads.strongest_axis(axis),
+            })
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+
+    fn bindings(&self, action: &str) -> &[Binding] {
+        self.map.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+}
@@ -0,0 +1,89 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use super::InputEvent;
+
+/// An input event and the frame it arrived before.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub frame: usize,
+    pub event: InputEvent,
+}
+
+/// Every input event of a session together with how many fixed updates each frame ran,
+/// which is all [`crate::Engine::replay`] needs to reproduce the session's updates exactly.
+///
+/// Recordings are stored as RON.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct InputRecording {
+    /// Updates per second of the recorded session.
+    pub update_rate: f64,
+    /// Number of [`crate::AppHandler::on_update`] calls of every frame.
+    pub updates: Vec<u32>,
+    /// Sorted by frame.
+    pub events: Vec<RecordedEvent>,
+}
+
+impl InputRecording {
+    pub fn new(update_rate: f64) -> Self {
+        Self {
+            update_rate,
+            updates: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.updates.len()
+    }
+
+    /// Records `event` for the frame that hasn't been pushed yet.
+    pub fn push_event(&mut self, event: InputEvent) {
+        self.events.push(RecordedEvent {
+            frame: self.frames(),
+            event,
+        });
+    }
+
+    pub fn push_frame(&mut self, updates: u32) {
+        self.updates.push(updates);
+    }
+
+    pub fn events_in(&self, frame: usize) -> impl Iterator<Item = &InputEvent> {
+        let start = self
+            .events
+            .partition_point(|recorded| recorded.frame < frame);
+
+        self.events[start..]
+            .iter()
+            .take_while(move |recorded| recorded.frame == frame)
+            .map(|recorded| &recorded.event)
+    }
+
+    pub fn from_ron(source: &str) -> anyhow::Result<Self> {
+        ron::from_str(source).context("Failed to parse input recording")
+    }
+
+    pub fn to_ron(&self) -> anyhow::Result<String> {
+        let config = ron::ser::PrettyConfig::default().compact_arrays(true);
+
+        ron::ser::to_string_pretty(self, config).context("Failed to serialize input recording")
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Self::from_ron(&source).with_context(|| format!("Failed to load {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        fs::write(path, self.to_ron()?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
@@ -1,11 +1,20 @@
 pub mod utils;
 pub mod logger;
 pub mod window;
+pub mod input;
 pub mod graphics;
+pub mod shader;
 pub mod renderer;
 pub mod gui;
+pub mod assets;
+pub mod ecs;
+pub mod scene;
 pub mod engine;
+pub mod testing;
 
 pub use engine::EngineConfig;
+pub use engine::RunMode;
 pub use engine::Engine;
 pub use engine::AppHandler;
+pub use engine::EngineContext;
+pub use engine::RedrawHandle;
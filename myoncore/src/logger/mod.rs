@@ -23,12 +23,14 @@ impl Logger {
             .with_thread_names(true)
             .with_ansi(true);
 
-        tracing_subscriber::registry()
+        if tracing_subscriber::registry()
             .with(env_filter)
             .with(fmt_layer)
-            .init();
-
-        info!("Logger system initialized!");
+            .try_init()
+            .is_ok()
+        {
+            info!("Logger system initialized!");
+        }
 
         Self
     }
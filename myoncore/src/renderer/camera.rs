@@ -0,0 +1,254 @@
+use std::cmp::Reverse;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ecs::{Entity, World},
+    scene::GlobalTransform,
+};
+
+/// WGSL declaration of the camera uniform, for pipelines that use
+/// [`super::Renderer::camera_layout`] as bind group 0.
+pub const CAMERA_WGSL: &str = r#"
+struct Camera {
+    view_proj: mat4x4<f32>,
+    view: mat4x4<f32>,
+    proj: mat4x4<f32>,
+    position: vec4<f32>,
+    viewport: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: Camera;
+"#;
+
+/// How an orthographic camera maps world units to pixels.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ScalingMode {
+    /// One world unit is one pixel.
+    WindowSize,
+    /// The viewport is this many world units high, the width follows the aspect ratio.
+    FixedHeight(f32),
+    /// One world unit is a whole number of pixels, the largest that still shows `width` by
+    /// `height` units. Bigger windows show more of the world around them instead of blurring,
+    /// and the camera position is snapped to whole pixels.
+    PixelPerfect { width: u32, height: u32 },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OrthographicProjection {
+    pub scaling: ScalingMode,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for OrthographicProjection {
+    fn default() -> Self {
+        Self {
+            scaling: ScalingMode::WindowSize,
+            near: -1000.0,
+            far: 1000.0,
+        }
+    }
+}
+
+impl OrthographicProjection {
+    /// Pixels per world unit.
+    pub fn scale(&self, viewport: Vec2) -> f32 {
+        match self.scaling {
+            ScalingMode::WindowSize => 1.0,
+            ScalingMode::FixedHeight(height) => viewport.y / height,
+            ScalingMode::PixelPerfect { width, height } => (viewport.x / width.max(1) as f32)
+                .min(viewport.y / height.max(1) as f32)
+                .floor()
+                .max(1.0),
+        }
+    }
+
+    pub fn matrix(&self, viewport: Vec2) -> Mat4 {
+        let scale = self.scale(viewport);
+
+        // Keeping the edges on whole pixels lines texels up with odd-sized windows too.
+        let left = -(viewport.x / 2.0).floor() / scale;
+        let bottom = -(viewport.y / 2.0).floor() / scale;
+
+        Mat4::orthographic_rh(
+            left,
+            left + viewport.x / scale,
+            bottom,
+            bottom + viewport.y / scale,
+            self.near,
+            self.far,
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PerspectiveProjection {
+    /// Vertical field of view, in radians.
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Default for PerspectiveProjection {
+    fn default() -> Self {
+        Self {
+            fov_y: 45f32.to_radians(),
+            near: 0.1,
+            far: 1000.0,
+        }
+    }
+}
+
+impl PerspectiveProjection {
+    pub fn matrix(&self, viewport: Vec2) -> Mat4 {
+        Mat4::perspective_rh(self.fov_y, viewport.x / viewport.y, self.near, self.far)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Projection {
+    Orthographic(OrthographicProjection),
+    Perspective(PerspectiveProjection),
+}
+
+impl Projection {
+    /// The aspect ratio comes from `viewport`, so it follows the window when it is resized.
+    pub fn matrix(&self, viewport: Vec2) -> Mat4 {
+        let viewport = viewport.max(Vec2::ONE);
+
+        match self {
+            Projection::Orthographic(projection) => projection.matrix(viewport),
+            Projection::Perspective(projection) => projection.matrix(viewport),
+        }
+    }
+}
+
+/// Component that renders the world from its entity's [`GlobalTransform`], looking down -Z.
+///
+/// Every frame the engine uploads the active camera with the highest [`Camera::order`] to the
+/// camera uniform of the [`super::Renderer`]. Without one, the uniform holds a pixel-space
+/// projection where (0, 0) is the top-left corner of the target and y points down.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Camera {
+    pub projection: Projection,
+    pub active: bool,
+    /// Which active camera is used, the highest one wins.
+    #[serde(default)]
+    pub order: i32,
+}
+
+impl Camera {
+    pub fn new(projection: Projection) -> Self {
+        Self {
+            projection,
+            active: true,
+            order: 0,
+        }
+    }
+
+    /// 2D camera centered on its position, with y pointing up.
+    pub fn orthographic(scaling: ScalingMode) -> Self {
+        Self::new(Projection::Orthographic(OrthographicProjection {
+            scaling,
+            ..Default::default()
+        }))
+    }
+
+    pub fn perspective(fov_y: f32, near: f32, far: f32) -> Self {
+        Self::new(Projection::Perspective(PerspectiveProjection {
+            fov_y,
+            near,
+            far,
+        }))
+    }
+
+    pub fn active(mut self, active: bool) -> Self {
+        self.active = active;
+        self
+    }
+
+    pub fn order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn view_matrix(&self, transform: &GlobalTransform, viewport: Vec2) -> Mat4 {
+        let mut matrix = transform.matrix();
+
+        if let Projection::Orthographic(projection) = &self.projection
+            && matches!(projection.scaling, ScalingMode::PixelPerfect { .. })
+        {
+            let scale = projection.scale(viewport.max(Vec2::ONE));
+            let snapped = (matrix.w_axis.truncate() * scale).round() / scale;
+
+            matrix.w_axis = snapped.extend(1.0);
+        }
+
+        matrix.inverse()
+    }
+
+    pub fn view_projection(&self, transform: &GlobalTransform, viewport: Vec2) -> Mat4 {
+        self.projection.matrix(viewport) * self.view_matrix(transform, viewport)
+    }
+
+    pub fn uniform(&self, transform: &GlobalTransform, viewport: Vec2) -> CameraUniform {
+        let view = self.view_matrix(transform, viewport);
+        let proj = self.projection.matrix(viewport);
+
+        CameraUniform::new(view, proj, transform.translation(), viewport)
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::orthographic(ScalingMode::WindowSize)
+    }
+}
+
+/// Layout of the camera uniform buffer, matching [`CAMERA_WGSL`].
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug, Pod, Zeroable)]
+pub struct CameraUniform {
+    pub view_proj: Mat4,
+    pub view: Mat4,
+    pub proj: Mat4,
+    pub position: Vec4,
+    /// Width, height, 1 / width, 1 / height.
+    pub viewport: Vec4,
+}
+
+impl CameraUniform {
+    pub fn new(view: Mat4, proj: Mat4, position: Vec3, viewport: Vec2) -> Self {
+        let viewport = viewport.max(Vec2::ONE);
+
+        Self {
+            view_proj: proj * view,
+            view,
+            proj,
+            position: position.extend(1.0),
+            viewport: Vec4::new(viewport.x, viewport.y, 1.0 / viewport.x, 1.0 / viewport.y),
+        }
+    }
+
+    /// Pixel-space projection where (0, 0) is the top-left corner and y points down.
+    pub fn pixel_space(viewport: Vec2) -> Self {
+        let proj = Mat4::orthographic_rh(0.0, viewport.x, viewport.y, 0.0, -1.0, 1.0);
+
+        Self::new(Mat4::IDENTITY, proj, Vec3::ZERO, viewport)
+    }
+}
+
+/// The active [`Camera`] with a [`GlobalTransform`] and the highest [`Camera::order`]. Ties
+/// go to the entity with the lowest index, so the choice doesn't depend on storage order.
+pub fn active_camera(world: &World) -> Option<(Entity, Camera, GlobalTransform)> {
+    world
+        .query::<(Entity, &Camera, &GlobalTransform)>()
+        .iter()
+        .filter(|(_, camera, _)| camera.active)
+        .max_by_key(|(entity, camera, _)| (camera.order, Reverse(*entity)))
+        .map(|(entity, camera, transform)| (entity, *camera, *transform))
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    mem,
+};
+
+use anyhow::Context;
+use wgpu::{CommandEncoder, Device, Queue, TextureFormat, TextureUsages, TextureView};
+
+/// A texture that only lives for one frame of a [`RenderGraph`]. Transient textures whose
+/// passes don't overlap can share the same GPU texture, so their contents don't survive
+/// past the last pass that reads them.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TransientTexture {
+    pub format: TextureFormat,
+    /// Width and height, or `None` for the size of the frame's color target.
+    pub size: Option<(u32, u32)>,
+    pub usage: TextureUsages,
+}
+
+impl TransientTexture {
+    pub fn new(format: TextureFormat) -> Self {
+        Self {
+            format,
+            size: None,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        }
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    pub fn usage(mut self, usage: TextureUsages) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    fn resolve(&self, target_size: (u32, u32)) -> Self {
+        Self {
+            size: Some(self.size.unwrap_or(target_size)),
+            ..*self
+        }
+    }
+}
+
+/// What a pass gets to record its commands.
+pub struct PassContext<'a> {
+    pub encoder: &'a mut CommandEncoder,
+    pub device: &'a Device,
+    pub queue: &'a Queue,
+    pass: &'a str,
+    resources: &'a HashMap<&'a str, (&'a wgpu::Texture, &'a TextureView)>,
+}
+
+impl<'a> PassContext<'a> {
+    /// The view of a resource the pass declared. Panics for anything else.
+    ///
+    /// Views borrow from the graph rather than the context, so they can be used while
+    /// [`Self::encoder`] records a pass.
+    pub fn view(&self, name: &str) -> &'a TextureView {
+        self.resource(name).1
+    }
+
+    pub fn texture(&self, name: &str) -> &'a wgpu::Texture {
+        self.resource(name).0
+    }
+
+    fn resource(&self, name: &str) -> (&'a wgpu::Texture, &'a TextureView) {
+        *self
+            .resources
+            .get(name)
+            .unwrap_or_else(|| panic!("Pass {} didn't declare {name}", self.pass))
+    }
+}
+
+pub(crate) type PassFn = Box<dyn FnOnce(&mut PassContext)>;
+
+struct Pass {
+    name: String,
+    reads: Vec<String>,
+    writes: Vec<String>,
+    run: PassFn,
+    // Runs after every other pass, whatever they read, like the renderer's overlay.
+    last: bool,
+}
+
+pub struct PassBuilder<'g> {
+    graph: &'g mut RenderGraph,
+    pass: Pass,
+}
+
+impl PassBuilder<'_> {
+    pub fn read(mut self, resource: impl Into<String>) -> Self {
+        self.pass.reads.push(resource.into());
+        self
+    }
+
+    pub fn write(mut self, resource: impl Into<String>) -> Self {
+        self.pass.writes.push(resource.into());
+        self
+    }
+
+    pub(crate) fn last(mut self) -> Self {
+        self.pass.last = true;
+        self
+    }
+
+    /// Adds the pass to the graph.
+    pub fn run(mut self, run: impl FnOnce(&mut PassContext) + 'static) {
+        self.pass.run = Box::new(run);
+        self.graph.passes.push(self.pass);
+    }
+}
+
+/// The order a [`RenderGraph`] runs in, and which GPU texture each transient texture uses.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GraphPlan {
+    /// Pass indices in execution order.
+    pub order: Vec<usize>,
+    /// Slot of every transient texture, in the order they were created, or `None` if no pass
+    /// uses it. Textures in the same slot share one GPU texture.
+    pub slots: Vec<Option<usize>>,
+    pub slot_textures: Vec<TransientTexture>,
+}
+
+/// Passes of one frame and the textures they read and write, by name.
+///
+/// [`RenderGraph::TARGET`] and [`RenderGraph::DEPTH`] are the frame's color and depth
+/// targets, [`RenderGraph::OUTPUT`] is the surface they end up in, other names are transient
+/// textures created with [`Self::create_texture`] or views added with [`Self::import`].
+/// Passes run after every pass that writes what they read, and passes that write the same
+/// resource run in the order they were added.
+#[derive(Default)]
+pub struct RenderGraph {
+    passes: Vec<Pass>,
+    transients: Vec<(String, TransientTexture)>,
+    imports: HashMap<String, (wgpu::Texture, TextureView)>,
+}
+
+impl RenderGraph {
+    pub const TARGET: &str = "target";
+    pub const DEPTH: &str = "depth";
+    /// The surface that the frame is presented from. The same texture as [`Self::TARGET`]
+    /// unless the scene is drawn into an HDR or multisampled target that is resolved into it.
+    pub const OUTPUT: &str = "output";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create_texture(&mut self, name: impl Into<String>, texture: TransientTexture) {
+        self.transients.push((name.into(), texture));
+    }
+
+    /// Makes a texture that outlives the frame available to passes, like a shadow map that
+    /// is only rendered now and then.
+    pub fn import(&mut self, name: impl Into<String>, texture: &wgpu::Texture) {
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.imports.insert(name.into(), (texture.clone(), view));
+    }
+
+    pub fn add_pass(&mut self, name: impl Into<String>) -> PassBuilder<'_> {
+        PassBuilder {
+            graph: self,
+            pass: Pass {
+                name: name.into(),
+                reads: Vec::new(),
+                writes: Vec::new(),
+                run: Box::new(|_| {}),
+                last: false,
+            },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    pub fn pass_name(&self, index: usize) -> Option<&str> {
+        self.passes.get(index).map(|pass| pass.name.as_str())
+    }
+
+    /// Orders the passes and assigns transient textures to slots, reusing a slot once the
+    /// last pass using its previous texture has run.
+    pub fn compile(&self, target_size: (u32, u32)) -> anyhow::Result<GraphPlan> {
+        let transients: HashMap<&str, usize> = self
+            .transients
+            .iter()
+            .enumerate()
+            .map(|(index, (name, _))| (name.as_str(), index))
+            .collect();
+
+        for pass in &self.passes {
+            for resource in pass.reads.iter().chain(&pass.writes) {
+                anyhow::ensure!(
+                    resource == Self::TARGET
+                        || resource == Self::DEPTH
+                        || resource == Self::OUTPUT
+                        || transients.contains_key(resource.as_str())
+                        || self.imports.contains_key(resource),
+                    "Pass {} uses unknown resource {resource}",
+                    pass.name
+                );
+            }
+        }
+
+        let mut writers: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, pass) in self.passes.iter().enumerate() {
+            for resource in &pass.writes {
+                writers.entry(resource).or_default().push(index);
+            }
+        }
+
+        let mut dependencies = vec![BTreeSet::new(); self.passes.len()];
+        for (index, pass) in self.passes.iter().enumerate() {
+            for resource in &pass.writes {
+                let earlier = writers[resource.as_str()]
+                    .iter()
+                    .copied()
+                    .take_while(|&writer| writer < index);
+                dependencies[index].extend(earlier);
+            }
+
+            for resource in &pass.reads {
+                let Some(resource_writers) = writers.get(resource.as_str()) else {
+                    anyhow::ensure!(
+                        !transients.contains_key(resource.as_str()),
+                        "Pass {} reads {resource}, which no pass writes",
+                        pass.name
+                    );
+                    continue;
+                };
+
+                // Readers see the final contents, unless they write it themselves.
+                let writes_too = pass.writes.contains(resource);
+                dependencies[index].extend(
+                    resource_writers
+                        .iter()
+                        .copied()
+                        .filter(|&writer| writer != index && (!writes_too || writer < index)),
+                );
+            }
+        }
+
+        let last: Vec<bool> = self.passes.iter().map(|pass| pass.last).collect();
+        for (index, dependencies) in dependencies.iter_mut().enumerate() {
+            if last[index] {
+                dependencies.extend((0..last.len()).filter(|&other| !last[other]));
+            } else {
+                dependencies.retain(|&other| !last[other]);
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.passes.len());
+        let mut done = vec![false; self.passes.len()];
+
+        while order.len() < self.passes.len() {
+            let next = (0..self.passes.len()).find(|&index| {
+                !done[index]
+                    && dependencies[index]
+                        .iter()
+                        .all(|&dependency| done[dependency])
+            });
+
+            let Some(next) = next else {
+                let stuck: Vec<&str> = (0..self.passes.len())
+                    .filter(|&index| !done[index])
+                    .map(|index| self.passes[index].name.as_str())
+                    .collect();
+                anyhow::bail!("Render passes depend on each other: {}", stuck.join(", "));
+            };
+
+            done[next] = true;
+            order.push(next);
+        }
+
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.transients.len()];
+        for (step, &index) in order.iter().enumerate() {
+            let pass = &self.passes[index];
+
+            for resource in pass.reads.iter().chain(&pass.writes) {
+                if let Some(&transient) = transients.get(resource.as_str()) {
+                    let lifetime = lifetimes[transient].get_or_insert((step, step));
+                    lifetime.1 = step;
+                }
+            }
+        }
+
+        let mut by_first_use: Vec<usize> = (0..self.transients.len()).collect();
+        by_first_use.sort_by_key(|&transient| lifetimes[transient].map(|(first, _)| first));
+
+        let mut slots = vec![None; self.transients.len()];
+        let mut slot_textures = Vec::new();
+        let mut slot_free_after: Vec<usize> = Vec::new();
+
+        for transient in by_first_use {
+            let Some((first, last)) = lifetimes[transient] else {
+                continue;
+            };
+
+            let texture = self.transients[transient].1.resolve(target_size);
+
+            let reusable = (0..slot_textures.len())
+                .find(|&slot| slot_textures[slot] == texture && slot_free_after[slot] < first);
+
+            let slot = reusable.unwrap_or_else(|| {
+                slot_textures.push(texture);
+                slot_free_after.push(0);
+                slot_textures.len() - 1
+            });
+
+            slots[transient] = Some(slot);
+            slot_free_after[slot] = last;
+        }
+
+        Ok(GraphPlan {
+            order,
+            slots,
+            slot_textures,
+        })
+    }
+}
+
+/// GPU textures behind transient slots, kept between frames.
+#[derive(Default)]
+pub(crate) struct TransientPool {
+    textures: Vec<(TransientTexture, wgpu::Texture, TextureView)>,
+}
+
+impl TransientPool {
+    /// Gives every slot a texture at the same index, reusing the ones from the last frame
+    /// and dropping the ones that aren't needed anymore.
+    fn acquire(&mut self, device: &Device, slots: &[TransientTexture]) {
+        let mut previous: Vec<_> = mem::take(&mut self.textures)
+            .into_iter()
+            .map(Some)
+            .collect();
+
+        for slot in slots {
+            let existing = previous
+                .iter_mut()
+                .find(|entry| {
+                    entry
+                        .as_ref()
+                        .is_some_and(|(texture, _, _)| texture == slot)
+                })
+                .and_then(Option::take);
+
+            let entry = existing.unwrap_or_else(|| {
+                let (width, height) = slot.size.expect("Slots have resolved sizes");
+                tracing::debug!("Creating transient texture ({width}x{height})...");
+
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Transient Texture"),
+                    size: wgpu::Extent3d {
+                        width: width.max(1),
+                        height: height.max(1),
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: slot.format,
+                    usage: slot.usage,
+                    view_formats: &[],
+                });
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+                (*slot, texture, view)
+            });
+
+            self.textures.push(entry);
+        }
+    }
+}
+
+/// Frame resources that [`execute`] hands to passes.
+pub(crate) struct FrameTargets<'a> {
+    pub device: &'a Device,
+    pub queue: &'a Queue,
+    pub encoder: &'a mut CommandEncoder,
+    pub target: (&'a wgpu::Texture, &'a TextureView),
+    pub depth: Option<(&'a wgpu::Texture, &'a TextureView)>,
+    pub output: (&'a wgpu::Texture, &'a TextureView),
+}
+
+pub(crate) fn execute(
+    graph: RenderGraph,
+    pool: &mut TransientPool,
+    frame: FrameTargets,
+) -> anyhow::Result<()> {
+    let target_size = (frame.target.0.width(), frame.target.0.height());
+    let plan = graph.compile(target_size)?;
+    pool.acquire(frame.device, &plan.slot_textures);
+
+    let mut resources: HashMap<&str, (&wgpu::Texture, &TextureView)> = HashMap::new();
+    resources.insert(RenderGraph::TARGET, frame.target);
+    if let Some(depth) = frame.depth {
+        resources.insert(RenderGraph::DEPTH, depth);
+    }
+    resources.insert(RenderGraph::OUTPUT, frame.output);
+    for (name, (texture, view)) in &graph.imports {
+        resources.insert(name, (texture, view));
+    }
+    for ((name, _), slot) in graph.transients.iter().zip(&plan.slots) {
+        let Some(slot) = slot else {
+            continue;
+        };
+
+        let (_, texture, view) = &pool.textures[*slot];
+        resources.insert(name, (texture, view));
+    }
+
+    let mut passes: Vec<Option<Pass>> = graph.passes.into_iter().map(Some).collect();
+
+    for index in plan.order {
+        let pass = passes[index].take().expect("Passes run once");
+
+        let declared: HashMap<&str, _> = pass
+            .reads
+            .iter()
+            .chain(&pass.writes)
+            .map(|name| {
+                resources
+                    .get(name.as_str())
+                    .map(|resource| (name.as_str(), *resource))
+                    .with_context(|| format!("Pass {} uses {name}, which isn't there", pass.name))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let mut context = PassContext {
+            encoder: &mut *frame.encoder,
+            device: frame.device,
+            queue: frame.queue,
+            pass: &pass.name,
+            resources: &declared,
+        };
+
+        (pass.run)(&mut context);
+    }
+
+    Ok(())
+}
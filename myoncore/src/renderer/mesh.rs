@@ -0,0 +1,506 @@
+use std::collections::{HashMap, HashSet};
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3, Vec4};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, RenderPipeline, TextureFormat};
+
+use crate::{
+    assets::{AssetServer, Material, Mesh, ModelMesh, Vertex},
+    ecs::World,
+    scene::GlobalTransform,
+};
+
+use super::{CAMERA_WGSL, Renderer, texture::Texture};
+
+const INITIAL_DRAW_CAPACITY: u64 = 64;
+
+/// One directional light plus constant ambient light, in linear color.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Lighting {
+    /// The direction the light travels in.
+    pub direction: Vec3,
+    pub color: Vec3,
+    pub ambient: Vec3,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            direction: Vec3::new(-0.4, -1.0, -0.3).normalize(),
+            color: Vec3::splat(3.0),
+            ambient: Vec3::splat(0.1),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct DrawUniform {
+    model: Mat4,
+    normal: Mat4,
+    base_color: Vec4,
+    emissive: Vec4,
+    params: Vec4,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct LightingUniform {
+    direction: Vec4,
+    color: Vec4,
+    ambient: Vec4,
+}
+
+impl From<&Lighting> for LightingUniform {
+    fn from(lighting: &Lighting) -> Self {
+        Self {
+            direction: lighting.direction.normalize_or_zero().extend(0.0),
+            color: lighting.color.extend(1.0),
+            ambient: lighting.ambient.extend(1.0),
+        }
+    }
+}
+
+/// Texture ids of a material, white where it has none.
+type MaterialKey = [u64; 3];
+
+struct QueuedDraw {
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    index_count: u32,
+    material: MaterialKey,
+    uniform: DrawUniform,
+}
+
+/// Draws opaque [`Mesh`]es with a metallic-roughness PBR pipeline, lit by [`Lighting`] and
+/// seen through the camera of the [`Renderer`].
+///
+/// Depth testing uses [`Renderer::depth_texture_view`], so the engine needs a depth format
+/// with a depth aspect.
+pub struct MeshBatch {
+    device: Device,
+    pipeline: RenderPipeline,
+    material_layout: BindGroupLayout,
+    draw_layout: BindGroupLayout,
+    draw_buffer: Buffer,
+    draw_bind_group: BindGroup,
+    draw_stride: u64,
+    draw_capacity: u64,
+    lighting_buffer: Buffer,
+    material_bind_groups: HashMap<MaterialKey, BindGroup>,
+    used_materials: HashSet<MaterialKey>,
+    queued: Vec<QueuedDraw>,
+    white: Texture,
+    lighting: Lighting,
+    depth_format: TextureFormat,
+    frame_index: u64,
+    draw_cursor: u64,
+}
+
+impl MeshBatch {
+    pub fn new(renderer: &Renderer) -> Self {
+        let device = renderer.device().clone();
+
+        let depth_format = renderer
+            .depth_format()
+            .filter(TextureFormat::has_depth_aspect)
+            .expect("MeshBatch needs a depth format, see EngineConfig::depth_format");
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mesh Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!("{CAMERA_WGSL}{}", include_str!("mesh.wgsl")).into(),
+            ),
+        });
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let sampler_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        };
+
+        let material_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Mesh Material Layout"),
+            entries: &[
+                texture_entry(0),
+                sampler_entry(1),
+                texture_entry(2),
+                sampler_entry(3),
+                texture_entry(4),
+                sampler_entry(5),
+            ],
+        });
+
+        let draw_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Mesh Draw Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<DrawUniform>() as u64),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(
+                            size_of::<LightingUniform>() as u64
+                        ),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mesh Pipeline Layout"),
+            bind_group_layouts: &[renderer.camera_layout(), &material_layout, &draw_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Mesh Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[Vertex::layout()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: renderer.format(),
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: renderer.sample_count(),
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let draw_stride = (size_of::<DrawUniform>() as u64)
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+
+        let lighting_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Mesh Lighting Buffer"),
+            size: size_of::<LightingUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let draw_buffer = Self::create_draw_buffer(&device, draw_stride, INITIAL_DRAW_CAPACITY);
+        let draw_bind_group =
+            Self::create_draw_bind_group(&device, &draw_layout, &draw_buffer, &lighting_buffer);
+
+        let white = Texture::white(&device, renderer.queue());
+
+        Self {
+            device,
+            pipeline,
+            material_layout,
+            draw_layout,
+            draw_buffer,
+            draw_bind_group,
+            draw_stride,
+            draw_capacity: INITIAL_DRAW_CAPACITY,
+            lighting_buffer,
+            material_bind_groups: HashMap::new(),
+            used_materials: HashSet::new(),
+            queued: Vec::new(),
+            white,
+            lighting: Lighting::default(),
+            depth_format,
+            frame_index: u64::MAX,
+            draw_cursor: 0,
+        }
+    }
+
+    pub fn lighting(&self) -> &Lighting {
+        &self.lighting
+    }
+
+    pub fn set_lighting(&mut self, lighting: Lighting) {
+        self.lighting = lighting;
+    }
+
+    pub fn draw(&mut self, mesh: &Mesh, material: &Material, transform: Mat4) {
+        let textures = [
+            &material.base_color_texture,
+            &material.metallic_roughness_texture,
+            &material.emissive_texture,
+        ]
+        .map(|texture| texture.as_ref().unwrap_or(&self.white));
+        let key = textures.map(Texture::id);
+
+        if !self.material_bind_groups.contains_key(&key) {
+            let bind_group = self.create_material_bind_group(textures);
+            self.material_bind_groups.insert(key, bind_group);
+        }
+
+        self.queued.push(QueuedDraw {
+            vertex_buffer: mesh.vertex_buffer.clone(),
+            index_buffer: mesh.index_buffer.clone(),
+            index_count: mesh.index_count,
+            material: key,
+            uniform: DrawUniform {
+                model: transform,
+                normal: transform.inverse().transpose(),
+                base_color: material.base_color,
+                emissive: material.emissive.extend(0.0),
+                params: Vec4::new(material.metallic, material.roughness, 0.0, 0.0),
+            },
+        });
+    }
+
+    /// Queues every entity with a [`ModelMesh`] and a [`GlobalTransform`] whose model is
+    /// loaded.
+    pub fn draw_world(&mut self, world: &World, assets: &AssetServer) {
+        for (transform, model_mesh) in world.query::<(&GlobalTransform, &ModelMesh)>().iter() {
+            let Some(model) = assets.get(model_mesh.model) else {
+                continue;
+            };
+
+            for primitive in model.meshes.get(model_mesh.mesh).into_iter().flatten() {
+                self.draw(
+                    &primitive.mesh,
+                    &model.materials[primitive.material],
+                    transform.matrix(),
+                );
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    /// Records the queued meshes into the current frame. Can be called several times per
+    /// frame, later flushes are depth tested against earlier ones.
+    pub fn flush(&mut self, renderer: &mut Renderer) {
+        if self.queued.is_empty() {
+            return;
+        }
+
+        if self.frame_index != renderer.frame_index() {
+            self.frame_index = renderer.frame_index();
+            self.draw_cursor = 0;
+
+            let mut used = std::mem::take(&mut self.used_materials);
+            used.extend(self.queued.iter().map(|draw| draw.material));
+            self.material_bind_groups
+                .retain(|key, _| used.contains(key));
+        }
+
+        self.queued.sort_by_key(|draw| draw.material);
+        self.reserve(renderer, self.queued.len() as u64);
+
+        let mut uniforms = vec![0; self.queued.len() * self.draw_stride as usize];
+        for (draw, bytes) in self
+            .queued
+            .iter()
+            .zip(uniforms.chunks_exact_mut(self.draw_stride as usize))
+        {
+            bytes[..size_of::<DrawUniform>()].copy_from_slice(bytemuck::bytes_of(&draw.uniform));
+        }
+
+        let first_offset = self.draw_cursor * self.draw_stride;
+        renderer
+            .queue()
+            .write_buffer(&self.draw_buffer, first_offset, &uniforms);
+        renderer.queue().write_buffer(
+            &self.lighting_buffer,
+            0,
+            bytemuck::bytes_of(&LightingUniform::from(&self.lighting)),
+        );
+        self.draw_cursor += self.queued.len() as u64;
+
+        let depth_view = renderer
+            .depth_texture_view
+            .as_ref()
+            .expect("Depth texture missing");
+        let texture_view = renderer.texture_view.as_ref().expect("TextureView missing");
+        let camera_bind_group = renderer.camera_bind_group().clone();
+        let encoder = renderer
+            .command_encoder
+            .as_mut()
+            .expect("CommandEncoder missing");
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Mesh Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: texture_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: self.depth_format.has_stencil_aspect().then_some(
+                        wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    ),
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &camera_bind_group, &[]);
+
+            let mut material = None;
+            for (i, draw) in self.queued.iter().enumerate() {
+                if material != Some(draw.material) {
+                    material = Some(draw.material);
+                    render_pass.set_bind_group(1, &self.material_bind_groups[&draw.material], &[]);
+                }
+
+                let offset = first_offset + i as u64 * self.draw_stride;
+                render_pass.set_bind_group(2, &self.draw_bind_group, &[offset as u32]);
+                render_pass.set_vertex_buffer(0, draw.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(draw.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..draw.index_count, 0, 0..1);
+            }
+        }
+
+        self.used_materials
+            .extend(self.queued.iter().map(|draw| draw.material));
+        self.queued.clear();
+    }
+
+    fn reserve(&mut self, renderer: &Renderer, draws: u64) {
+        // Buffers recorded earlier in this frame are kept alive by the command encoder, so
+        // growing just starts writing into a fresh buffer.
+        if self.draw_cursor + draws > self.draw_capacity {
+            self.draw_capacity = (self.draw_cursor + draws).next_power_of_two();
+            self.draw_buffer =
+                Self::create_draw_buffer(renderer.device(), self.draw_stride, self.draw_capacity);
+            self.draw_bind_group = Self::create_draw_bind_group(
+                renderer.device(),
+                &self.draw_layout,
+                &self.draw_buffer,
+                &self.lighting_buffer,
+            );
+            self.draw_cursor = 0;
+        }
+    }
+
+    fn create_material_bind_group(&self, textures: [&Texture; 3]) -> BindGroup {
+        let [base_color, metallic_roughness, emissive] = textures;
+
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Mesh Material Bind Group"),
+            layout: &self.material_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&base_color.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&base_color.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&metallic_roughness.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&metallic_roughness.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&emissive.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::Sampler(&emissive.sampler),
+                },
+            ],
+        })
+    }
+
+    fn create_draw_buffer(device: &Device, stride: u64, capacity: u64) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Mesh Draw Buffer"),
+            size: stride * capacity,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_draw_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        draw_buffer: &Buffer,
+        lighting_buffer: &Buffer,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Mesh Draw Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: draw_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(size_of::<DrawUniform>() as u64),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: lighting_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+}
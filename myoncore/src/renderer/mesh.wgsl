@@ -0,0 +1,111 @@
+// Prefixed with CAMERA_WGSL.
+
+struct Draw {
+    model: mat4x4<f32>,
+    normal: mat4x4<f32>,
+    base_color: vec4<f32>,
+    emissive: vec4<f32>,
+    // Metallic, roughness.
+    params: vec4<f32>,
+};
+
+struct Lighting {
+    direction: vec4<f32>,
+    color: vec4<f32>,
+    ambient: vec4<f32>,
+};
+
+@group(1) @binding(0)
+var base_color_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var base_color_sampler: sampler;
+@group(1) @binding(2)
+var metallic_roughness_texture: texture_2d<f32>;
+@group(1) @binding(3)
+var metallic_roughness_sampler: sampler;
+@group(1) @binding(4)
+var emissive_texture: texture_2d<f32>;
+@group(1) @binding(5)
+var emissive_sampler: sampler;
+
+@group(2) @binding(0)
+var<uniform> draw: Draw;
+@group(2) @binding(1)
+var<uniform> lighting: Lighting;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+const PI: f32 = 3.14159265;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    let world_position = draw.model * vec4<f32>(in.position, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * world_position;
+    out.world_position = world_position.xyz;
+    out.normal = (draw.normal * vec4<f32>(in.normal, 0.0)).xyz;
+    out.uv = in.uv;
+
+    return out;
+}
+
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a = roughness * roughness;
+    let a2 = a * a;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+
+    return a2 / (PI * d * d);
+}
+
+fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+
+    return n_dot_v / (n_dot_v * (1.0 - k) + k) * n_dot_l / (n_dot_l * (1.0 - k) + k);
+}
+
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(1.0 - cos_theta, 5.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let base_color = draw.base_color * textureSample(base_color_texture, base_color_sampler, in.uv);
+    let metallic_roughness = textureSample(metallic_roughness_texture, metallic_roughness_sampler, in.uv);
+    let emissive = draw.emissive.rgb * textureSample(emissive_texture, emissive_sampler, in.uv).rgb;
+
+    let metallic = clamp(draw.params.x * metallic_roughness.b, 0.0, 1.0);
+    let roughness = clamp(draw.params.y * metallic_roughness.g, 0.04, 1.0);
+
+    let n = normalize(in.normal);
+    let v = normalize(camera.position.xyz - in.world_position);
+    let l = normalize(-lighting.direction.xyz);
+    let h = normalize(v + l);
+
+    let n_dot_l = max(dot(n, l), 0.0);
+    let n_dot_v = max(dot(n, v), 1e-4);
+    let n_dot_h = max(dot(n, h), 0.0);
+
+    let f0 = mix(vec3<f32>(0.04), base_color.rgb, metallic);
+    let fresnel = fresnel_schlick(max(dot(h, v), 0.0), f0);
+    let specular = distribution_ggx(n_dot_h, roughness) * geometry_smith(n_dot_v, n_dot_l, roughness)
+        * fresnel / (4.0 * n_dot_v * n_dot_l + 1e-4);
+    let diffuse = (1.0 - fresnel) * (1.0 - metallic) * base_color.rgb / PI;
+
+    let color = (diffuse + specular) * lighting.color.rgb * n_dot_l
+        + lighting.ambient.rgb * base_color.rgb
+        + emissive;
+
+    return vec4<f32>(color, base_color.a);
+}
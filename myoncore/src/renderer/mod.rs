@@ -1,61 +1,684 @@
-use std::iter;
+pub mod camera;
+pub mod graph;
+pub mod mesh;
+pub mod mipmap;
+pub mod post;
+pub mod sprite;
+pub mod texture;
 
-use wgpu::{CommandEncoder, SurfaceTexture, TextureView};
+use std::{iter, mem, path::Path, sync::mpsc};
 
-use crate::graphics::Graphics;
+use anyhow::Context;
+use glam::Vec2;
+use image::RgbaImage;
+use wgpu::{
+    Adapter, BindGroup, BindGroupLayout, Buffer, CommandEncoder, Device, Queue, SurfaceTexture,
+    TextureFormat, TextureView,
+};
+
+use crate::{graphics::Graphics, scene::GlobalTransform};
+
+use graph::{FrameTargets, PassFn, TransientPool};
+use post::PostProcessor;
+
+pub use camera::{
+    CAMERA_WGSL, Camera, CameraUniform, OrthographicProjection, PerspectiveProjection, Projection,
+    ScalingMode,
+};
+pub use graph::{GraphPlan, PassBuilder, PassContext, RenderGraph, TransientTexture};
+pub use mesh::{Lighting, MeshBatch};
+pub use post::{Bloom, ColorGrading, HDR_FORMAT, PostProcess, Tonemapping, Vignette};
+pub use sprite::{Sprite, SpriteBatch};
+pub use texture::Texture;
+
+// Name of the HDR texture that a multisampled HDR target resolves into.
+const MSAA_RESOLVED: &str = "msaa_resolved";
+
+/// Depth format used when [`crate::EngineConfig::depth_format`] isn't changed.
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
 
 pub struct Renderer {
     pub surface_texture: Option<SurfaceTexture>,
+    /// View that the scene is drawn into. With [`Self::hdr`], this is an [`HDR_FORMAT`]
+    /// texture, with [`Self::msaa_samples`] a multisampled one, otherwise the same texture as
+    /// [`Self::output_view`].
     pub texture_view: Option<TextureView>,
+    /// View of the surface, what gets presented at the end of the frame.
+    pub output_view: Option<TextureView>,
+    /// View of the depth/stencil texture, the same size as [`Self::texture_view`]. Cleared
+    /// at the start of every frame.
+    pub depth_texture_view: Option<TextureView>,
     pub command_encoder: Option<CommandEncoder>,
-    graphics: *mut Graphics,
+    /// Passes of the current frame, run by [`Self::execute_graph`]. Emptied at the start of
+    /// every frame.
+    pub graph: RenderGraph,
+    overlay: Option<PassFn>,
+    /// Effects used to resolve the HDR target. Ignored without [`Self::hdr`].
+    pub post_process: PostProcess,
+    target: Option<wgpu::Texture>,
+    depth_texture: Option<wgpu::Texture>,
+    hdr_texture: Option<wgpu::Texture>,
+    msaa_texture: Option<wgpu::Texture>,
+    post: Option<PostProcessor>,
+    format: TextureFormat,
+    output_format: TextureFormat,
+    depth_format: Option<TextureFormat>,
+    msaa_samples: u32,
+    sample_count: u32,
+    transient_pool: TransientPool,
+    frame_index: u64,
+    camera: CameraUniform,
+    camera_buffer: Buffer,
+    camera_layout: BindGroupLayout,
+    camera_bind_group: BindGroup,
+    adapter: Adapter,
+    device: Device,
+    queue: Queue,
 }
 
 impl Renderer {
-    pub unsafe fn new(graphics: *mut Graphics) -> Self {
+    /// Without a `depth_format`, there is no depth/stencil texture.
+    pub fn new(graphics: &Graphics, depth_format: Option<TextureFormat>) -> Self {
+        let device = &graphics.device;
+
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Camera Buffer"),
+            size: size_of::<CameraUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: wgpu::BufferSize::new(size_of::<CameraUniform>() as u64),
+                },
+                count: None,
+            }],
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let format = graphics
+            .surface_format
+            .expect("Failed to get surface_format!");
+
         Self {
             surface_texture: None,
             texture_view: None,
+            output_view: None,
+            depth_texture_view: None,
             command_encoder: None,
-	    graphics,
+            graph: RenderGraph::new(),
+            overlay: None,
+            post_process: PostProcess::default(),
+            target: None,
+            depth_texture: None,
+            hdr_texture: None,
+            msaa_texture: None,
+            post: None,
+            format,
+            output_format: format,
+            depth_format,
+            msaa_samples: 1,
+            sample_count: 1,
+            transient_pool: TransientPool::default(),
+            frame_index: 0,
+            camera: CameraUniform::pixel_space(Vec2::ONE),
+            camera_buffer,
+            camera_layout,
+            camera_bind_group,
+            adapter: graphics.adapter.clone(),
+            device: graphics.device.clone(),
+            queue: graphics.queue.clone(),
+        }
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &Queue {
+        &self.queue
+    }
+
+    /// Draws the scene into an [`HDR_FORMAT`] texture, which [`Self::execute_graph`] resolves
+    /// into the surface with [`Self::post_process`].
+    pub fn hdr(mut self, hdr: bool) -> Self {
+        if hdr {
+            self.post = Some(PostProcessor::new(
+                &self.device,
+                &self.queue,
+                self.output_format,
+            ));
+            self.format = HDR_FORMAT;
+        } else {
+            self.post = None;
+            self.hdr_texture = None;
+            self.format = self.output_format;
         }
+
+        self.update_sample_count();
+        self
+    }
+
+    /// Draws the scene with `samples` samples per pixel, resolved by [`Self::execute_graph`].
+    /// Falls back to the highest count below that the formats support.
+    pub fn msaa_samples(mut self, samples: u32) -> Self {
+        self.msaa_samples = samples.max(1);
+        self.update_sample_count();
+        self
+    }
+
+    /// Sample count of [`Self::texture_view`] and [`Self::depth_texture_view`], which
+    /// pipelines drawing the scene need to match.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.post.is_some()
+    }
+
+    /// Format of [`Self::texture_view`], which pipelines drawing the scene should use.
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Format of [`Self::output_view`].
+    pub fn output_format(&self) -> TextureFormat {
+        self.output_format
+    }
+
+    pub fn depth_format(&self) -> Option<TextureFormat> {
+        self.depth_format
     }
 
-    pub unsafe fn begin_frame(&mut self) -> Result<(), wgpu::SurfaceError> {
-	unsafe {
-            self.surface_texture = Some((*self.graphics).surface.get_current_texture()?);
+    pub fn depth_texture(&self) -> Option<&wgpu::Texture> {
+        self.depth_texture.as_ref()
+    }
 
-            self.texture_view = Some(
-		self.surface_texture
-                    .as_ref()
-                    .expect("Failed to acquire next texture")
-                    .texture
-                    .create_view(&wgpu::TextureViewDescriptor::default()),
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    pub fn target_size(&self) -> Option<(u32, u32)> {
+        self.target
+            .as_ref()
+            .map(|texture| (texture.width(), texture.height()))
+    }
+
+    /// The camera of the current frame.
+    pub fn camera(&self) -> &CameraUniform {
+        &self.camera
+    }
+
+    /// Bind group layout of the camera uniform, see [`CAMERA_WGSL`].
+    pub fn camera_layout(&self) -> &BindGroupLayout {
+        &self.camera_layout
+    }
+
+    pub fn camera_bind_group(&self) -> &BindGroup {
+        &self.camera_bind_group
+    }
+
+    /// Uploads `camera`, with the aspect ratio of the current target. The engine already does
+    /// this for the active camera in the world before [`crate::AppHandler::on_render`].
+    ///
+    /// The buffer is written before the frame's commands run, so pipelines bound to
+    /// [`Self::camera_bind_group`] only see the last camera set in a frame.
+    pub fn set_camera(&mut self, camera: &Camera, transform: &GlobalTransform) {
+        let uniform = camera.uniform(transform, self.viewport());
+        self.set_camera_uniform(uniform);
+    }
+
+    pub fn set_camera_uniform(&mut self, uniform: CameraUniform) {
+        self.camera = uniform;
+        self.queue
+            .write_buffer(&self.camera_buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    fn viewport(&self) -> Vec2 {
+        let (width, height) = self.target_size().expect("Renderer is not inside a frame");
+        Vec2::new(width as f32, height as f32)
+    }
+
+    pub fn begin_frame(&mut self, graphics: &Graphics) -> Result<(), wgpu::SurfaceError> {
+        let target = match graphics.surface.as_ref() {
+            Some(surface) => {
+                let surface_texture = surface.get_current_texture()?;
+                let texture = surface_texture.texture.clone();
+                self.surface_texture = Some(surface_texture);
+
+                texture
+            }
+
+            None => graphics
+                .offscreen_texture
+                .clone()
+                .expect("Offscreen texture missing"),
+        };
+
+        let output_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        self.target = Some(target);
+        let hdr_view = self.is_hdr().then(|| self.prepare_hdr());
+        self.texture_view = Some(if self.sample_count > 1 {
+            self.prepare_msaa()
+        } else {
+            hdr_view.unwrap_or_else(|| output_view.clone())
+        });
+        self.output_view = Some(output_view);
+        self.graph = RenderGraph::new();
+        self.overlay = None;
+        self.frame_index += 1;
+
+        self.set_camera_uniform(CameraUniform::pixel_space(self.viewport()));
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.prepare_depth(&mut encoder);
+        self.command_encoder = Some(encoder);
+
+        Ok(())
+    }
+
+    /// Records the passes of [`Self::graph`] into the command encoder, after anything that
+    /// was recorded directly. With [`Self::msaa_samples`] or [`Self::hdr`], the scene is then
+    /// resolved into the surface, and the pass set with [`Self::set_overlay`] runs last.
+    ///
+    /// If the passes of [`Self::graph`] don't compile, they are skipped and the error is
+    /// returned, but the resolve and overlay passes still run.
+    pub fn execute_graph(&mut self) -> anyhow::Result<()> {
+        let mut graph = mem::take(&mut self.graph);
+        let target = self
+            .target
+            .as_ref()
+            .context("Renderer is not inside a frame")?;
+        let target_size = (target.width(), target.height());
+
+        let app_result = graph.compile(target_size).map(|_| ());
+        if app_result.is_err() {
+            graph = RenderGraph::new();
+        }
+
+        let mut resolved = RenderGraph::TARGET;
+        if self.sample_count > 1 {
+            resolved = match self.hdr_texture.as_ref() {
+                Some(hdr_texture) => {
+                    graph.import(MSAA_RESOLVED, hdr_texture);
+                    MSAA_RESOLVED
+                }
+                None => RenderGraph::OUTPUT,
+            };
+
+            graph
+                .add_pass("MSAA Resolve")
+                .read(RenderGraph::TARGET)
+                .write(resolved)
+                .run(move |ctx| {
+                    ctx.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("MSAA Resolve Pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: ctx.view(RenderGraph::TARGET),
+                            resolve_target: Some(ctx.view(resolved)),
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: wgpu::StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                        occlusion_query_set: None,
+                        timestamp_writes: None,
+                    });
+                });
+        }
+
+        if let Some(post) = self.post.as_ref() {
+            post.add_passes(
+                &mut graph,
+                resolved,
+                &self.queue,
+                &self.post_process,
+                target_size,
             );
+        }
 
+        let output = (
+            target,
+            self.output_view.as_ref().context("Output view missing")?,
+        );
+        let scene_texture = if self.sample_count > 1 {
+            self.msaa_texture.as_ref()
+        } else {
+            self.hdr_texture.as_ref()
+        };
+        let scene = scene_texture.zip(self.texture_view.as_ref());
+
+        if let Some(overlay) = self.overlay.take() {
+            graph
+                .add_pass("Overlay")
+                .write(RenderGraph::OUTPUT)
+                .last()
+                .run(overlay);
+        }
+
+        if graph.is_empty() {
+            return app_result;
+        }
+
+        let frame = FrameTargets {
+            device: &self.device,
+            queue: &self.queue,
+            encoder: self
+                .command_encoder
+                .as_mut()
+                .context("Command encoder missing")?,
+            target: scene.unwrap_or(output),
+            depth: self
+                .depth_texture
+                .as_ref()
+                .zip(self.depth_texture_view.as_ref()),
+            output,
+        };
+
+        graph::execute(graph, &mut self.transient_pool, frame)?;
+
+        app_result.context("Skipped the render graph passes of this frame")
+    }
+
+    /// Sets the pass that draws over the finished frame in [`RenderGraph::OUTPUT`], after the
+    /// scene was resolved, such as the GUI. It runs after every pass of [`Self::graph`], even
+    /// ones that read the output. Only lasts until the end of the frame.
+    pub fn set_overlay(&mut self, run: impl FnOnce(&mut PassContext) + 'static) {
+        self.overlay = Some(Box::new(run));
+    }
+
+    pub fn end_frame(&mut self) {
+        self.queue.submit(iter::once(
+            self.command_encoder
+                .take()
+                .expect("Failed to submit command encoder.")
+                .finish(),
+        ));
+
+        if let Some(surface_texture) = self.surface_texture.take() {
+            self.target = None;
+            surface_texture.present();
+        }
+    }
+
+    /// Reads back the current color target as an RGBA image.
+    ///
+    /// When called inside a frame, the commands recorded so far are submitted first so the
+    /// capture contains everything drawn up to this point.
+    pub fn capture_frame(&mut self) -> anyhow::Result<RgbaImage> {
+        let texture = self.target.as_ref().context("No color target to capture")?;
+
+        anyhow::ensure!(
+            texture.usage().contains(wgpu::TextureUsages::COPY_SRC),
+            "Color target doesn't support COPY_SRC"
+        );
+
+        let swizzle = match texture.format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => anyhow::bail!("Unsupported color target format for capture: {format:?}"),
+        };
+
+        let width = texture.width();
+        let height = texture.height();
+        let unpadded_bytes_per_row = width * 4;
+        let padded_bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let in_frame = self.command_encoder.is_some();
+        let mut encoder = self.command_encoder.take().unwrap_or_else(|| {
+            self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None })
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        self.queue.submit(iter::once(encoder.finish()));
+
+        if in_frame {
             self.command_encoder = Some(
-		(*self.graphics)
-                    .device
+                self.device
                     .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None }),
             );
+        }
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+        self.device.poll(wgpu::PollType::Wait)?;
+        receiver.recv()??;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+
+            for row in data.chunks_exact(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        buffer.unmap();
+
+        if swizzle {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        RgbaImage::from_raw(width, height, pixels).context("Captured frame has an invalid size")
+    }
+
+    /// Captures the current color target and writes it to `path` as a PNG.
+    pub fn save_frame(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        self.capture_frame()?
+            .save_with_format(path, image::ImageFormat::Png)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        tracing::info!("Saved frame to {}", path.display());
+
+        Ok(())
+    }
+
+    /// Picks the highest supported sample count up to the requested one, for both the color
+    /// and depth formats.
+    fn update_sample_count(&mut self) {
+        let supported = |format: TextureFormat, count: u32| {
+            self.adapter
+                .get_texture_format_features(format)
+                .flags
+                .sample_count_supported(count)
+        };
+
+        let mut count = 1 << self.msaa_samples.ilog2();
+        while count > 1
+            && !(supported(self.format, count)
+                && self
+                    .depth_format
+                    .is_none_or(|depth| supported(depth, count)))
+        {
+            count /= 2;
+        }
+
+        if count < self.msaa_samples {
+            tracing::warn!(
+                "{}x MSAA isn't supported, using {count}x instead",
+                self.msaa_samples
+            );
+        }
 
-            Ok(())
-	}
+        self.sample_count = count;
     }
 
-    pub unsafe fn end_frame(&mut self) {
-	unsafe {
-            (*self.graphics).queue.submit(iter::once(
-		self.command_encoder
-                    .take()
-                    .expect("Failed to submit command encoder.")
-                    .finish(),
-            ));
+    /// Recreates the HDR texture when the target changed size, and returns a view of it.
+    fn prepare_hdr(&mut self) -> TextureView {
+        let size = self.target_size().expect("Renderer is not inside a frame");
+        ensure_texture(
+            &self.device,
+            &mut self.hdr_texture,
+            "HDR Texture",
+            size,
+            HDR_FORMAT,
+            1,
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+        );
 
-            self.surface_texture
-		.take()
-		.expect("Failed to present surface texture")
-		.present();
-	}
+        self.hdr_texture
+            .as_ref()
+            .expect("HDR texture missing")
+            .create_view(&wgpu::TextureViewDescriptor::default())
     }
+
+    /// Recreates the multisampled color texture when the target changed size, and returns a
+    /// view of it.
+    fn prepare_msaa(&mut self) -> TextureView {
+        let size = self.target_size().expect("Renderer is not inside a frame");
+        ensure_texture(
+            &self.device,
+            &mut self.msaa_texture,
+            "MSAA Texture",
+            size,
+            self.format,
+            self.sample_count,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        self.msaa_texture
+            .as_ref()
+            .expect("MSAA texture missing")
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// Recreates the depth texture when the target changed size, and clears it.
+    fn prepare_depth(&mut self, encoder: &mut CommandEncoder) {
+        let Some(format) = self.depth_format else {
+            return;
+        };
+
+        let size = self.target_size().expect("Renderer is not inside a frame");
+        let recreated = ensure_texture(
+            &self.device,
+            &mut self.depth_texture,
+            "Depth Texture",
+            size,
+            format,
+            self.sample_count,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+
+        if recreated {
+            self.depth_texture_view = self
+                .depth_texture
+                .as_ref()
+                .map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        }
+
+        let view = self
+            .depth_texture_view
+            .as_ref()
+            .expect("Depth texture missing");
+        let stencil_ops = format.has_stencil_aspect().then_some(wgpu::Operations {
+            load: wgpu::LoadOp::Clear(0),
+            store: wgpu::StoreOp::Store,
+        });
+        let depth_ops = format.has_depth_aspect().then_some(wgpu::Operations {
+            load: wgpu::LoadOp::Clear(1.0),
+            store: wgpu::StoreOp::Store,
+        });
+
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth Clear Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view,
+                depth_ops,
+                stencil_ops,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+    }
+}
+
+/// Creates `texture` again unless it already matches the size, format and sample count.
+/// Returns whether it was recreated.
+fn ensure_texture(
+    device: &Device,
+    texture: &mut Option<wgpu::Texture>,
+    label: &str,
+    (width, height): (u32, u32),
+    format: TextureFormat,
+    sample_count: u32,
+    usage: wgpu::TextureUsages,
+) -> bool {
+    let current = texture.as_ref().is_some_and(|texture| {
+        (texture.width(), texture.height()) == (width, height)
+            && texture.format() == format
+            && texture.sample_count() == sample_count
+    });
+
+    if current {
+        return false;
+    }
+
+    tracing::debug!("Creating {label} ({width}x{height}, {sample_count}x)...");
+
+    *texture = Some(device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage,
+        view_formats: &[],
+    }));
+
+    true
 }
@@ -0,0 +1,447 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroupLayout, Buffer, Device, Queue, RenderPipeline, Sampler, ShaderModule, TextureFormat,
+    TextureView,
+};
+
+use super::{
+    graph::{PassContext, RenderGraph, TransientTexture},
+    texture::Texture,
+};
+
+/// Format of the scene target when HDR is on, see [`super::Renderer::hdr`].
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Tonemapping {
+    /// Clamps to the displayable range.
+    None,
+    Reinhard,
+    #[default]
+    Aces,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bloom {
+    pub enabled: bool,
+    /// Brightness above which pixels start to glow.
+    pub threshold: f32,
+    pub intensity: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 1.0,
+            intensity: 0.3,
+        }
+    }
+}
+
+/// Remaps colors through a lookup table after tonemapping.
+///
+/// The LUT is a strip of N slices of N x N texels (e.g. 256x16), red going right within a
+/// slice, green going down and blue picking the slice. It's indexed with sRGB encoded colors,
+/// so a LUT exported from an image editor works as is.
+#[derive(Clone, Default)]
+pub struct ColorGrading {
+    pub enabled: bool,
+    pub lut: Option<Texture>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Vignette {
+    pub enabled: bool,
+    /// How dark the corners get, from 0 to 1.
+    pub intensity: f32,
+    /// Distance from the center where darkening ends, 1 being the corners.
+    pub radius: f32,
+    /// Width of the transition towards the radius.
+    pub smoothness: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            intensity: 0.3,
+            radius: 1.0,
+            smoothness: 0.5,
+        }
+    }
+}
+
+/// Effects applied when resolving the HDR scene into the surface. They can be changed
+/// between frames.
+#[derive(Clone)]
+pub struct PostProcess {
+    pub exposure: f32,
+    pub tonemapping: Tonemapping,
+    pub bloom: Bloom,
+    pub color_grading: ColorGrading,
+    pub vignette: Vignette,
+    pub fxaa: bool,
+}
+
+impl Default for PostProcess {
+    fn default() -> Self {
+        Self {
+            exposure: 1.0,
+            tonemapping: Tonemapping::default(),
+            bloom: Bloom::default(),
+            color_grading: ColorGrading::default(),
+            vignette: Vignette::default(),
+            fxaa: false,
+        }
+    }
+}
+
+const BLOOM: u32 = 1;
+const COLOR_GRADING: u32 = 2;
+const VIGNETTE: u32 = 4;
+const ENCODE_SRGB: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct PostUniform {
+    exposure: f32,
+    bloom_threshold: f32,
+    bloom_intensity: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_smoothness: f32,
+    tonemapping: u32,
+    flags: u32,
+}
+
+/// Shared state of the passes that [`PostProcessor::add_passes`] adds.
+#[derive(Clone)]
+struct PostResources {
+    sampler: Sampler,
+    bind_group_layout: BindGroupLayout,
+    uniform_buffer: Buffer,
+    black: TextureView,
+}
+
+/// Pipelines of the post-processing chain that turns the HDR scene into the final image.
+pub(crate) struct PostProcessor {
+    resources: PostResources,
+    bright: RenderPipeline,
+    blur_horizontal: RenderPipeline,
+    blur_vertical: RenderPipeline,
+    composite: RenderPipeline,
+    fxaa: RenderPipeline,
+    output_format: TextureFormat,
+}
+
+impl PostProcessor {
+    pub(crate) fn new(device: &Device, queue: &Queue, output_format: TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("post.wgsl"));
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Layout"),
+            entries: &[
+                texture_entry(0),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<PostUniform>() as u64),
+                    },
+                    count: None,
+                },
+                texture_entry(3),
+                texture_entry(4),
+            ],
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Uniform Buffer"),
+            size: size_of::<PostUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Stands in for the bloom and LUT textures of effects that are off.
+        let black = Texture::from_rgba8(device, queue, 1, 1, &[0, 0, 0, 255], Some("Black"));
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |entry_point, format| {
+            Self::create_pipeline(device, &layout, &shader, entry_point, format)
+        };
+
+        Self {
+            bright: pipeline("fs_bright", HDR_FORMAT),
+            blur_horizontal: pipeline("fs_blur_horizontal", HDR_FORMAT),
+            blur_vertical: pipeline("fs_blur_vertical", HDR_FORMAT),
+            composite: pipeline("fs_composite", output_format),
+            fxaa: pipeline("fs_fxaa", output_format),
+            resources: PostResources {
+                sampler,
+                bind_group_layout,
+                uniform_buffer,
+                black: black.view,
+            },
+            output_format,
+        }
+    }
+
+    /// Adds the passes of the enabled effects, reading `source` and writing
+    /// [`RenderGraph::OUTPUT`].
+    pub(crate) fn add_passes(
+        &self,
+        graph: &mut RenderGraph,
+        source: &'static str,
+        queue: &Queue,
+        settings: &PostProcess,
+        target_size: (u32, u32),
+    ) {
+        let color_grading = settings.color_grading.enabled && settings.color_grading.lut.is_some();
+
+        let mut flags = 0;
+        if settings.bloom.enabled {
+            flags |= BLOOM;
+        }
+        if color_grading {
+            flags |= COLOR_GRADING;
+        }
+        if settings.vignette.enabled {
+            flags |= VIGNETTE;
+        }
+        if !self.output_format.is_srgb() {
+            flags |= ENCODE_SRGB;
+        }
+
+        let uniform = PostUniform {
+            exposure: settings.exposure,
+            bloom_threshold: settings.bloom.threshold,
+            bloom_intensity: settings.bloom.intensity,
+            vignette_intensity: settings.vignette.intensity,
+            vignette_radius: settings.vignette.radius,
+            vignette_smoothness: settings.vignette.smoothness,
+            tonemapping: settings.tonemapping as u32,
+            flags,
+        };
+        queue.write_buffer(
+            &self.resources.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&uniform),
+        );
+
+        let mut composite = vec![source];
+
+        if settings.bloom.enabled {
+            let (width, height) = target_size;
+            let half =
+                TransientTexture::new(HDR_FORMAT).size(width.div_ceil(2), height.div_ceil(2));
+            graph.create_texture("post_bloom_bright", half);
+            graph.create_texture("post_bloom_blur", half);
+            graph.create_texture("post_bloom", half);
+
+            self.add_pass(
+                graph,
+                "Post Bloom Bright",
+                &self.bright,
+                source,
+                "post_bloom_bright",
+            );
+            self.add_pass(
+                graph,
+                "Post Bloom Blur Horizontal",
+                &self.blur_horizontal,
+                "post_bloom_bright",
+                "post_bloom_blur",
+            );
+            self.add_pass(
+                graph,
+                "Post Bloom Blur Vertical",
+                &self.blur_vertical,
+                "post_bloom_blur",
+                "post_bloom",
+            );
+            composite.push("post_bloom");
+        }
+
+        let lut = settings
+            .color_grading
+            .lut
+            .as_ref()
+            .filter(|_| color_grading)
+            .map(|lut| lut.view.clone());
+
+        let composite_target = if settings.fxaa {
+            graph.create_texture("post_ldr", TransientTexture::new(self.output_format));
+            "post_ldr"
+        } else {
+            RenderGraph::OUTPUT
+        };
+
+        let mut builder = graph.add_pass("Post Composite").write(composite_target);
+        for resource in &composite {
+            builder = builder.read(*resource);
+        }
+
+        let resources = self.resources.clone();
+        let pipeline = self.composite.clone();
+        let bloom = settings.bloom.enabled;
+        builder.run(move |ctx| {
+            let source = ctx.view(source);
+            let bloom = bloom.then(|| ctx.view("post_bloom"));
+            let target = ctx.view(composite_target);
+            resources.draw(ctx, &pipeline, source, bloom, lut.as_ref(), target);
+        });
+
+        if settings.fxaa {
+            self.add_pass(
+                graph,
+                "Post FXAA",
+                &self.fxaa,
+                "post_ldr",
+                RenderGraph::OUTPUT,
+            );
+        }
+    }
+
+    fn add_pass(
+        &self,
+        graph: &mut RenderGraph,
+        name: &str,
+        pipeline: &RenderPipeline,
+        source: &'static str,
+        target: &'static str,
+    ) {
+        let resources = self.resources.clone();
+        let pipeline = pipeline.clone();
+
+        graph
+            .add_pass(name)
+            .read(source)
+            .write(target)
+            .run(move |ctx| {
+                let (source, target) = (ctx.view(source), ctx.view(target));
+                resources.draw(ctx, &pipeline, source, None, None, target);
+            });
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &ShaderModule,
+        entry_point: &str,
+        format: TextureFormat,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Post Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+impl PostResources {
+    fn draw(
+        &self,
+        ctx: &mut PassContext,
+        pipeline: &RenderPipeline,
+        source: &TextureView,
+        bloom: Option<&TextureView>,
+        lut: Option<&TextureView>,
+        target: &TextureView,
+    ) {
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(bloom.unwrap_or(&self.black)),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(lut.unwrap_or(&self.black)),
+                },
+            ],
+        });
+
+        let mut render_pass = ctx.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,222 @@
+struct Post {
+    exposure: f32,
+    bloom_threshold: f32,
+    bloom_intensity: f32,
+    vignette_intensity: f32,
+    vignette_radius: f32,
+    vignette_smoothness: f32,
+    // 0 = none, 1 = Reinhard, 2 = ACES.
+    tonemapping: u32,
+    // BLOOM | COLOR_GRADING | VIGNETTE | ENCODE_SRGB.
+    flags: u32,
+};
+
+const BLOOM: u32 = 1u;
+const COLOR_GRADING: u32 = 2u;
+const VIGNETTE: u32 = 4u;
+const ENCODE_SRGB: u32 = 8u;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> post: Post;
+@group(0) @binding(3)
+var bloom_texture: texture_2d<f32>;
+@group(0) @binding(4)
+var lut_texture: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+
+    return out;
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+@fragment
+fn fs_bright(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+
+    // Four bilinear taps cover the 4x4 texels behind this half-resolution pixel.
+    var color = textureSample(source_texture, source_sampler, in.uv + texel * vec2<f32>(-1.0, -1.0)).rgb;
+    color += textureSample(source_texture, source_sampler, in.uv + texel * vec2<f32>(1.0, -1.0)).rgb;
+    color += textureSample(source_texture, source_sampler, in.uv + texel * vec2<f32>(-1.0, 1.0)).rgb;
+    color += textureSample(source_texture, source_sampler, in.uv + texel * vec2<f32>(1.0, 1.0)).rgb;
+    color *= 0.25 * post.exposure;
+
+    let brightness = max(color.r, max(color.g, color.b));
+    let contribution = max(brightness - post.bloom_threshold, 0.0) / max(brightness, 1e-4);
+
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+fn blur(uv: vec2<f32>, direction: vec2<f32>) -> vec4<f32> {
+    let step = direction / vec2<f32>(textureDimensions(source_texture));
+
+    // 9-tap gaussian folded into 5 bilinear taps.
+    var color = textureSample(source_texture, source_sampler, uv).rgb * 0.227027;
+    color += textureSample(source_texture, source_sampler, uv + step * 1.384615).rgb * 0.316216;
+    color += textureSample(source_texture, source_sampler, uv - step * 1.384615).rgb * 0.316216;
+    color += textureSample(source_texture, source_sampler, uv + step * 3.230769).rgb * 0.070270;
+    color += textureSample(source_texture, source_sampler, uv - step * 3.230769).rgb * 0.070270;
+
+    return vec4<f32>(color, 1.0);
+}
+
+@fragment
+fn fs_blur_horizontal(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(1.0, 0.0));
+}
+
+@fragment
+fn fs_blur_vertical(in: VertexOutput) -> @location(0) vec4<f32> {
+    return blur(in.uv, vec2<f32>(0.0, 1.0));
+}
+
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    // Stephen Hill's fit of the ACES reference transform.
+    let input = mat3x3<f32>(
+        vec3<f32>(0.59719, 0.07600, 0.02840),
+        vec3<f32>(0.35458, 0.90834, 0.13383),
+        vec3<f32>(0.04823, 0.01566, 0.83777),
+    );
+    let output = mat3x3<f32>(
+        vec3<f32>(1.60475, -0.10208, -0.00327),
+        vec3<f32>(-0.53108, 1.10813, -0.07276),
+        vec3<f32>(-0.07367, -0.00605, 1.07602),
+    );
+
+    let v = input * color;
+    let a = v * (v + 0.0245786) - 0.000090537;
+    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+
+    return output * (a / b);
+}
+
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    switch post.tonemapping {
+        case 1u: {
+            return color / (1.0 + luminance(color));
+        }
+        case 2u: {
+            return aces(color);
+        }
+        default: {
+            return color;
+        }
+    }
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn grade(color: vec3<f32>) -> vec3<f32> {
+    // The LUT is a strip of `size` slices of size x size texels, blue picks the slice.
+    let size = f32(textureDimensions(lut_texture).y);
+    let encoded = linear_to_srgb(color) * (size - 1.0);
+
+    let slice = floor(encoded.b);
+    let next = min(slice + 1.0, size - 1.0);
+    let texel = (encoded.rg + 0.5) / vec2<f32>(size * size, size);
+    let offset = vec2<f32>(1.0 / size, 0.0);
+
+    // The LUT is sampled from an sRGB texture, so it gives back linear colors.
+    let low = textureSampleLevel(lut_texture, source_sampler, texel + offset * slice, 0.0).rgb;
+    let high = textureSampleLevel(lut_texture, source_sampler, texel + offset * next, 0.0).rgb;
+
+    return mix(low, high, encoded.b - slice);
+}
+
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSample(source_texture, source_sampler, in.uv).rgb * post.exposure;
+
+    if (post.flags & BLOOM) != 0u {
+        color += textureSample(bloom_texture, source_sampler, in.uv).rgb * post.bloom_intensity;
+    }
+
+    color = clamp(tonemap(max(color, vec3<f32>(0.0))), vec3<f32>(0.0), vec3<f32>(1.0));
+
+    if (post.flags & COLOR_GRADING) != 0u {
+        color = grade(color);
+    }
+
+    if (post.flags & VIGNETTE) != 0u {
+        let distance = length(in.uv - 0.5) * 1.41421356;
+        let falloff = 1.0 - smoothstep(post.vignette_radius - post.vignette_smoothness, post.vignette_radius, distance);
+        color *= mix(1.0 - post.vignette_intensity, 1.0, falloff);
+    }
+
+    if (post.flags & ENCODE_SRGB) != 0u {
+        color = linear_to_srgb(color);
+    }
+
+    return vec4<f32>(color, 1.0);
+}
+
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(luminance(color));
+}
+
+@fragment
+fn fs_fxaa(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    let center = textureSampleLevel(source_texture, source_sampler, in.uv, 0.0);
+
+    let luma_nw = luma(textureSampleLevel(source_texture, source_sampler, in.uv + texel * vec2<f32>(-1.0, -1.0), 0.0).rgb);
+    let luma_ne = luma(textureSampleLevel(source_texture, source_sampler, in.uv + texel * vec2<f32>(1.0, -1.0), 0.0).rgb);
+    let luma_sw = luma(textureSampleLevel(source_texture, source_sampler, in.uv + texel * vec2<f32>(-1.0, 1.0), 0.0).rgb);
+    let luma_se = luma(textureSampleLevel(source_texture, source_sampler, in.uv + texel * vec2<f32>(1.0, 1.0), 0.0).rgb);
+    let luma_m = luma(center.rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // Leave flat areas alone.
+    if luma_max - luma_min < max(0.0312, luma_max * 0.125) {
+        return center;
+    }
+
+    var direction = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * 0.125, 1.0 / 128.0);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2<f32>(-8.0), vec2<f32>(8.0)) * texel;
+
+    let near = 0.5 * (
+        textureSampleLevel(source_texture, source_sampler, in.uv + direction * (1.0 / 3.0 - 0.5), 0.0).rgb
+        + textureSampleLevel(source_texture, source_sampler, in.uv + direction * (2.0 / 3.0 - 0.5), 0.0).rgb
+    );
+    let far = near * 0.5 + 0.25 * (
+        textureSampleLevel(source_texture, source_sampler, in.uv - direction * 0.5, 0.0).rgb
+        + textureSampleLevel(source_texture, source_sampler, in.uv + direction * 0.5, 0.0).rgb
+    );
+
+    let luma_far = luma(far);
+    if luma_far < luma_min || luma_far > luma_max {
+        return vec4<f32>(near, center.a);
+    }
+
+    return vec4<f32>(far, center.a);
+}
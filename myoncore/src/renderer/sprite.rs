@@ -0,0 +1,478 @@
+use std::collections::{HashMap, HashSet};
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec2, Vec4};
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, RenderPipeline};
+
+use super::{Renderer, texture::Texture};
+
+const INITIAL_INSTANCE_CAPACITY: u64 = 256;
+const INITIAL_GLOBALS_CAPACITY: u64 = 4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Sprite {
+    pub position: Vec2,
+    pub size: Vec2,
+    pub origin: Vec2,
+    pub rotation: f32,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+    pub color: Vec4,
+    pub layer: i32,
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            size: Vec2::ONE,
+            origin: Vec2::splat(0.5),
+            rotation: 0.0,
+            uv_min: Vec2::ZERO,
+            uv_max: Vec2::ONE,
+            color: Vec4::ONE,
+            layer: 0,
+        }
+    }
+}
+
+impl Sprite {
+    pub fn new(position: Vec2, size: Vec2) -> Self {
+        Self {
+            position,
+            size,
+            ..Default::default()
+        }
+    }
+
+    pub fn origin(mut self, origin: Vec2) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn uv(mut self, uv_min: Vec2, uv_max: Vec2) -> Self {
+        self.uv_min = uv_min;
+        self.uv_max = uv_max;
+        self
+    }
+
+    pub fn color(mut self, color: Vec4) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn layer(mut self, layer: i32) -> Self {
+        self.layer = layer;
+        self
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct SpriteInstance {
+    position: [f32; 2],
+    size: [f32; 2],
+    origin: [f32; 2],
+    rotation: f32,
+    uv_rect: [f32; 4],
+    color: [f32; 4],
+}
+
+impl SpriteInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32,
+        4 => Float32x4,
+        5 => Float32x4,
+    ];
+}
+
+impl From<&Sprite> for SpriteInstance {
+    fn from(sprite: &Sprite) -> Self {
+        Self {
+            position: sprite.position.to_array(),
+            size: sprite.size.to_array(),
+            origin: sprite.origin.to_array(),
+            rotation: sprite.rotation,
+            uv_rect: [
+                sprite.uv_min.x,
+                sprite.uv_min.y,
+                sprite.uv_max.x,
+                sprite.uv_max.y,
+            ],
+            color: sprite.color.to_array(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct SpriteGlobals {
+    view_proj: Mat4,
+    y_up: u32,
+    _padding: [u32; 3],
+}
+
+struct QueuedSprite {
+    layer: i32,
+    texture: u64,
+    instance: SpriteInstance,
+}
+
+pub struct SpriteBatch {
+    device: Device,
+    pipeline: RenderPipeline,
+    globals_layout: BindGroupLayout,
+    texture_layout: BindGroupLayout,
+    globals_buffer: Buffer,
+    globals_bind_group: BindGroup,
+    globals_stride: u64,
+    globals_capacity: u64,
+    instance_buffer: Buffer,
+    instance_capacity: u64,
+    texture_bind_groups: HashMap<u64, BindGroup>,
+    used_textures: HashSet<u64>,
+    queued: Vec<QueuedSprite>,
+    white: Texture,
+    view_projection: Option<Mat4>,
+    frame_index: u64,
+    globals_cursor: u64,
+    instance_cursor: u64,
+}
+
+impl SpriteBatch {
+    pub fn new(renderer: &Renderer) -> Self {
+        let device = renderer.device().clone();
+
+        let shader = device.create_shader_module(wgpu::include_wgsl!("sprite.wgsl"));
+
+        let globals_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sprite Globals Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(size_of::<SpriteGlobals>() as u64),
+                },
+                count: None,
+            }],
+        });
+
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sprite Texture Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Sprite Pipeline Layout"),
+            bind_group_layouts: &[&globals_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Sprite Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<SpriteInstance>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &SpriteInstance::ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: renderer.format(),
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: renderer.sample_count(),
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let globals_stride = (size_of::<SpriteGlobals>() as u64)
+            .next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+
+        let globals_buffer =
+            Self::create_globals_buffer(&device, globals_stride, INITIAL_GLOBALS_CAPACITY);
+        let globals_bind_group =
+            Self::create_globals_bind_group(&device, &globals_layout, &globals_buffer);
+        let instance_buffer = Self::create_instance_buffer(&device, INITIAL_INSTANCE_CAPACITY);
+
+        let white = Texture::white(&device, renderer.queue());
+
+        Self {
+            device,
+            pipeline,
+            globals_layout,
+            texture_layout,
+            globals_buffer,
+            globals_bind_group,
+            globals_stride,
+            globals_capacity: INITIAL_GLOBALS_CAPACITY,
+            instance_buffer,
+            instance_capacity: INITIAL_INSTANCE_CAPACITY,
+            texture_bind_groups: HashMap::new(),
+            used_textures: HashSet::new(),
+            queued: Vec::new(),
+            white,
+            view_projection: None,
+            frame_index: u64::MAX,
+            globals_cursor: 0,
+            instance_cursor: 0,
+        }
+    }
+
+    /// Overrides the camera of the [`Renderer`]. Textures stay upright whichever way y points.
+    pub fn set_view_projection(&mut self, view_projection: Option<Mat4>) {
+        self.view_projection = view_projection;
+    }
+
+    pub fn draw(&mut self, texture: &Texture, sprite: Sprite) {
+        if !self.texture_bind_groups.contains_key(&texture.id()) {
+            let bind_group = self.create_texture_bind_group(texture);
+            self.texture_bind_groups.insert(texture.id(), bind_group);
+        }
+
+        self.queued.push(QueuedSprite {
+            layer: sprite.layer,
+            texture: texture.id(),
+            instance: SpriteInstance::from(&sprite),
+        });
+    }
+
+    pub fn draw_quad(&mut self, sprite: Sprite) {
+        let white = self.white.clone();
+        self.draw(&white, sprite);
+    }
+
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    /// Sorts the queued sprites by layer and texture, and records them into the current frame.
+    /// Can be called several times per frame.
+    pub fn flush(&mut self, renderer: &mut Renderer) {
+        if self.queued.is_empty() {
+            return;
+        }
+
+        if self.frame_index != renderer.frame_index() {
+            self.frame_index = renderer.frame_index();
+            self.globals_cursor = 0;
+            self.instance_cursor = 0;
+
+            let white = self.white.id();
+            let mut used = std::mem::take(&mut self.used_textures);
+            used.extend(self.queued.iter().map(|sprite| sprite.texture));
+            self.texture_bind_groups
+                .retain(|id, _| used.contains(id) || *id == white);
+        }
+
+        self.queued
+            .sort_by_key(|sprite| (sprite.layer, sprite.texture));
+
+        let instances: Vec<SpriteInstance> =
+            self.queued.iter().map(|sprite| sprite.instance).collect();
+        self.reserve(renderer, instances.len() as u64);
+
+        let view_proj = self.view_projection.unwrap_or(renderer.camera().view_proj);
+
+        // Sprites are laid out top to bottom, so they're flipped when y points up on screen.
+        let y_up =
+            view_proj.x_axis.x * view_proj.y_axis.y - view_proj.y_axis.x * view_proj.x_axis.y > 0.0;
+        let globals = SpriteGlobals {
+            view_proj,
+            y_up: y_up as u32,
+            _padding: [0; 3],
+        };
+
+        let globals_offset = self.globals_cursor * self.globals_stride;
+        let instance_offset = self.instance_cursor * size_of::<SpriteInstance>() as u64;
+
+        renderer.queue().write_buffer(
+            &self.globals_buffer,
+            globals_offset,
+            bytemuck::bytes_of(&globals),
+        );
+        renderer.queue().write_buffer(
+            &self.instance_buffer,
+            instance_offset,
+            bytemuck::cast_slice(&instances),
+        );
+
+        let first_instance = self.instance_cursor as u32;
+        self.globals_cursor += 1;
+        self.instance_cursor += instances.len() as u64;
+
+        let texture_view = renderer.texture_view.as_ref().expect("TextureView missing");
+        let encoder = renderer
+            .command_encoder
+            .as_mut()
+            .expect("CommandEncoder missing");
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Sprite Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: texture_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.globals_bind_group, &[globals_offset as u32]);
+            render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+
+            let mut start = 0;
+            while start < self.queued.len() {
+                let texture = self.queued[start].texture;
+                let end = self.queued[start..]
+                    .iter()
+                    .position(|sprite| sprite.texture != texture)
+                    .map_or(self.queued.len(), |count| start + count);
+
+                render_pass.set_bind_group(1, &self.texture_bind_groups[&texture], &[]);
+                render_pass.draw(
+                    0..6,
+                    first_instance + start as u32..first_instance + end as u32,
+                );
+
+                start = end;
+            }
+        }
+
+        self.used_textures
+            .extend(self.queued.iter().map(|sprite| sprite.texture));
+        self.queued.clear();
+    }
+
+    fn reserve(&mut self, renderer: &Renderer, instances: u64) {
+        // Buffers recorded earlier in this frame are kept alive by the command encoder, so
+        // growing just starts writing into fresh buffers.
+        if self.instance_cursor + instances > self.instance_capacity {
+            self.instance_capacity = (self.instance_cursor + instances).next_power_of_two();
+            self.instance_buffer =
+                Self::create_instance_buffer(renderer.device(), self.instance_capacity);
+            self.instance_cursor = 0;
+        }
+
+        if self.globals_cursor + 1 > self.globals_capacity {
+            self.globals_capacity *= 2;
+            self.globals_buffer = Self::create_globals_buffer(
+                renderer.device(),
+                self.globals_stride,
+                self.globals_capacity,
+            );
+            self.globals_bind_group = Self::create_globals_bind_group(
+                renderer.device(),
+                &self.globals_layout,
+                &self.globals_buffer,
+            );
+            self.globals_cursor = 0;
+        }
+    }
+
+    fn create_texture_bind_group(&self, texture: &Texture) -> BindGroup {
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sprite Texture Bind Group"),
+            layout: &self.texture_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+        })
+    }
+
+    fn create_globals_buffer(device: &Device, stride: u64, capacity: u64) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sprite Globals Buffer"),
+            size: stride * capacity,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_globals_bind_group(
+        device: &Device,
+        layout: &BindGroupLayout,
+        buffer: &Buffer,
+    ) -> BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sprite Globals Bind Group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(size_of::<SpriteGlobals>() as u64),
+                }),
+            }],
+        })
+    }
+
+    fn create_instance_buffer(device: &Device, capacity: u64) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sprite Instance Buffer"),
+            size: capacity * size_of::<SpriteInstance>() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use crate::ecs::{Entity, World};
+
+/// The entity this one is attached to. Managed by [`set_parent`] and [`remove_parent`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Parent(Entity);
+
+impl Parent {
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Entities attached to this one, in the order they were attached.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Children(Vec<Entity>);
+
+impl Children {
+    pub fn as_slice(&self) -> &[Entity] {
+        &self.0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Attaches `child` to `parent`, detaching it from its old parent first.
+///
+/// Returns false and changes nothing if either entity doesn't exist or if `parent` is
+/// `child` or one of its descendants, which would make a cycle.
+pub fn set_parent(world: &mut World, child: Entity, parent: Entity) -> bool {
+    if !world.is_alive(child) || !world.is_alive(parent) || is_ancestor(world, child, parent) {
+        return false;
+    }
+
+    remove_parent(world, child);
+
+    world.insert(child, Parent(parent));
+    if let Some(mut children) = world.get_mut::<Children>(parent) {
+        children.0.push(child);
+        return true;
+    }
+    world.insert(parent, Children(vec![child]));
+
+    true
+}
+
+/// Makes `child` a root again. Returns whether it had a parent.
+pub fn remove_parent(world: &mut World, child: Entity) -> bool {
+    let Some(Parent(parent)) = world.remove::<Parent>(child) else {
+        return false;
+    };
+
+    let now_empty = world
+        .get_mut::<Children>(parent)
+        .is_some_and(|mut children| {
+            children.0.retain(|&entity| entity != child);
+            children.0.is_empty()
+        });
+
+    if now_empty {
+        world.remove::<Children>(parent);
+    }
+
+    true
+}
+
+/// Despawns `entity` with all of its descendants, detaching it from its parent.
+pub fn despawn_recursive(world: &mut World, entity: Entity) {
+    remove_parent(world, entity);
+
+    let mut stack = vec![entity];
+    while let Some(entity) = stack.pop() {
+        if let Some(children) = world.remove::<Children>(entity) {
+            stack.extend(children.0);
+        }
+
+        world.despawn(entity);
+    }
+}
+
+/// Whether `ancestor` is `entity` or one of the entities above it.
+fn is_ancestor(world: &World, ancestor: Entity, mut entity: Entity) -> bool {
+    loop {
+        if entity == ancestor {
+            return true;
+        }
+
+        match world.get::<Parent>(entity) {
+            Some(parent) => entity = parent.0,
+            None => return false,
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod hierarchy;
+pub mod serialize;
+pub mod transform;
+
+pub use hierarchy::{Children, Parent, despawn_recursive, remove_parent, set_parent};
+pub use serialize::SceneRegistry;
+pub use transform::{GlobalTransform, Transform, propagate_transforms};
@@ -0,0 +1,492 @@
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::Path,
+};
+
+use anyhow::Context;
+use serde::{
+    Deserializer, Serialize, Serializer,
+    de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    ser::SerializeMap,
+};
+
+use crate::{
+    ecs::{Entity, World},
+    renderer::Camera,
+};
+
+use super::{Parent, Transform, set_parent};
+
+type SerializeFn = for<'w> fn(&'w World, Entity) -> Option<Box<dyn erased_serde::Serialize + 'w>>;
+type DeserializeFn = for<'de> fn(
+    &mut dyn erased_serde::Deserializer<'de>,
+) -> Result<Box<dyn Any>, erased_serde::Error>;
+type InsertFn = fn(&mut World, Entity, Box<dyn Any>);
+
+struct Registration {
+    name: String,
+    serialize: SerializeFn,
+    deserialize: DeserializeFn,
+    insert: InsertFn,
+}
+
+/// The component types that scenes can contain, under the names used in scene files.
+///
+/// Scenes are written as RON or JSON. Every entity gets an `id` that `parent` fields refer
+/// to, and a map of its registered components, which are saved with their serde
+/// representation. Components that aren't registered are skipped when saving:
+///
+/// ```ron
+/// (
+///     entities: [
+///         (
+///             id: 0,
+///             parent: None,
+///             components: {
+///                 "Transform": (translation: (0.0, 1.0, 0.0)),
+///             },
+///         ),
+///     ],
+/// )
+/// ```
+///
+/// Asset references should be stored as [`crate::assets::AssetRef`]s, which are saved by
+/// path. [`Transform`] and [`Camera`] are registered by default, and missing [`Transform`]
+/// fields are taken from the identity. The hierarchy is saved through `parent`.
+pub struct SceneRegistry {
+    components: Vec<Registration>,
+    by_name: HashMap<String, usize>,
+}
+
+impl Default for SceneRegistry {
+    fn default() -> Self {
+        Self {
+            components: Vec::new(),
+            by_name: HashMap::new(),
+        }
+        .register::<Transform>("Transform")
+        .register::<Camera>("Camera")
+    }
+}
+
+impl SceneRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets `T` be saved and loaded as `name`, replacing a type registered under that name.
+    pub fn register<T: Serialize + DeserializeOwned + 'static>(
+        mut self,
+        name: impl Into<String>,
+    ) -> Self {
+        let registration = Registration {
+            name: name.into(),
+            serialize: serialize_component::<T>,
+            deserialize: deserialize_component::<T>,
+            insert: insert_component::<T>,
+        };
+
+        match self.by_name.get(&registration.name) {
+            Some(&index) => self.components[index] = registration,
+            None => {
+                self.by_name
+                    .insert(registration.name.clone(), self.components.len());
+                self.components.push(registration);
+            }
+        }
+
+        self
+    }
+
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.by_name.contains_key(name)
+    }
+
+    /// Spawns the entities of a RON scene into `world`, returning them in file order.
+    pub fn from_ron(&self, world: &mut World, source: &str) -> anyhow::Result<Vec<Entity>> {
+        let mut deserializer = ron::Deserializer::from_str(source)?;
+        let entities = SceneSeed(self)
+            .deserialize(&mut deserializer)
+            .map_err(|e| deserializer.span_error(e))?;
+        deserializer.end()?;
+
+        spawn(world, entities)
+    }
+
+    /// Spawns the entities of a JSON scene into `world`, returning them in file order.
+    pub fn from_json(&self, world: &mut World, source: &str) -> anyhow::Result<Vec<Entity>> {
+        let mut deserializer = serde_json::Deserializer::from_str(source);
+        let entities = SceneSeed(self).deserialize(&mut deserializer)?;
+        deserializer.end()?;
+
+        spawn(world, entities)
+    }
+
+    /// Writes every entity in `world` as RON.
+    pub fn to_ron(&self, world: &World) -> anyhow::Result<String> {
+        ron::ser::to_string_pretty(&SceneWriter::new(self, world), Default::default())
+            .context("Failed to serialize scene")
+    }
+
+    /// Writes every entity in `world` as JSON.
+    pub fn to_json(&self, world: &World) -> anyhow::Result<String> {
+        serde_json::to_string_pretty(&SceneWriter::new(self, world))
+            .context("Failed to serialize scene")
+    }
+
+    /// Loads a `.ron` or `.json` scene into `world`.
+    pub fn load(&self, world: &mut World, path: impl AsRef<Path>) -> anyhow::Result<Vec<Entity>> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        match Format::of(path)? {
+            Format::Ron => self.from_ron(world, &source),
+            Format::Json => self.from_json(world, &source),
+        }
+        .with_context(|| format!("Failed to load {}", path.display()))
+    }
+
+    /// Saves `world` as a `.ron` or `.json` scene.
+    pub fn save(&self, world: &World, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        let source = match Format::of(path)? {
+            Format::Ron => self.to_ron(world)?,
+            Format::Json => self.to_json(world)?,
+        };
+
+        fs::write(path, source).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+enum Format {
+    Ron,
+    Json,
+}
+
+impl Format {
+    fn of(path: &Path) -> anyhow::Result<Self> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ron") => Ok(Self::Ron),
+            Some("json") => Ok(Self::Json),
+            _ => anyhow::bail!("{} is not a .ron or .json scene", path.display()),
+        }
+    }
+}
+
+fn serialize_component<T: Serialize + 'static>(
+    world: &World,
+    entity: Entity,
+) -> Option<Box<dyn erased_serde::Serialize + '_>> {
+    struct Borrowed<'w, T>(std::cell::Ref<'w, T>);
+
+    impl<T: Serialize> Serialize for Borrowed<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize(serializer)
+        }
+    }
+
+    world
+        .get::<T>(entity)
+        .map(|component| Box::new(Borrowed(component)) as Box<dyn erased_serde::Serialize>)
+}
+
+fn deserialize_component<T: DeserializeOwned + 'static>(
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<Box<dyn Any>, erased_serde::Error> {
+    erased_serde::deserialize::<T>(deserializer)
+        .map(|component| Box::new(component) as Box<dyn Any>)
+}
+
+fn insert_component<T: 'static>(world: &mut World, entity: Entity, component: Box<dyn Any>) {
+    let component = component
+        .downcast::<T>()
+        .expect("Components are deserialized by their own registration");
+
+    world.insert(entity, *component);
+}
+
+struct SceneWriter<'a> {
+    registry: &'a SceneRegistry,
+    world: &'a World,
+    entities: Vec<Entity>,
+    ids: HashMap<Entity, usize>,
+}
+
+impl<'a> SceneWriter<'a> {
+    fn new(registry: &'a SceneRegistry, world: &'a World) -> Self {
+        let mut entities = world.entities().to_vec();
+        entities.sort();
+
+        let ids = entities
+            .iter()
+            .enumerate()
+            .map(|(id, entity)| (*entity, id))
+            .collect();
+
+        Self {
+            registry,
+            world,
+            entities,
+            ids,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SceneFile<'a> {
+    entities: Vec<EntityFile<'a>>,
+}
+
+#[derive(Serialize)]
+struct EntityFile<'a> {
+    id: usize,
+    parent: Option<usize>,
+    components: ComponentsWriter<'a>,
+}
+
+struct ComponentsWriter<'a> {
+    registry: &'a SceneRegistry,
+    world: &'a World,
+    entity: Entity,
+}
+
+impl Serialize for SceneWriter<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entities = self
+            .entities
+            .iter()
+            .map(|&entity| EntityFile {
+                id: self.ids[&entity],
+                parent: self
+                    .world
+                    .get::<Parent>(entity)
+                    .and_then(|parent| self.ids.get(&parent.get()).copied()),
+                components: ComponentsWriter {
+                    registry: self.registry,
+                    world: self.world,
+                    entity,
+                },
+            })
+            .collect();
+
+        SceneFile { entities }.serialize(serializer)
+    }
+}
+
+impl Serialize for ComponentsWriter<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+
+        for registration in &self.registry.components {
+            if let Some(component) = (registration.serialize)(self.world, self.entity) {
+                map.serialize_entry(&registration.name, &*component)?;
+            }
+        }
+
+        map.end()
+    }
+}
+
+struct LoadedEntity {
+    id: usize,
+    parent: Option<usize>,
+    components: Vec<(InsertFn, Box<dyn Any>)>,
+}
+
+fn spawn(world: &mut World, loaded: Vec<LoadedEntity>) -> anyhow::Result<Vec<Entity>> {
+    // Check the ids first, so a broken file doesn't leave half a scene behind.
+    let mut known = HashSet::new();
+    for loaded in &loaded {
+        anyhow::ensure!(known.insert(loaded.id), "Duplicate entity id {}", loaded.id);
+    }
+    for loaded in &loaded {
+        if let Some(parent) = loaded.parent {
+            anyhow::ensure!(
+                known.contains(&parent),
+                "Scene entity {} has unknown parent {parent}",
+                loaded.id
+            );
+        }
+    }
+
+    let mut entities = Vec::with_capacity(loaded.len());
+    let mut ids = HashMap::new();
+
+    for loaded in &loaded {
+        let entity = world.spawn_empty();
+        ids.insert(loaded.id, entity);
+        entities.push(entity);
+    }
+
+    for (loaded, &entity) in loaded.into_iter().zip(&entities) {
+        for (insert, component) in loaded.components {
+            insert(world, entity, component);
+        }
+
+        let Some(parent) = loaded.parent else {
+            continue;
+        };
+
+        if !set_parent(world, entity, ids[&parent]) {
+            tracing::error!(
+                "Ignoring the parent of scene entity {}, it would make a cycle",
+                loaded.id
+            );
+        }
+    }
+
+    Ok(entities)
+}
+
+struct SceneSeed<'a>(&'a SceneRegistry);
+
+impl<'de> DeserializeSeed<'de> for SceneSeed<'_> {
+    type Value = Vec<LoadedEntity>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("SceneFile", &["entities"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for SceneSeed<'_> {
+    type Value = Vec<LoadedEntity>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a scene")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entities = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "entities" => entities = Some(map.next_value_seed(EntitiesSeed(self.0))?),
+                _ => return Err(de::Error::unknown_field(&key, &["entities"])),
+            }
+        }
+
+        entities.ok_or_else(|| de::Error::missing_field("entities"))
+    }
+}
+
+struct EntitiesSeed<'a>(&'a SceneRegistry);
+
+impl<'de> DeserializeSeed<'de> for EntitiesSeed<'_> {
+    type Value = Vec<LoadedEntity>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntitiesSeed<'_> {
+    type Value = Vec<LoadedEntity>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of entities")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut entities = Vec::new();
+
+        while let Some(entity) = seq.next_element_seed(EntitySeed(self.0))? {
+            entities.push(entity);
+        }
+
+        Ok(entities)
+    }
+}
+
+const ENTITY_FIELDS: &[&str] = &["id", "parent", "components"];
+
+struct EntitySeed<'a>(&'a SceneRegistry);
+
+impl<'de> DeserializeSeed<'de> for EntitySeed<'_> {
+    type Value = LoadedEntity;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("EntityFile", ENTITY_FIELDS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntitySeed<'_> {
+    type Value = LoadedEntity;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an entity")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut id = None;
+        let mut parent = None;
+        let mut components = Vec::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" => id = Some(map.next_value()?),
+                "parent" => parent = map.next_value()?,
+                "components" => components = map.next_value_seed(ComponentsSeed(self.0))?,
+                _ => return Err(de::Error::unknown_field(&key, ENTITY_FIELDS)),
+            }
+        }
+
+        Ok(LoadedEntity {
+            id: id.ok_or_else(|| de::Error::missing_field("id"))?,
+            parent,
+            components,
+        })
+    }
+}
+
+struct ComponentsSeed<'a>(&'a SceneRegistry);
+
+impl<'de> DeserializeSeed<'de> for ComponentsSeed<'_> {
+    type Value = Vec<(InsertFn, Box<dyn Any>)>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ComponentsSeed<'_> {
+    type Value = Vec<(InsertFn, Box<dyn Any>)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of component names to components")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut components = Vec::new();
+
+        while let Some(name) = map.next_key::<String>()? {
+            let Some(&index) = self.0.by_name.get(&name) else {
+                return Err(de::Error::custom(format!(
+                    "Unknown component {name}, it needs to be registered"
+                )));
+            };
+
+            let registration = &self.0.components[index];
+            let component = map.next_value_seed(ComponentSeed(registration))?;
+            components.push((registration.insert, component));
+        }
+
+        Ok(components)
+    }
+}
+
+struct ComponentSeed<'a>(&'a Registration);
+
+impl<'de> DeserializeSeed<'de> for ComponentSeed<'_> {
+    type Value = Box<dyn Any>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(deserializer);
+
+        (self.0.deserialize)(&mut deserializer)
+            .map_err(|e| de::Error::custom(format!("{}: {e}", self.0.name)))
+    }
+}
@@ -0,0 +1,196 @@
+use glam::{Mat4, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+
+use crate::ecs::{Entity, With, Without, World};
+
+use super::{Children, Parent};
+
+/// Position, rotation and scale relative to the parent, or to the world for roots.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub fn from_xyz(x: f32, y: f32, z: f32) -> Self {
+        Self::from_translation(Vec3::new(x, y, z))
+    }
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self {
+            scale,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    pub fn rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Rotates so that [`Self::forward`] points at `target`.
+    pub fn looking_at(mut self, target: Vec3, up: Vec3) -> Self {
+        let forward = (target - self.translation).normalize();
+        let right = up.cross(-forward).normalize();
+        let up = (-forward).cross(right);
+
+        self.rotation = Quat::from_mat3(&glam::Mat3::from_cols(right, up, -forward));
+        self
+    }
+
+    /// -Z, rotated.
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * Vec3::NEG_Z
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.rotation * Vec3::X
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.rotation * Vec3::Y
+    }
+
+    pub fn compute_matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+/// World-space matrix of an entity, written by [`propagate_transforms`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GlobalTransform {
+    matrix: Mat4,
+    /// The local transform and parent the matrix was computed from.
+    source: Option<(Transform, Option<Entity>)>,
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self {
+            matrix: Mat4::IDENTITY,
+            source: None,
+        }
+    }
+}
+
+impl GlobalTransform {
+    pub fn matrix(&self) -> Mat4 {
+        self.matrix
+    }
+
+    pub fn translation(&self) -> Vec3 {
+        self.matrix.w_axis.truncate()
+    }
+
+    pub fn to_scale_rotation_translation(&self) -> (Vec3, Quat, Vec3) {
+        self.matrix.to_scale_rotation_translation()
+    }
+
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.matrix.transform_point3(point)
+    }
+}
+
+/// Updates the [`GlobalTransform`] of every entity with a [`Transform`], adding missing ones.
+///
+/// Only subtrees whose local transform or parent changed since the last call are recomputed.
+/// Entities without a [`Transform`] count as the identity, so their children follow the next
+/// ancestor that has one, and are recomputed every call. Children whose parent was despawned
+/// are treated as roots. The engine calls this every frame after
+/// the [`crate::ecs::Stage::Render`] systems and before [`crate::AppHandler::on_render`].
+pub fn propagate_transforms(world: &mut World) {
+    let missing = world
+        .query_filtered::<Entity, (With<Transform>, Without<GlobalTransform>)>()
+        .entities()
+        .to_vec();
+
+    for entity in missing {
+        world.insert(entity, GlobalTransform::default());
+    }
+
+    let roots: Vec<Entity> = world
+        .query::<(Entity, Option<&Parent>)>()
+        .iter()
+        .filter(|(_, parent)| !parent.is_some_and(|parent| world.is_alive(parent.get())))
+        .map(|(entity, _)| entity)
+        .collect();
+
+    let (Some(transforms), Some(mut globals)) = (
+        world.storage::<Transform>(),
+        world.storage_mut::<GlobalTransform>(),
+    ) else {
+        return;
+    };
+    let children = world.storage::<Children>();
+
+    let mut stack: Vec<_> = roots
+        .into_iter()
+        .map(|root| (root, None, Mat4::IDENTITY, false))
+        .collect();
+
+    while let Some((entity, parent, parent_matrix, parent_changed)) = stack.pop() {
+        let (matrix, changed) = match (transforms.get(entity), globals.get_mut(entity)) {
+            (Some(transform), Some(global)) => {
+                let source = Some((*transform, parent));
+                let changed = parent_changed || global.source != source;
+
+                if changed {
+                    global.matrix = parent_matrix * transform.compute_matrix();
+                    global.source = source;
+                }
+
+                (global.matrix, changed)
+            }
+
+            // Nothing records whether the pass-through matrix changed, so always update below.
+            _ => (parent_matrix, true),
+        };
+
+        if let Some(children) = children.as_ref().and_then(|children| children.get(entity)) {
+            stack.extend(
+                children
+                    .iter()
+                    .map(|child| (child, Some(entity), matrix, changed)),
+            );
+        }
+    }
+}
@@ -0,0 +1,174 @@
+pub mod preprocessor;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use wgpu::{Device, ShaderModule};
+
+use crate::utils::normalize;
+
+pub use preprocessor::{Preprocessor, ProcessedShader, ShaderDefs};
+
+/// A preprocessing or compile error, pointing at the file and line it came from
+/// rather than at the expanded source.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ShaderError {
+    pub file: PathBuf,
+    /// 1-based, or 0 when the error has no location.
+    pub line: u32,
+    /// 1-based, or 0 when the error has no location.
+    pub column: u32,
+    pub message: String,
+}
+
+impl ShaderError {
+    pub(crate) fn at(file: &Path, line: u32, message: String) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            line,
+            column: 0,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (0, _) => write!(f, "{}: {}", self.file.display(), self.message),
+            (line, 0) => write!(f, "{}:{line}: {}", self.file.display(), self.message),
+            (line, column) => write!(
+                f,
+                "{}:{line}:{column}: {}",
+                self.file.display(),
+                self.message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// Validates preprocessed WGSL and turns it into a shader module.
+pub fn compile(
+    device: &Device,
+    shader: &ProcessedShader,
+    label: Option<&str>,
+) -> Result<ShaderModule, ShaderError> {
+    let module = naga::front::wgsl::parse_str(&shader.source)
+        .map_err(|e| shader.error(e.message().to_string(), e.location(&shader.source)))?;
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|e| {
+        let location = e.location(&shader.source);
+        shader.error(e.into_inner().to_string(), location)
+    })?;
+
+    // naga accepting the shader doesn't mean the device supports everything it uses.
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label,
+        source: wgpu::ShaderSource::Wgsl(shader.source.as_str().into()),
+    });
+
+    if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+        return Err(shader.error(error.to_string(), None));
+    }
+
+    Ok(module)
+}
+
+struct CachedModule {
+    module: ShaderModule,
+    files: Vec<PathBuf>,
+}
+
+/// Loads WGSL files through the [`Preprocessor`] and caches one module per file and
+/// [`ShaderDefs`] variant.
+pub struct ShaderLibrary {
+    preprocessor: Preprocessor,
+    device: Option<Device>,
+    modules: HashMap<(PathBuf, ShaderDefs), CachedModule>,
+}
+
+impl ShaderLibrary {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            preprocessor: Preprocessor::new(root),
+            device: None,
+            modules: HashMap::new(),
+        }
+    }
+
+    pub fn attach(&mut self, device: &Device) {
+        self.device = Some(device.clone());
+        self.modules.clear();
+    }
+
+    pub fn preprocessor(&self) -> &Preprocessor {
+        &self.preprocessor
+    }
+
+    /// Returns the module for `path` with `defs`, compiling it the first time it's asked for.
+    ///
+    /// Errors are [`ShaderError`]s and can be downcast to one.
+    pub fn get(
+        &mut self,
+        path: impl AsRef<Path>,
+        defs: &ShaderDefs,
+    ) -> anyhow::Result<ShaderModule> {
+        let key = (normalize(path.as_ref()), defs.clone());
+
+        if let Some(cached) = self.modules.get(&key) {
+            return Ok(cached.module.clone());
+        }
+
+        let device = self
+            .device
+            .as_ref()
+            .context("The shader library has no device yet")?;
+
+        let shader = self.preprocessor.process(&key.0, defs)?;
+        let module = compile(device, &shader, key.0.to_str())?;
+
+        self.modules.insert(
+            key,
+            CachedModule {
+                module: module.clone(),
+                files: shader.files().to_vec(),
+            },
+        );
+
+        Ok(module)
+    }
+
+    /// Drops every cached module that includes `path`, so it gets rebuilt on the next `get`.
+    pub fn invalidate(&mut self, path: impl AsRef<Path>) {
+        let path = normalize(path.as_ref());
+
+        self.modules
+            .retain(|_, cached| !cached.files.contains(&path));
+    }
+
+    pub fn clear(&mut self) {
+        self.modules.clear();
+    }
+
+    /// Number of cached modules, counting each variant separately.
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+}
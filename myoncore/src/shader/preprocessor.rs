@@ -0,0 +1,357 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::ShaderError;
+use crate::utils::normalize;
+
+/// A set of `#define`s that selects one variant of a shader.
+#[derive(Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct ShaderDefs {
+    defines: BTreeMap<String, String>,
+}
+
+impl ShaderDefs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines a flag for `#ifdef`.
+    pub fn define(self, name: impl Into<String>) -> Self {
+        self.value(name, "")
+    }
+
+    /// Defines a name that gets replaced with `value` wherever it appears in the source.
+    pub fn value(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.defines.insert(name.into(), value.to_string());
+        self
+    }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SourceLine {
+    file: usize,
+    line: u32,
+}
+
+/// WGSL with all directives resolved, plus where every line originally came from.
+#[derive(Clone, Debug)]
+pub struct ProcessedShader {
+    pub source: String,
+    files: Vec<PathBuf>,
+    lines: Vec<SourceLine>,
+}
+
+impl ProcessedShader {
+    /// Every file that ended up in the output, starting with the one that was processed.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Maps a 1-based line of [`ProcessedShader::source`] back to its file and line.
+    pub fn map_line(&self, line: u32) -> Option<(&Path, u32)> {
+        let source_line = self.lines.get(line.checked_sub(1)? as usize)?;
+
+        Some((&self.files[source_line.file], source_line.line))
+    }
+
+    pub(crate) fn error(
+        &self,
+        message: String,
+        location: Option<naga::SourceLocation>,
+    ) -> ShaderError {
+        let mapped = location.and_then(|location| {
+            let (file, line) = self.map_line(location.line_number)?;
+            Some((file.to_path_buf(), line, location.line_position))
+        });
+
+        match mapped {
+            Some((file, line, column)) => ShaderError {
+                file,
+                line,
+                column,
+                message,
+            },
+
+            None => ShaderError {
+                file: self.files.first().cloned().unwrap_or_default(),
+                line: 0,
+                column: 0,
+                message,
+            },
+        }
+    }
+}
+
+struct Condition {
+    active: bool,
+    parent_active: bool,
+    taken: bool,
+    has_else: bool,
+    line: u32,
+}
+
+/// Resolves `#include`, `#define`, `#undef`, `#ifdef`, `#ifndef`, `#else` and `#endif`.
+///
+/// Include paths are relative to the including file, or to the root when they start with
+/// `/`. Each file is only included once per shader, so shared files don't need guards.
+pub struct Preprocessor {
+    root: PathBuf,
+}
+
+impl Preprocessor {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn process(
+        &self,
+        path: impl AsRef<Path>,
+        defs: &ShaderDefs,
+    ) -> Result<ProcessedShader, ShaderError> {
+        let mut state = State::new(defs);
+
+        self.process_file(&normalize(path.as_ref()), &mut state, None)?;
+
+        Ok(state.output)
+    }
+
+    /// Processes source that doesn't live in a file. `path` is only used for error messages
+    /// and to resolve relative includes.
+    pub fn process_source(
+        &self,
+        path: impl AsRef<Path>,
+        source: &str,
+        defs: &ShaderDefs,
+    ) -> Result<ProcessedShader, ShaderError> {
+        let mut state = State::new(defs);
+
+        let path = normalize(path.as_ref());
+        state.included.insert(path.clone());
+        self.process_lines(&path, source, &mut state)?;
+
+        Ok(state.output)
+    }
+
+    fn process_file(
+        &self,
+        path: &Path,
+        state: &mut State,
+        included_from: Option<(&Path, u32)>,
+    ) -> Result<(), ShaderError> {
+        if state.stack.iter().any(|file| file == path) {
+            let (file, line) = included_from.unwrap_or((path, 0));
+            return Err(ShaderError::at(
+                file,
+                line,
+                format!("{} includes itself", path.display()),
+            ));
+        }
+
+        if !state.included.insert(path.to_path_buf()) {
+            return Ok(());
+        }
+
+        let source = fs::read_to_string(self.root.join(path)).map_err(|e| {
+            let (file, line) = included_from.unwrap_or((path, 0));
+            ShaderError::at(
+                file,
+                line,
+                format!("Failed to read {}: {e}", path.display()),
+            )
+        })?;
+
+        state.stack.push(path.to_path_buf());
+        let result = self.process_lines(path, &source, state);
+        state.stack.pop();
+
+        result
+    }
+
+    fn process_lines(
+        &self,
+        path: &Path,
+        source: &str,
+        state: &mut State,
+    ) -> Result<(), ShaderError> {
+        let file = state.output.files.len();
+        state.output.files.push(path.to_path_buf());
+
+        let mut conditions: Vec<Condition> = Vec::new();
+
+        for (index, text) in source.lines().enumerate() {
+            let line = index as u32 + 1;
+            let active = conditions.last().is_none_or(|condition| condition.active);
+            let error = |message: String| ShaderError::at(path, line, message);
+
+            let Some(directive) = text.trim_start().strip_prefix('#') else {
+                if active {
+                    state
+                        .output
+                        .source
+                        .push_str(&substitute(text, &state.defines));
+                    state.output.source.push('\n');
+                    state.output.lines.push(SourceLine { file, line });
+                }
+
+                continue;
+            };
+
+            let (name, argument) = directive
+                .split_once(char::is_whitespace)
+                .map_or((directive.trim_end(), ""), |(name, argument)| {
+                    (name, argument.trim())
+                });
+
+            match name {
+                "ifdef" | "ifndef" => {
+                    let identifier = identifier(argument)
+                        .ok_or_else(|| error(format!("#{name} needs a name")))?;
+                    let defined = state.defines.contains_key(identifier);
+                    let condition = if name == "ifdef" { defined } else { !defined };
+
+                    conditions.push(Condition {
+                        active: active && condition,
+                        parent_active: active,
+                        taken: condition,
+                        has_else: false,
+                        line,
+                    });
+                }
+
+                "else" => {
+                    let condition = conditions
+                        .last_mut()
+                        .ok_or_else(|| error(String::from("#else without #ifdef")))?;
+
+                    if condition.has_else {
+                        return Err(error(String::from("#else after #else")));
+                    }
+
+                    condition.has_else = true;
+                    condition.active = condition.parent_active && !condition.taken;
+                }
+
+                "endif" => {
+                    conditions
+                        .pop()
+                        .ok_or_else(|| error(String::from("#endif without #ifdef")))?;
+                }
+
+                _ if !active => {}
+
+                "define" => {
+                    let (identifier_name, value) = argument
+                        .split_once(char::is_whitespace)
+                        .map_or((argument, ""), |(name, value)| (name, value.trim()));
+                    let identifier_name = identifier(identifier_name)
+                        .ok_or_else(|| error(String::from("#define needs a name")))?;
+
+                    state
+                        .defines
+                        .insert(identifier_name.to_string(), value.to_string());
+                }
+
+                "undef" => {
+                    let identifier = identifier(argument)
+                        .ok_or_else(|| error(String::from("#undef needs a name")))?;
+                    state.defines.remove(identifier);
+                }
+
+                "include" => {
+                    let target = argument
+                        .strip_prefix('"')
+                        .and_then(|argument| argument.strip_suffix('"'))
+                        .ok_or_else(|| error(String::from("#include needs a quoted path")))?;
+
+                    let target = match target.strip_prefix('/') {
+                        Some(target) => normalize(Path::new(target)),
+                        None => normalize(&path.parent().unwrap_or(Path::new("")).join(target)),
+                    };
+
+                    self.process_file(&target, state, Some((path, line)))?;
+                }
+
+                _ => return Err(error(format!("Unknown directive #{name}"))),
+            }
+        }
+
+        if let Some(condition) = conditions.last() {
+            return Err(ShaderError::at(
+                path,
+                condition.line,
+                String::from("#ifdef without #endif"),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+struct State {
+    defines: BTreeMap<String, String>,
+    included: HashSet<PathBuf>,
+    stack: Vec<PathBuf>,
+    output: ProcessedShader,
+}
+
+impl State {
+    fn new(defs: &ShaderDefs) -> Self {
+        Self {
+            defines: defs.defines.clone(),
+            included: HashSet::new(),
+            stack: Vec::new(),
+            output: ProcessedShader {
+                source: String::new(),
+                files: Vec::new(),
+                lines: Vec::new(),
+            },
+        }
+    }
+}
+
+fn identifier(text: &str) -> Option<&str> {
+    let mut chars = text.chars();
+    let first = chars.next()?;
+
+    ((first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_'))
+    .then_some(text)
+}
+
+/// Replaces every identifier that has a non-empty `#define` value.
+fn substitute(line: &str, defines: &BTreeMap<String, String>) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let word = &rest[..end];
+
+        match defines.get(word) {
+            Some(value) if !value.is_empty() => output.push_str(value),
+            _ => output.push_str(word),
+        }
+
+        rest = &rest[end..];
+    }
+
+    output.push_str(rest);
+
+    output
+}
@@ -0,0 +1,197 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use image::{Rgba, RgbaImage};
+
+use crate::engine::{AppHandler, Engine, EngineConfig};
+
+pub const UPDATE_ENV: &str = "MYON_UPDATE_GOLDEN";
+
+pub struct ImageComparison {
+    pub mismatched_pixels: usize,
+    pub max_difference: u8,
+    pub diff: RgbaImage,
+}
+
+pub fn render_frames<A: AppHandler>(
+    config: EngineConfig,
+    app: A,
+    frames: u32,
+) -> anyhow::Result<RgbaImage> {
+    let mut engine = Engine::new(config, app);
+    engine.run_headless(frames);
+
+    let renderer = engine.renderer().context("Headless renderer missing")?;
+
+    renderer.capture_frame()
+}
+
+/// Compares two images channel by channel. Pixels whose largest channel difference exceeds
+/// `tolerance` are counted as mismatched and painted red in the diff image, matching pixels
+/// are kept as a dimmed copy of the reference.
+pub fn compare_images(actual: &RgbaImage, reference: &RgbaImage, tolerance: u8) -> ImageComparison {
+    let (width, height) = reference.dimensions();
+    let mut diff = RgbaImage::new(width, height);
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+
+    for (x, y, expected) in reference.enumerate_pixels() {
+        let difference = actual
+            .get_pixel_checked(x, y)
+            .map(|pixel| {
+                pixel
+                    .0
+                    .iter()
+                    .zip(expected.0.iter())
+                    .map(|(a, b)| a.abs_diff(*b))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .unwrap_or(u8::MAX);
+
+        max_difference = max_difference.max(difference);
+
+        let pixel = if difference > tolerance {
+            mismatched_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = expected.0;
+            Rgba([r / 4, g / 4, b / 4, 255])
+        };
+
+        diff.put_pixel(x, y, pixel);
+    }
+
+    ImageComparison {
+        mismatched_pixels,
+        max_difference,
+        diff,
+    }
+}
+
+pub struct GoldenTest {
+    reference_dir: PathBuf,
+    output_dir: PathBuf,
+    frames: u32,
+    tolerance: u8,
+    max_mismatched_pixels: usize,
+}
+
+impl GoldenTest {
+    pub fn new(reference_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            reference_dir: reference_dir.into(),
+            output_dir: env::temp_dir().join("myon-golden"),
+            frames: 3,
+            tolerance: 2,
+            max_mismatched_pixels: 0,
+        }
+    }
+
+    pub fn output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = output_dir.into();
+        self
+    }
+
+    pub fn frames(mut self, frames: u32) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn max_mismatched_pixels(mut self, max_mismatched_pixels: usize) -> Self {
+        self.max_mismatched_pixels = max_mismatched_pixels;
+        self
+    }
+
+    /// Renders `app` headlessly and compares the last frame against `<reference_dir>/<name>.png`.
+    ///
+    /// Setting the `MYON_UPDATE_GOLDEN` environment variable writes the rendered frame as the
+    /// new reference instead of comparing.
+    pub fn check<A: AppHandler>(
+        &self,
+        name: &str,
+        config: EngineConfig,
+        app: A,
+    ) -> anyhow::Result<()> {
+        let actual = render_frames(config, app, self.frames)?;
+
+        self.check_image(name, &actual)
+    }
+
+    pub fn check_image(&self, name: &str, actual: &RgbaImage) -> anyhow::Result<()> {
+        let reference_path = self.reference_dir.join(format!("{name}.png"));
+
+        if env::var_os(UPDATE_ENV).is_some() {
+            save_png(actual, &reference_path)?;
+            tracing::info!("Updated golden image {}", reference_path.display());
+
+            return Ok(());
+        }
+
+        if !reference_path.exists() {
+            let actual_path = self.output_dir.join(format!("{name}.actual.png"));
+            save_png(actual, &actual_path)?;
+
+            anyhow::bail!(
+                "Missing golden image {}, rendered frame written to {} (set {UPDATE_ENV}=1 to accept it)",
+                reference_path.display(),
+                actual_path.display()
+            );
+        }
+
+        let reference = image::open(&reference_path)
+            .with_context(|| format!("Failed to read {}", reference_path.display()))?
+            .to_rgba8();
+
+        let actual_path = self.output_dir.join(format!("{name}.actual.png"));
+
+        if actual.dimensions() != reference.dimensions() {
+            save_png(actual, &actual_path)?;
+
+            anyhow::bail!(
+                "Golden image {name} size mismatch: rendered {:?}, expected {:?}, see {}",
+                actual.dimensions(),
+                reference.dimensions(),
+                actual_path.display()
+            );
+        }
+
+        let comparison = compare_images(actual, &reference, self.tolerance);
+
+        if comparison.mismatched_pixels <= self.max_mismatched_pixels {
+            return Ok(());
+        }
+
+        let diff_path = self.output_dir.join(format!("{name}.diff.png"));
+        save_png(actual, &actual_path)?;
+        save_png(&comparison.diff, &diff_path)?;
+
+        anyhow::bail!(
+            "Golden image {name} mismatch: {} pixels differ (max difference {}, tolerance {}), see {} and {}",
+            comparison.mismatched_pixels,
+            comparison.max_difference,
+            self.tolerance,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn save_png(image: &RgbaImage, path: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    image
+        .save_with_format(path, image::ImageFormat::Png)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -1,9 +1,15 @@
-use std::time::{Duration, Instant};
+use std::{
+    hint,
+    path::{Component, Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
 
 pub struct FrameTimer {
     last_instant: Instant,
     frame_count: u32,
     accumulated: Duration,
+    frame_time: Duration,
     pub fps: f32,
     pub delta_time: f32,
 }
@@ -20,6 +26,7 @@ impl FrameTimer {
             last_instant: Instant::now(),
             frame_count: 0,
             accumulated: Duration::ZERO,
+            frame_time: Duration::ZERO,
             fps: 0.0,
             delta_time: 0.0,
         }
@@ -29,6 +36,7 @@ impl FrameTimer {
         let now = Instant::now();
         let frame_time = now - self.last_instant;
         self.last_instant = now;
+        self.frame_time = frame_time;
 
         self.delta_time = frame_time.as_secs_f32();
 
@@ -45,4 +53,208 @@ impl FrameTimer {
     pub fn fps(&self) -> f32 {
         self.fps
     }
+
+    /// Time between the last two calls to [`FrameTimer::update`].
+    pub fn frame_time(&self) -> Duration {
+        self.frame_time
+    }
+}
+
+/// Splits variable frame times into a whole number of fixed-length update steps.
+///
+/// Leftover time is carried over to the next frame and exposed as [`FixedTimestep::alpha`],
+/// for interpolating between the last two update states when rendering. Frames that take
+/// longer than `max_steps` steps are clamped, so a slow update can't make the next frame
+/// even slower.
+pub struct FixedTimestep {
+    rate: f64,
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    pub fn new(rate: f64, max_steps: u32) -> Self {
+        Self {
+            rate,
+            step: period_of(rate, "Update rate"),
+            max_steps: max_steps.max(1),
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// Adds `frame_time` and returns how many steps to run.
+    pub fn advance(&mut self, frame_time: Duration) -> u32 {
+        self.accumulator += frame_time.min(self.step * self.max_steps);
+
+        let steps =
+            ((self.accumulator.as_nanos() / self.step.as_nanos()) as u32).min(self.max_steps);
+        self.accumulator -= self.step * steps;
+
+        steps
+    }
+
+    /// How far the accumulated time is into the next step, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.step.as_secs_f64()).min(1.0) as f32
+    }
+
+    /// Updates per second.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    pub fn delta_time(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+
+    /// Changes the update rate, keeping the time already accumulated.
+    pub fn set_rate(&mut self, rate: f64) {
+        self.step = period_of(rate, "Update rate");
+        self.rate = rate;
+    }
+
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps.max(1);
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+    }
+}
+
+/// Time between two ticks at `rate` per second, at least a nanosecond so it can be divided by.
+/// Panics with a message naming `what` if the rate isn't positive.
+fn period_of(rate: f64, what: &str) -> Duration {
+    assert!(
+        rate > 0.0 && rate.is_finite(),
+        "{what} must be positive and finite, got {rate}"
+    );
+
+    Duration::from_secs_f64(1.0 / rate).max(Duration::from_nanos(1))
+}
+
+/// Paces frames to a fixed rate. Sleeps for most of the remaining time and spins for the
+/// rest, since sleeping alone can overshoot by a whole scheduler tick.
+pub struct FrameLimiter {
+    period: Duration,
+    spin: Duration,
+    next: Option<Instant>,
+}
+
+impl FrameLimiter {
+    pub fn new(fps: f64) -> Self {
+        Self {
+            period: period_of(fps, "Frame rate cap"),
+            spin: Duration::from_millis(2),
+            next: None,
+        }
+    }
+
+    /// How long before the deadline to stop sleeping and start spinning.
+    pub fn spin(mut self, spin: Duration) -> Self {
+        self.spin = spin;
+        self
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    pub fn set_fps(&mut self, fps: f64) {
+        self.period = period_of(fps, "Frame rate cap");
+        self.next = None;
+    }
+
+    /// Blocks until the next frame is due. The first call returns right away, and a frame
+    /// that ran more than a period late starts a new schedule instead of rushing to catch up.
+    pub fn wait(&mut self) {
+        let now = Instant::now();
+        let deadline = self.next.unwrap_or(now);
+
+        if let Some(remaining) = deadline.checked_duration_since(now) {
+            if remaining > self.spin {
+                thread::sleep(remaining - self.spin);
+            }
+
+            while Instant::now() < deadline {
+                hint::spin_loop();
+            }
+        }
+
+        let now = Instant::now();
+        self.next = Some(if now - deadline > self.period {
+            now + self.period
+        } else {
+            deadline + self.period
+        });
+    }
+}
+
+/// When to draw next in [`crate::RunMode::Reactive`]: a deadline that frames set from egui's
+/// repaint requests, and that anything happening in the background can pull forward.
+#[derive(Default)]
+pub struct RedrawSchedule {
+    next: Option<Instant>,
+}
+
+impl RedrawSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the deadline after a frame was drawn. `Duration::MAX` waits for something
+    /// to happen.
+    pub fn after_frame(&mut self, now: Instant, delay: Duration) {
+        self.next = now.checked_add(delay);
+    }
+
+    /// Draws no later than `delay` from `now`, keeping an earlier deadline.
+    pub fn redraw_after(&mut self, now: Instant, delay: Duration) {
+        if let Some(at) = now.checked_add(delay) {
+            self.next = Some(self.next.map_or(at, |next| next.min(at)));
+        }
+    }
+
+    /// Whether a frame is due at `now`, clearing the deadline if it is.
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        let due = self.next.is_some_and(|next| next <= now);
+        if due {
+            self.next = None;
+        }
+
+        due
+    }
+
+    /// When to wake up next, `None` to sleep until an event arrives. With `poll`, wakes at
+    /// least that often to check things that can't wake the event loop themselves.
+    pub fn wake_at(&self, now: Instant, poll: Option<Duration>) -> Option<Instant> {
+        let poll_at = poll.and_then(|poll| now.checked_add(poll));
+
+        match (self.next, poll_at) {
+            (Some(next), Some(poll_at)) => Some(next.min(poll_at)),
+            (next, poll_at) => next.or(poll_at),
+        }
+    }
+}
+
+/// Removes `.` and `..` so that the same file always gets the same path.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
 }
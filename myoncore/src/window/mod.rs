@@ -1,10 +1,12 @@
+use std::sync::Arc;
+
 use winit::{
     event_loop::ActiveEventLoop,
     window::{Window, WindowAttributes},
 };
 
 pub struct WindowSystem {
-    pub window: Window,
+    pub window: Arc<Window>,
 }
 
 impl WindowSystem {
@@ -13,6 +15,8 @@ impl WindowSystem {
             .create_window(window_attributes)
             .expect("Failed to create window!");
 
-        Self { window }
+        Self {
+            window: Arc::new(window),
+        }
     }
 }
@@ -0,0 +1,83 @@
+use myoncore::{
+    EngineConfig,
+    graphics::{AdapterSettings, Graphics, list_adapters},
+};
+use wgpu::{Backends, Features, Limits};
+
+#[test]
+fn lists_and_picks_adapters_by_name_and_backend() {
+    let graphics = Graphics::new_headless(8, 8);
+    let info = graphics.adapter.get_info();
+
+    let adapters = list_adapters(Backends::all());
+    assert!(adapters.iter().any(|adapter| adapter.name == info.name));
+
+    let settings = AdapterSettings {
+        preferred_adapter: Some((info.name.clone(), info.backend)),
+        ..AdapterSettings::headless()
+    };
+    let graphics = Graphics::new_headless_with(8, 8, &settings);
+    assert_eq!(graphics.adapter.get_info().name, info.name);
+    assert_eq!(graphics.adapter.get_info().backend, info.backend);
+
+    let settings = AdapterSettings {
+        preferred_adapter: Some((String::from("No Such GPU"), info.backend)),
+        ..AdapterSettings::headless()
+    };
+    let graphics = Graphics::new_headless_with(8, 8, &settings);
+    assert_eq!(graphics.size(), (8, 8));
+}
+
+#[test]
+fn enables_supported_optional_features() {
+    let optional = Features::DEPTH32FLOAT_STENCIL8
+        | Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+        | Features::TEXTURE_COMPRESSION_ASTC;
+
+    let settings = AdapterSettings {
+        optional_features: optional,
+        ..AdapterSettings::headless()
+    };
+    let graphics = Graphics::new_headless_with(8, 8, &settings);
+
+    let supported = graphics.adapter.features() & optional;
+    assert_eq!(graphics.features() & optional, supported);
+    assert_eq!(settings.features_for(&graphics.adapter), supported);
+}
+
+#[test]
+fn fits_default_limits_to_the_adapter() {
+    let graphics = Graphics::new_headless(8, 8);
+    let supported = graphics.adapter.limits();
+
+    let limits = AdapterSettings::headless().limits_for(&graphics.adapter);
+    assert!(limits.check_limits(&supported));
+    assert!(graphics.device.limits().check_limits(&supported));
+
+    let settings = AdapterSettings {
+        limits: Some(Limits::downlevel_webgl2_defaults()),
+        ..AdapterSettings::headless()
+    };
+    assert_eq!(
+        settings.limits_for(&graphics.adapter),
+        Limits::downlevel_webgl2_defaults()
+    );
+}
+
+#[test]
+fn config_keeps_headless_adapter_defaults() {
+    let config = EngineConfig::new().limits(Limits::downlevel_defaults());
+
+    let headless = config.adapter_settings(true);
+    assert_eq!(headless.backends, Backends::all());
+    assert!(headless.force_fallback_adapter);
+    assert_eq!(headless.limits, Some(Limits::downlevel_defaults()));
+
+    let windowed = config.adapter_settings(false);
+    assert_eq!(windowed.backends, Backends::PRIMARY);
+    assert!(!windowed.force_fallback_adapter);
+
+    let config = config.backends(Backends::GL).force_fallback_adapter(false);
+    assert_eq!(config.adapter_settings(true).backends, Backends::GL);
+    assert!(!config.adapter_settings(true).force_fallback_adapter);
+}
@@ -0,0 +1,208 @@
+use std::{
+    io::Cursor,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use image::{ImageFormat, Rgba, RgbaImage};
+use myoncore::{
+    assets::{
+        Asset, AssetContext, AssetEvent, AssetServer, Handle, LoadState, MeshData, Shader, Text,
+        TextureData, TextureLoader,
+    },
+    graphics::Graphics,
+    renderer::Texture,
+};
+
+fn encode(image: &RgbaImage, format: ImageFormat) -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, format).unwrap();
+    bytes.into_inner()
+}
+
+#[test]
+fn decodes_png_and_tga() {
+    let image = RgbaImage::from_fn(3, 2, |x, y| Rgba([x as u8 * 80, y as u8 * 120, 7, 255]));
+
+    for (format, path) in [(ImageFormat::Png, "a.png"), (ImageFormat::Tga, "a.TGA")] {
+        let data = TextureData::from_bytes_at(&encode(&image, format), Path::new(path)).unwrap();
+
+        assert_eq!((data.width, data.height), (3, 2));
+        assert_eq!(data.format, wgpu::TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(data.levels, vec![image.as_raw().clone()]);
+    }
+
+    let tga = encode(&image, ImageFormat::Tga);
+    assert!(TextureData::from_bytes(&tga).is_err());
+
+    let error = TextureData::from_bytes_at(b"not an image", Path::new("a.png")).unwrap_err();
+    assert_eq!(error.to_string(), "Unknown image format");
+}
+
+#[test]
+fn uploads_with_generated_mipmaps() {
+    let graphics = Graphics::new_headless(1, 1);
+    let mut loader = TextureLoader::new(&graphics.device, &graphics.queue);
+
+    let image = RgbaImage::from_pixel(16, 8, Rgba([255, 0, 0, 255]));
+    let texture = loader
+        .load_from_bytes(&encode(&image, ImageFormat::Png), Some("Red"))
+        .unwrap();
+
+    assert_eq!(texture.size(), (16, 8));
+    assert_eq!(texture.texture.mip_level_count(), 5);
+}
+
+#[test]
+fn rejects_textures_the_device_cant_hold() {
+    let graphics = Graphics::new_headless(1, 1);
+    let mut loader = TextureLoader::new(&graphics.device, &graphics.queue);
+
+    let empty = TextureData::from_rgba8(0, 0, Vec::new());
+    assert!(loader.create(&empty, None).is_err());
+
+    let width = graphics.device.limits().max_texture_dimension_2d + 1;
+    let wide = TextureData::from_rgba8(width, 1, vec![0; width as usize * 4]);
+    let Err(error) = loader.create(&wide, None) else {
+        panic!("Created a {width}x1 texture");
+    };
+    assert!(error.to_string().contains("isn't between 1 and"), "{error}");
+}
+
+#[test]
+fn asset_server_loads_in_background_and_dedupes() {
+    let root = std::env::temp_dir().join(format!("myon-assets-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+
+    let image = RgbaImage::from_pixel(4, 4, Rgba([0, 255, 0, 255]));
+    std::fs::write(root.join("green.png"), encode(&image, ImageFormat::Png)).unwrap();
+    std::fs::write(root.join("broken.png"), b"not an image").unwrap();
+
+    let graphics = Graphics::new_headless(1, 1);
+    let mut assets = AssetServer::new(&root);
+
+    let green: Handle<Texture> = assets.load("green.png");
+    let broken: Handle<Texture> = assets.load("broken.png");
+    let missing: Handle<Shader> = assets.load("missing.wgsl");
+
+    assert_eq!(assets.load::<Texture>("green.png"), green);
+    assert_eq!(assets.load::<Texture>("./green.png"), green);
+    assert_eq!(assets.load::<Texture>("sub/../green.png"), green);
+    assert_eq!(assets.load_state(green), LoadState::Loading);
+
+    // Without a GPU the decoded texture has nowhere to go yet.
+    assets.wait();
+    assert_eq!(assets.load_state(green), LoadState::Loading);
+
+    assets.attach(AssetContext::new(&graphics.device, &graphics.queue));
+    assets.update();
+
+    assert!(assets.is_loaded(green));
+    assert_eq!(assets.get(green).unwrap().size(), (4, 4));
+    assert!(matches!(assets.load_state(broken), LoadState::Failed(_)));
+    assert!(matches!(assets.load_state(missing), LoadState::Failed(_)));
+    assert_eq!(assets.pending(), 0);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn parses_obj_meshes() {
+    let obj = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nf 1/1 2/1 3/1 4/1\n";
+    let mesh = MeshData::from_obj(obj).unwrap();
+
+    assert_eq!(mesh.vertices.len(), 4);
+    assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+    assert_eq!(mesh.vertices[0].normal, [0.0, 0.0, 1.0]);
+    assert_eq!(mesh.vertices[0].uv, [0.0, 1.0]);
+
+    assert!(MeshData::from_obj("v 0 0 0\nf 1 2 3\n").is_err());
+}
+
+#[test]
+fn reloads_changed_files_in_place() {
+    let root = std::env::temp_dir().join(format!("myon-reload-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+
+    std::fs::write(root.join("config.txt"), "first").unwrap();
+    let image = RgbaImage::from_pixel(2, 2, Rgba([0, 0, 255, 255]));
+    std::fs::write(root.join("blue.png"), encode(&image, ImageFormat::Png)).unwrap();
+
+    let graphics = Graphics::new_headless(1, 1);
+    let mut assets = AssetServer::new(&root);
+    assets.attach(AssetContext::new(&graphics.device, &graphics.queue));
+    assets.watch().unwrap();
+
+    // Written differently from the watcher's paths, which are relative to the root.
+    let config: Handle<Text> = assets.load("./sub/../config.txt");
+    let blue: Handle<Texture> = assets.load("blue.png");
+    assets.wait();
+    assert_eq!(assets.get(config).unwrap().contents, "first");
+
+    std::fs::write(root.join("config.txt"), "second").unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut events = Vec::new();
+    while assets.get(config).unwrap().contents != "second" && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+        assets.update();
+        assets.wait();
+        events.extend(assets.take_events());
+    }
+
+    assert_eq!(assets.get(config).unwrap().contents, "second");
+    assert!(
+        events
+            .iter()
+            .any(|event| event.is(config) && matches!(event, AssetEvent::Reloaded { .. }))
+    );
+
+    // A broken file is reported but the handle keeps the last good version.
+    std::fs::write(root.join("blue.png"), b"not an image").unwrap();
+    assets.reload(blue);
+    assets.wait();
+
+    assert!(
+        assets
+            .take_events()
+            .iter()
+            .any(|event| event.is(blue) && matches!(event, AssetEvent::ReloadFailed { .. }))
+    );
+    assert!(assets.is_loaded(blue));
+    assert_eq!(assets.get(blue).unwrap().size(), (2, 2));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+struct Panicky;
+
+impl Asset for Panicky {
+    type Data = ();
+
+    fn decode(_bytes: Vec<u8>, _path: &Path) -> anyhow::Result<Self::Data> {
+        panic!("decoder bug");
+    }
+
+    fn create(_data: (), _path: &Path, _ctx: &mut AssetContext) -> anyhow::Result<Self> {
+        Ok(Self)
+    }
+}
+
+#[test]
+fn reports_panicking_decoders_as_failures() {
+    let root = std::env::temp_dir().join(format!("myon-panic-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("file.bin"), b"data").unwrap();
+
+    let mut assets = AssetServer::new(&root);
+    let handle: Handle<Panicky> = assets.load("file.bin");
+    assets.wait();
+
+    assert_eq!(assets.pending(), 0);
+    let LoadState::Failed(error) = assets.load_state(handle) else {
+        panic!("{:?}", assets.load_state(handle));
+    };
+    assert!(error.contains("decoder bug"), "{error}");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,133 @@
+use glam::{Mat4, Vec2, Vec3};
+use myoncore::{
+    AppHandler, Engine, EngineConfig, EngineContext,
+    ecs::World,
+    gui::Gui,
+    renderer::{
+        Camera, Projection, Renderer, ScalingMode, Sprite, SpriteBatch, Texture,
+        camera::active_camera,
+    },
+    scene::{Transform, propagate_transforms},
+    utils::FrameTimer,
+};
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
+
+#[test]
+fn projections_follow_the_viewport() {
+    let camera = Camera::perspective(60f32.to_radians(), 0.1, 100.0);
+
+    for viewport in [Vec2::new(200.0, 100.0), Vec2::new(100.0, 400.0)] {
+        let projection = camera.projection.matrix(viewport);
+        let aspect = viewport.x / viewport.y;
+        assert!((projection.x_axis.x * aspect - projection.y_axis.y).abs() < 1e-5);
+    }
+
+    let Projection::Orthographic(pixel_perfect) = Camera::orthographic(ScalingMode::PixelPerfect {
+        width: 320,
+        height: 240,
+    })
+    .projection
+    else {
+        unreachable!();
+    };
+
+    assert_eq!(pixel_perfect.scale(Vec2::new(640.0, 480.0)), 2.0);
+    assert_eq!(pixel_perfect.scale(Vec2::new(1000.0, 700.0)), 2.0);
+    assert_eq!(pixel_perfect.scale(Vec2::new(100.0, 100.0)), 1.0);
+
+    // A 2x scale shows 325 units of a 650 pixel wide window, not just 320.
+    let matrix = pixel_perfect.matrix(Vec2::new(650.0, 480.0));
+    let edge = matrix.project_point3(Vec3::new(162.5, 0.0, 0.0));
+    assert!((edge.x - 1.0).abs() < 1e-5, "{edge}");
+}
+
+#[derive(Default)]
+struct CameraApp {
+    seen: Vec<Mat4>,
+    batch: Option<SpriteBatch>,
+    texture: Option<Texture>,
+    top: Option<[u8; 4]>,
+}
+
+impl AppHandler for CameraApp {
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
+
+    fn on_update(&mut self, _ctx: &mut EngineContext, _dt: f32) {}
+
+    fn on_render(&mut self, _ctx: &mut EngineContext, renderer: &mut Renderer, _alpha: f32) {
+        self.seen.push(renderer.camera().view_proj);
+
+        // Red on top, green at the bottom.
+        let texture = self.texture.get_or_insert_with(|| {
+            let pixels = [[255, 0, 0, 255], [0, 255, 0, 255]];
+            Texture::from_rgba8(
+                renderer.device(),
+                renderer.queue(),
+                1,
+                2,
+                pixels.as_flattened(),
+                Some("Red Over Green"),
+            )
+        });
+        let batch = self.batch.get_or_insert_with(|| SpriteBatch::new(renderer));
+
+        batch.draw(texture, Sprite::new(Vec2::ZERO, Vec2::splat(16.0)));
+        batch.flush(renderer);
+
+        let frame = renderer.capture_frame().unwrap();
+        self.top = Some(frame.get_pixel(16, 11).0);
+    }
+
+    fn on_gui(
+        &mut self,
+        _gui: &mut Gui,
+        _frametimer: &FrameTimer,
+        _window: Option<&Window>,
+        _event_loop: Option<&ActiveEventLoop>,
+    ) {
+    }
+}
+
+#[test]
+fn renders_from_the_active_camera() {
+    let config = EngineConfig::new().width(32).height(32).gamepads(false);
+    let mut engine = Engine::new(config, CameraApp::default());
+
+    engine.run_headless(1);
+    let pixel_space = Mat4::orthographic_rh(0.0, 32.0, 32.0, 0.0, -1.0, 1.0);
+    assert_eq!(engine.app().seen, [pixel_space]);
+
+    let world = &mut engine.context().world;
+    world.spawn((
+        Transform::from_xyz(100.0, 0.0, 0.0),
+        Camera::default().active(false),
+    ));
+    let camera = Camera::orthographic(ScalingMode::WindowSize);
+    world.spawn((Transform::IDENTITY, camera));
+
+    engine.run_headless(1);
+    let expected = camera.view_projection(&Default::default(), Vec2::splat(32.0));
+    assert_eq!(engine.app().seen[1], expected);
+
+    // With y up, the sprite covers 8..24 on screen and is still drawn upright.
+    let [r, g, _, _] = engine.app().top.unwrap();
+    assert!(r > 200 && g < 50, "{:?}", engine.app().top);
+}
+
+#[test]
+fn picks_the_camera_with_the_highest_order() {
+    let mut world = World::new();
+    world.spawn((Transform::IDENTITY, Camera::default()));
+    let first = world.spawn((Transform::IDENTITY, Camera::default().order(2)));
+    world.spawn((
+        Transform::IDENTITY,
+        Camera::default().order(5).active(false),
+    ));
+    let second = world.spawn((Transform::IDENTITY, Camera::default().order(2)));
+    propagate_transforms(&mut world);
+
+    assert_eq!(active_camera(&world).unwrap().0, first);
+
+    world.despawn(first);
+    assert_eq!(active_camera(&world).unwrap().0, second);
+}
@@ -0,0 +1,32 @@
+use myoncore::{graphics::Graphics, renderer::Renderer};
+use wgpu::TextureFormat;
+
+#[test]
+fn recreates_depth_texture_on_resize() {
+    let mut graphics = Graphics::new_headless(16, 8);
+    let mut renderer = Renderer::new(&graphics, Some(TextureFormat::Depth24PlusStencil8));
+
+    renderer.begin_frame(&graphics).unwrap();
+    assert!(renderer.depth_texture_view.is_some());
+    let depth = renderer.depth_texture().unwrap();
+    assert_eq!((depth.width(), depth.height()), (16, 8));
+    assert_eq!(depth.format(), TextureFormat::Depth24PlusStencil8);
+    renderer.end_frame();
+
+    graphics.resize(40, 30);
+    renderer.begin_frame(&graphics).unwrap();
+    let depth = renderer.depth_texture().unwrap();
+    assert_eq!((depth.width(), depth.height()), (40, 30));
+    renderer.end_frame();
+}
+
+#[test]
+fn depth_texture_is_optional() {
+    let graphics = Graphics::new_headless(16, 8);
+    let mut renderer = Renderer::new(&graphics, None);
+
+    renderer.begin_frame(&graphics).unwrap();
+    assert!(renderer.depth_texture_view.is_none());
+    assert!(renderer.depth_texture().is_none());
+    renderer.end_frame();
+}
@@ -0,0 +1,183 @@
+use myoncore::{
+    AppHandler, Engine, EngineConfig, EngineContext,
+    ecs::{Entity, Stage, With, Without, World},
+    gui::Gui,
+    renderer::Renderer,
+    utils::FrameTimer,
+};
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Position(f32);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Velocity(f32);
+
+struct Frozen;
+
+#[test]
+fn recycles_entities_with_new_generations() {
+    let mut world = World::new();
+
+    let a = world.spawn((Position(1.0),));
+    let b = world.spawn((Position(2.0), Velocity(1.0)));
+    assert_eq!(world.len(), 2);
+
+    assert!(world.despawn(a));
+    assert!(!world.despawn(a));
+    assert!(!world.is_alive(a));
+
+    let c = world.spawn_empty();
+    assert_eq!(c.index(), a.index());
+    assert_ne!(c, a);
+    assert!(world.get::<Position>(a).is_none());
+    assert!(!world.insert(a, Velocity(5.0)));
+
+    assert!(world.insert(c, Velocity(3.0)));
+    assert_eq!(*world.get::<Velocity>(c).unwrap(), Velocity(3.0));
+    assert_eq!(world.remove::<Velocity>(b), Some(Velocity(1.0)));
+    assert!(!world.has::<Velocity>(b) && world.has::<Position>(b));
+
+    world.clear();
+    assert!(world.is_empty() && !world.is_alive(b));
+}
+
+#[test]
+fn queries_components_with_filters() {
+    let mut world = World::new();
+
+    let moving = world.spawn((Position(0.0), Velocity(2.0)));
+    let frozen = world.spawn((Position(0.0), Velocity(2.0), Frozen));
+    let still = world.spawn((Position(5.0),));
+
+    world
+        .query_filtered::<(&mut Position, &Velocity), Without<Frozen>>()
+        .for_each(|(position, velocity)| position.0 += velocity.0);
+
+    assert_eq!(*world.get::<Position>(moving).unwrap(), Position(2.0));
+    assert_eq!(*world.get::<Position>(frozen).unwrap(), Position(0.0));
+
+    let query = world.query::<(Entity, &Position, Option<&Velocity>)>();
+    let mut found: Vec<_> = query
+        .iter()
+        .map(|(entity, _, v)| (entity, v.copied()))
+        .collect();
+    found.sort_by_key(|(entity, _)| *entity);
+    assert_eq!(
+        found,
+        [
+            (moving, Some(Velocity(2.0))),
+            (frozen, Some(Velocity(2.0))),
+            (still, None)
+        ]
+    );
+    drop(query);
+
+    let mut query = world.query_filtered::<&mut Velocity, With<Frozen>>();
+    assert_eq!(query.entities(), [frozen]);
+    assert!(query.get(moving).is_none());
+    query.get(frozen).unwrap().0 = 0.0;
+
+    // Other components can still be borrowed while a query is alive.
+    assert_eq!(*world.get::<Position>(still).unwrap(), Position(5.0));
+    drop(query);
+
+    assert!(world.query::<&String>().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "already borrowed")]
+fn panics_on_conflicting_borrows() {
+    let mut world = World::new();
+    world.spawn((Position(0.0),));
+
+    let _query = world.query::<&mut Position>();
+    world.get::<Position>(world.entities()[0]);
+}
+
+#[test]
+fn stores_resources_by_type() {
+    let mut world = World::new();
+
+    assert!(world.get_resource::<u32>().is_none());
+    assert_eq!(world.insert_resource(1u32), None);
+    assert_eq!(world.insert_resource(2u32), Some(1));
+
+    *world.resource_mut::<u32>() += 1;
+    assert_eq!(*world.resource::<u32>(), 3);
+
+    assert_eq!(world.remove_resource::<u32>(), Some(3));
+    assert!(!world.contains_resource::<u32>());
+}
+
+#[derive(Default)]
+struct Log(Vec<&'static str>);
+
+struct Recorder;
+
+impl AppHandler for Recorder {
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
+
+    fn on_update(&mut self, ctx: &mut EngineContext, _dt: f32) {
+        ctx.world.resource_mut::<Log>().0.push("on_update");
+    }
+
+    fn on_render(&mut self, ctx: &mut EngineContext, _renderer: &mut Renderer, _alpha: f32) {
+        ctx.world.resource_mut::<Log>().0.push("on_render");
+    }
+
+    fn on_gui(
+        &mut self,
+        _gui: &mut Gui,
+        _frametimer: &FrameTimer,
+        _window: Option<&Window>,
+        _event_loop: Option<&ActiveEventLoop>,
+    ) {
+    }
+}
+
+fn log(name: &'static str) -> impl FnMut(&mut EngineContext) {
+    move |ctx| ctx.world.resource_mut::<Log>().0.push(name)
+}
+
+#[test]
+fn runs_stages_from_the_engine_loop() {
+    let config = EngineConfig::new().width(8).height(8);
+    let mut engine = Engine::new(config, Recorder);
+
+    let ctx = engine.context();
+    ctx.world.insert_resource(Log::default());
+    ctx.add_system(Stage::Render, log("render"));
+    ctx.add_system(Stage::PostUpdate, log("post_update"));
+    ctx.add_system(Stage::Update, |ctx: &mut EngineContext| {
+        let first = ctx.world.resource::<Log>().0.len() == 1;
+        ctx.world.resource_mut::<Log>().0.push("update");
+
+        // Systems added while their stage runs start on the next run.
+        if first {
+            ctx.add_system(Stage::Update, log("added"));
+        }
+    });
+    ctx.add_system(Stage::PreUpdate, log("pre_update"));
+
+    engine.run_headless(2);
+
+    assert_eq!(
+        engine.context().world.resource::<Log>().0,
+        [
+            "pre_update",
+            "update",
+            "on_update",
+            "post_update",
+            "render",
+            "on_render",
+            "pre_update",
+            "update",
+            "added",
+            "on_update",
+            "post_update",
+            "render",
+            "on_render",
+        ]
+    );
+}
@@ -0,0 +1,161 @@
+use glam::{Vec2, Vec4};
+use image::{Rgba, RgbaImage};
+use myoncore::{
+    AppHandler, EngineConfig, EngineContext,
+    gui::Gui,
+    renderer::{Renderer, Sprite, SpriteBatch, Texture},
+    testing::{GoldenTest, compare_images},
+    utils::FrameTimer,
+};
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
+
+fn clear(renderer: &mut Renderer) {
+    let texture_view = renderer.texture_view.as_ref().expect("TextureView missing");
+    let encoder = renderer
+        .command_encoder
+        .as_mut()
+        .expect("CommandEncoder missing");
+
+    let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: texture_view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color {
+                    r: 0.2,
+                    g: 0.4,
+                    b: 0.8,
+                    a: 1.0,
+                }),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+}
+
+struct ClearAndPanel;
+
+impl AppHandler for ClearAndPanel {
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
+
+    fn on_update(&mut self, _ctx: &mut EngineContext, _dt: f32) {}
+
+    fn on_render(&mut self, _ctx: &mut EngineContext, renderer: &mut Renderer, _alpha: f32) {
+        clear(renderer);
+    }
+
+    fn on_gui(
+        &mut self,
+        gui: &mut Gui,
+        _frametimer: &FrameTimer,
+        _window: Option<&Window>,
+        _event_loop: Option<&ActiveEventLoop>,
+    ) {
+        egui::SidePanel::left("golden_panel")
+            .exact_width(48.0)
+            .show_separator_line(false)
+            .show(&gui.ctx, |_ui| {});
+    }
+}
+
+#[test]
+fn clear_and_panel_matches_golden() {
+    let config = EngineConfig::new().width(128).height(96);
+
+    GoldenTest::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"))
+        .tolerance(4)
+        .check("clear_and_panel", config, ClearAndPanel)
+        .unwrap();
+}
+
+#[derive(Default)]
+struct Sprites {
+    batch: Option<SpriteBatch>,
+    checker: Option<Texture>,
+}
+
+impl AppHandler for Sprites {
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
+
+    fn on_update(&mut self, _ctx: &mut EngineContext, _dt: f32) {}
+
+    fn on_render(&mut self, _ctx: &mut EngineContext, renderer: &mut Renderer, _alpha: f32) {
+        clear(renderer);
+
+        let checker = self.checker.get_or_insert_with(|| {
+            let pixels = [
+                [255, 255, 255, 255],
+                [0, 0, 0, 255],
+                [0, 0, 0, 255],
+                [255, 255, 255, 255],
+            ];
+            let mut texture = Texture::from_rgba8(
+                renderer.device(),
+                renderer.queue(),
+                2,
+                2,
+                pixels.as_flattened(),
+                Some("Checker"),
+            );
+            texture.sampler = renderer
+                .device()
+                .create_sampler(&wgpu::SamplerDescriptor::default());
+            texture
+        });
+        let batch = self.batch.get_or_insert_with(|| SpriteBatch::new(renderer));
+
+        batch.draw(
+            checker,
+            Sprite::new(Vec2::new(40.0, 48.0), Vec2::new(32.0, 32.0)).rotation(0.5),
+        );
+        batch.draw_quad(
+            Sprite::new(Vec2::new(88.0, 48.0), Vec2::new(40.0, 24.0))
+                .color(Vec4::new(1.0, 0.0, 0.0, 1.0))
+                .layer(1),
+        );
+        batch.draw_quad(
+            Sprite::new(Vec2::new(80.0, 40.0), Vec2::new(24.0, 24.0))
+                .color(Vec4::new(0.0, 1.0, 0.0, 0.5)),
+        );
+        batch.flush(renderer);
+    }
+
+    fn on_gui(
+        &mut self,
+        _gui: &mut Gui,
+        _frametimer: &FrameTimer,
+        _window: Option<&Window>,
+        _event_loop: Option<&ActiveEventLoop>,
+    ) {
+    }
+}
+
+#[test]
+fn sprite_batch_matches_golden() {
+    let config = EngineConfig::new().width(128).height(96);
+
+    GoldenTest::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"))
+        .tolerance(4)
+        .max_mismatched_pixels(16)
+        .check("sprite_batch", config, Sprites::default())
+        .unwrap();
+}
+
+#[test]
+fn compare_images_reports_mismatches() {
+    let reference = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+    let mut actual = reference.clone();
+    actual.put_pixel(1, 2, Rgba([13, 20, 30, 255]));
+    actual.put_pixel(3, 3, Rgba([200, 20, 30, 255]));
+
+    let comparison = compare_images(&actual, &reference, 3);
+
+    assert_eq!(comparison.mismatched_pixels, 1);
+    assert_eq!(comparison.max_difference, 190);
+    assert_eq!(*comparison.diff.get_pixel(3, 3), Rgba([255, 0, 0, 255]));
+    assert_ne!(*comparison.diff.get_pixel(1, 2), Rgba([255, 0, 0, 255]));
+}
@@ -0,0 +1,280 @@
+use myoncore::{
+    graphics::Graphics,
+    renderer::{RenderGraph, Renderer, TransientTexture},
+};
+use wgpu::TextureFormat;
+
+const BLIT_WGSL: &str = "
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureLoad(source, vec2<i32>(position.xy), 0);
+}
+";
+
+fn pass(graph: &mut RenderGraph, name: &str, reads: &[&str], writes: &[&str]) {
+    let mut builder = graph.add_pass(name);
+    for resource in reads {
+        builder = builder.read(*resource);
+    }
+    for resource in writes {
+        builder = builder.write(*resource);
+    }
+    builder.run(|_| {});
+}
+
+fn order(graph: &RenderGraph) -> Vec<&str> {
+    graph
+        .compile((64, 64))
+        .unwrap()
+        .order
+        .into_iter()
+        .map(|index| graph.pass_name(index).unwrap())
+        .collect()
+}
+
+#[test]
+fn orders_passes_by_their_resources() {
+    let color = TransientTexture::new(TextureFormat::Rgba16Float);
+
+    let mut graph = RenderGraph::new();
+    graph.create_texture("scene", color);
+    graph.create_texture("bloom", color.size(32, 32));
+    pass(
+        &mut graph,
+        "composite",
+        &["scene", "bloom"],
+        &[RenderGraph::TARGET],
+    );
+    pass(&mut graph, "bloom", &["scene"], &["bloom"]);
+    pass(&mut graph, "opaque", &[], &["scene", RenderGraph::DEPTH]);
+    pass(&mut graph, "transparent", &[RenderGraph::DEPTH], &["scene"]);
+    pass(&mut graph, "ui", &[], &[RenderGraph::TARGET]);
+
+    assert_eq!(
+        order(&graph),
+        ["opaque", "transparent", "bloom", "composite", "ui"]
+    );
+
+    let mut graph = RenderGraph::new();
+    graph.create_texture("a", color);
+    graph.create_texture("b", color);
+    pass(&mut graph, "first", &["b"], &["a"]);
+    pass(&mut graph, "second", &["a"], &["b"]);
+
+    let error = graph.compile((64, 64)).unwrap_err().to_string();
+    assert!(error.contains("first, second"), "{error}");
+
+    let mut graph = RenderGraph::new();
+    pass(&mut graph, "lonely", &["missing"], &[RenderGraph::TARGET]);
+    assert!(graph.compile((64, 64)).is_err());
+
+    let mut graph = RenderGraph::new();
+    graph.create_texture("empty", color);
+    pass(&mut graph, "reader", &["empty"], &[RenderGraph::TARGET]);
+    assert!(graph.compile((64, 64)).is_err());
+}
+
+#[test]
+fn aliases_transients_that_dont_overlap() {
+    let color = TransientTexture::new(TextureFormat::Rgba16Float);
+
+    let mut graph = RenderGraph::new();
+    graph.create_texture("a", color);
+    graph.create_texture("b", color);
+    graph.create_texture("c", color);
+    graph.create_texture("small", color.size(16, 16));
+    graph.create_texture("unused", color);
+    pass(&mut graph, "1", &[], &["a"]);
+    pass(&mut graph, "2", &["a"], &["b"]);
+    pass(&mut graph, "3", &["b"], &["c", "small"]);
+    pass(&mut graph, "4", &["c", "small"], &[RenderGraph::TARGET]);
+
+    let plan = graph.compile((64, 64)).unwrap();
+    assert_eq!(plan.slots, [Some(0), Some(1), Some(0), Some(2), None]);
+    assert_eq!(plan.slot_textures.len(), 3);
+    assert_eq!(plan.slot_textures[0].size, Some((64, 64)));
+    assert_eq!(plan.slot_textures[2].size, Some((16, 16)));
+}
+
+#[test]
+fn executes_graph_into_the_target() {
+    let graphics = Graphics::new_headless(8, 8);
+    let mut renderer = Renderer::new(&graphics, None);
+    let device = renderer.device().clone();
+
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Blit Shader"),
+        source: wgpu::ShaderSource::Wgsl(BLIT_WGSL.into()),
+    });
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Blit Pipeline"),
+        layout: None,
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(renderer.format().into())],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    });
+
+    for frame in 0..2 {
+        renderer.begin_frame(&graphics).unwrap();
+
+        let green = f64::from(frame);
+        let pipeline = pipeline.clone();
+        renderer
+            .graph
+            .create_texture("scene", TransientTexture::new(TextureFormat::Rgba8Unorm));
+        renderer
+            .graph
+            .add_pass("blit")
+            .read("scene")
+            .write(RenderGraph::TARGET)
+            .run(move |ctx| {
+                let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: None,
+                    layout: &pipeline.get_bind_group_layout(0),
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(ctx.view("scene")),
+                    }],
+                });
+
+                let mut pass = ctx.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Blit Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: ctx.view(RenderGraph::TARGET),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.draw(0..3, 0..1);
+            });
+        renderer
+            .graph
+            .add_pass("fill")
+            .write("scene")
+            .run(move |ctx| {
+                ctx.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Fill Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: ctx.view("scene"),
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color {
+                                r: 1.0,
+                                g: green,
+                                b: 0.0,
+                                a: 1.0,
+                            }),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+            });
+
+        renderer.execute_graph().unwrap();
+        assert!(renderer.graph.is_empty());
+
+        let image = renderer.capture_frame().unwrap();
+        assert_eq!(image.get_pixel(4, 4).0, [255, 255 * frame as u8, 0, 255]);
+        renderer.end_frame();
+    }
+}
+
+#[test]
+fn runs_the_overlay_last() {
+    let graphics = Graphics::new_headless(8, 8);
+    let mut renderer = Renderer::new(&graphics, None);
+
+    let clear = |view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder, color| {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+    };
+
+    // The scene pass reads the output, but the overlay still runs after it.
+    renderer.begin_frame(&graphics).unwrap();
+    renderer.set_overlay(move |ctx| {
+        clear(
+            ctx.view(RenderGraph::OUTPUT),
+            ctx.encoder,
+            wgpu::Color::BLUE,
+        )
+    });
+    renderer
+        .graph
+        .add_pass("scene")
+        .read(RenderGraph::OUTPUT)
+        .write(RenderGraph::TARGET)
+        .run(move |ctx| clear(ctx.view(RenderGraph::TARGET), ctx.encoder, wgpu::Color::RED));
+
+    renderer.execute_graph().unwrap();
+    let image = renderer.capture_frame().unwrap();
+    assert_eq!(image.get_pixel(4, 4).0, [0, 0, 255, 255]);
+    renderer.end_frame();
+
+    // A broken app pass is skipped, but the overlay is still drawn.
+    renderer.begin_frame(&graphics).unwrap();
+    renderer.set_overlay(move |ctx| {
+        clear(
+            ctx.view(RenderGraph::OUTPUT),
+            ctx.encoder,
+            wgpu::Color::GREEN,
+        )
+    });
+    renderer
+        .graph
+        .add_pass("broken")
+        .read("missing")
+        .write(RenderGraph::TARGET)
+        .run(move |ctx| clear(ctx.view(RenderGraph::TARGET), ctx.encoder, wgpu::Color::RED));
+
+    let error = renderer.execute_graph().unwrap_err();
+    assert!(format!("{error:#}").contains("unknown resource missing"));
+    let image = renderer.capture_frame().unwrap();
+    assert_eq!(image.get_pixel(4, 4).0, [0, 255, 0, 255]);
+    renderer.end_frame();
+}
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use glam::Vec2;
+use myoncore::input::{
+    AxisBinding, Binding, GamepadAxis, GamepadButton, GamepadEvent, Input, InputEvent, InputMap,
+    VirtualGamepads,
+};
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+fn key(code: KeyCode, pressed: bool) -> InputEvent {
+    InputEvent::Key { code, pressed }
+}
+
+#[test]
+fn tracks_pressed_held_and_released() {
+    let mut input = Input::new();
+
+    input.handle_event(&key(KeyCode::Space, true));
+    input.handle_event(&InputEvent::MouseButton {
+        button: MouseButton::Left,
+        pressed: true,
+    });
+    input.handle_event(&InputEvent::CursorMoved(Vec2::new(10.0, 20.0)));
+    input.handle_event(&InputEvent::MouseWheel(Vec2::new(0.0, 1.5)));
+
+    assert!(input.key_pressed(KeyCode::Space) && input.key_held(KeyCode::Space));
+    assert!(input.mouse_pressed(MouseButton::Left));
+    assert_eq!(input.mouse_position(), Vec2::new(10.0, 20.0));
+    assert_eq!(input.wheel_delta(), Vec2::new(0.0, 1.5));
+
+    input.end_frame();
+
+    assert!(!input.key_pressed(KeyCode::Space) && input.key_held(KeyCode::Space));
+    assert_eq!(input.wheel_delta(), Vec2::ZERO);
+    assert_eq!(input.mouse_position(), Vec2::new(10.0, 20.0));
+
+    input.handle_event(&key(KeyCode::Space, false));
+    assert!(input.key_released(KeyCode::Space) && !input.key_held(KeyCode::Space));
+
+    input.handle_event(&InputEvent::FocusLost);
+    assert!(input.mouse_released(MouseButton::Left));
+    assert!(!input.mouse_held(MouseButton::Left));
+}
+
+#[test]
+fn maps_actions_and_axes_from_ron() {
+    let map = InputMap::from_ron(
+        r#"(
+            actions: {
+                "jump": [Key(Space), Mouse(Left)],
+            },
+            axes: {
+                "move_x": [Buttons(negative: Key(KeyA), positive: Key(KeyD))],
+            },
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        map,
+        InputMap::new()
+            .bind("jump", Binding::Key(KeyCode::Space))
+            .bind("jump", Binding::Mouse(MouseButton::Left))
+            .bind_axis(
+                "move_x",
+                AxisBinding::Buttons {
+                    negative: Binding::Key(KeyCode::KeyA),
+                    positive: Binding::Key(KeyCode::KeyD),
+                },
+            )
+    );
+    assert_eq!(InputMap::from_ron(&map.to_ron().unwrap()).unwrap(), map);
+
+    let mut input = Input::new();
+    input.map = map;
+
+    input.handle_event(&key(KeyCode::Space, true));
+    input.handle_event(&InputEvent::MouseButton {
+        button: MouseButton::Left,
+        pressed: true,
+    });
+    input.handle_event(&key(KeyCode::KeyD, true));
+
+    assert!(input.action_pressed("jump") && input.action_held("jump"));
+    assert_eq!(input.axis("move_x"), 1.0);
+    assert_eq!(input.axis("unknown"), 0.0);
+
+    input.end_frame();
+    input.handle_event(&key(KeyCode::Space, false));
+    assert!(
+        !input.action_released("jump"),
+        "the mouse button is still held"
+    );
+
+    input.handle_event(&key(KeyCode::KeyA, true));
+    assert_eq!(input.axis("move_x"), 0.0);
+}
+
+#[test]
+fn maps_virtual_gamepads_like_keys() {
+    let pads = VirtualGamepads::new();
+
+    let mut input = Input::new();
+    input.gamepads.set_backend(pads.clone());
+    input.map = InputMap::new()
+        .bind("jump", Binding::Gamepad(GamepadButton::South))
+        .bind_axis("move_x", AxisBinding::GamepadAxis(GamepadAxis::LeftStickX));
+
+    let id = pads.connect("Virtual pad");
+    pads.press(id, GamepadButton::South);
+    pads.set_axis(id, GamepadAxis::LeftStickX, 0.1);
+    input.poll_gamepads();
+
+    assert_eq!(input.gamepads.get(id).unwrap().name, "Virtual pad");
+    assert!(input.action_pressed("jump"));
+    assert_eq!(input.axis("move_x"), 0.0, "inside the deadzone");
+
+    input.end_frame();
+    pads.set_axis(id, GamepadAxis::LeftStickX, -0.575);
+    input.poll_gamepads();
+
+    assert!(!input.action_pressed("jump") && input.action_held("jump"));
+    assert!((input.axis("move_x") + 0.5).abs() < 1e-4);
+
+    input
+        .gamepads
+        .rumble(id, 1.5, 0.25, Duration::from_millis(100))
+        .unwrap();
+    assert_eq!(pads.take_rumbles()[0].strong, 1.0);
+
+    input.end_frame();
+    pads.disconnect(id);
+    input.poll_gamepads();
+
+    assert!(input.action_released("jump"));
+    assert_eq!(input.axis("move_x"), 0.0);
+    assert_eq!(input.gamepads.events(), [GamepadEvent::Disconnected(id)]);
+
+    input.end_frame();
+    assert!(input.gamepads.get(id).is_none());
+}
+
+#[test]
+fn only_presses_can_be_captured_by_the_gui() {
+    assert!(key(KeyCode::Space, true).is_press());
+    assert!(InputEvent::MouseWheel(Vec2::Y).is_press());
+    assert!(!key(KeyCode::Space, false).is_press());
+    assert!(!InputEvent::CursorMoved(Vec2::ZERO).is_press());
+    assert!(!InputEvent::FocusLost.is_press());
+}
+
+#[test]
+fn mouse_delta_skips_cursor_jumps() {
+    let mut input = Input::new();
+
+    input.handle_event(&InputEvent::CursorMoved(Vec2::new(100.0, 50.0)));
+    assert_eq!(input.mouse_delta(), Vec2::ZERO);
+
+    input.handle_event(&InputEvent::CursorMoved(Vec2::new(103.0, 46.0)));
+    assert_eq!(input.mouse_delta(), Vec2::new(3.0, -4.0));
+    input.end_frame();
+
+    input.handle_event(&InputEvent::CursorLeft);
+    input.handle_event(&InputEvent::CursorMoved(Vec2::new(0.0, 10.0)));
+    assert_eq!(input.mouse_delta(), Vec2::ZERO);
+    assert_eq!(input.mouse_position(), Vec2::new(0.0, 10.0));
+
+    input.handle_event(&InputEvent::FocusLost);
+    input.handle_event(&InputEvent::CursorMoved(Vec2::new(20.0, 10.0)));
+    assert_eq!(input.mouse_delta(), Vec2::ZERO);
+}
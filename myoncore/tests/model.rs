@@ -0,0 +1,212 @@
+use glam::{Vec3, Vec4};
+use myoncore::{
+    AppHandler, Engine, EngineConfig, EngineContext,
+    assets::{Model, ModelData, ModelMesh},
+    gui::Gui,
+    renderer::{Camera, MeshBatch, Renderer},
+    scene::{Children, GlobalTransform, Parent, Transform},
+    utils::FrameTimer,
+};
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
+
+const GLTF: &str = r#"{
+    "asset": { "version": "2.0" },
+    "scene": 0,
+    "scenes": [{ "nodes": [0] }],
+    "nodes": [
+        { "name": "Root", "children": [1, 2] },
+        { "name": "Near", "mesh": 0 },
+        { "name": "Far", "mesh": 1, "translation": [0.0, 0.0, -1.0], "scale": [2.0, 2.0, 2.0] }
+    ],
+    "meshes": [
+        { "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }] },
+        {
+            "primitives": [
+                { "attributes": { "POSITION": 0 }, "indices": 1, "material": 1 },
+                { "attributes": { "POSITION": 0 }, "indices": 1 }
+            ]
+        }
+    ],
+    "materials": [
+        {
+            "name": "Red",
+            "pbrMetallicRoughness": { "baseColorFactor": [1.0, 0.0, 0.0, 1.0], "metallicFactor": 0.0 }
+        },
+        {
+            "name": "Green",
+            "pbrMetallicRoughness": { "baseColorFactor": [0.0, 1.0, 0.0, 1.0], "metallicFactor": 0.0 }
+        }
+    ],
+    "accessors": [
+        {
+            "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+            "min": [-1.0, -1.0, 0.0], "max": [1.0, 1.0, 0.0]
+        },
+        { "bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR" }
+    ],
+    "bufferViews": [
+        { "buffer": 0, "byteOffset": 0, "byteLength": 48 },
+        { "buffer": 0, "byteOffset": 48, "byteLength": 12 }
+    ],
+    "buffers": [{ "uri": "quad.bin", "byteLength": 60 }]
+}"#;
+
+/// A quad facing +Z, in the external buffer of [`GLTF`].
+fn quad_bin() -> Vec<u8> {
+    let positions: [[f32; 3]; 4] = [
+        [-1.0, -1.0, 0.0],
+        [1.0, -1.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [-1.0, 1.0, 0.0],
+    ];
+    let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+    let mut bytes = bytemuck::cast_slice(&positions).to_vec();
+    bytes.extend_from_slice(bytemuck::cast_slice(&indices));
+    bytes
+}
+
+fn write_model() -> std::path::PathBuf {
+    let root = std::env::temp_dir().join(format!("myon-model-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("scene.gltf"), GLTF).unwrap();
+    std::fs::write(root.join("quad.bin"), quad_bin()).unwrap();
+
+    root
+}
+
+#[test]
+fn decodes_gltf_documents() {
+    let root = write_model();
+    let data = ModelData::from_gltf(GLTF.as_bytes(), Some(&root)).unwrap();
+
+    assert_eq!(data.roots, [0]);
+    assert_eq!(data.nodes[0].children, [1, 2]);
+    assert_eq!(
+        data.nodes[2].transform.translation,
+        Vec3::new(0.0, 0.0, -1.0)
+    );
+    assert_eq!(data.nodes[2].transform.scale, Vec3::splat(2.0));
+
+    // The primitive without a material gets the default one.
+    assert_eq!(data.materials.len(), 3);
+    assert_eq!(data.materials[1].name.as_deref(), Some("Green"));
+    assert_eq!(data.materials[1].base_color, Vec4::new(0.0, 1.0, 0.0, 1.0));
+    assert_eq!(data.meshes[1][1].material, 2);
+
+    let quad = &data.meshes[0][0].mesh;
+    assert_eq!(quad.indices, [0, 1, 2, 0, 2, 3]);
+    assert!(
+        quad.vertices
+            .iter()
+            .all(|vertex| vertex.normal == [0.0, 0.0, 1.0])
+    );
+
+    assert!(ModelData::from_gltf(GLTF.as_bytes(), None).is_err());
+}
+
+#[derive(Default)]
+struct ModelApp {
+    batch: Option<MeshBatch>,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl AppHandler for ModelApp {
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
+
+    fn on_update(&mut self, _ctx: &mut EngineContext, _dt: f32) {}
+
+    fn on_render(&mut self, ctx: &mut EngineContext, renderer: &mut Renderer, _alpha: f32) {
+        let texture_view = renderer.texture_view.as_ref().expect("TextureView missing");
+        let encoder = renderer
+            .command_encoder
+            .as_mut()
+            .expect("CommandEncoder missing");
+
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: texture_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLUE),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        let batch = self.batch.get_or_insert_with(|| MeshBatch::new(renderer));
+        batch.draw_world(&ctx.world, &ctx.assets);
+        batch.flush(renderer);
+
+        let frame = renderer.capture_frame().unwrap();
+        self.pixels = [(32, 32), (2, 2)]
+            .map(|(x, y)| frame.get_pixel(x, y).0)
+            .to_vec();
+    }
+
+    fn on_gui(
+        &mut self,
+        _gui: &mut Gui,
+        _frametimer: &FrameTimer,
+        _window: Option<&Window>,
+        _event_loop: Option<&ActiveEventLoop>,
+    ) {
+    }
+}
+
+#[test]
+fn renders_loaded_models_with_depth() {
+    let root = write_model();
+    let config = EngineConfig::new()
+        .width(64)
+        .height(64)
+        .asset_root(&root)
+        .gamepads(false);
+    let mut engine = Engine::new(config, ModelApp::default());
+    engine.run_headless(1);
+
+    let ctx = engine.context();
+    let handle = ctx.assets.load::<Model>("scene.gltf");
+    ctx.assets.wait();
+
+    let model = ctx.assets.get(handle).expect("Model failed to load");
+    assert_eq!(model.find_node("Far"), Some(2));
+    let spawned = model.spawn(handle, &mut ctx.world);
+
+    assert_eq!(ctx.world.get::<Children>(spawned).unwrap().len(), 1);
+    let gltf_root = ctx.world.get::<Children>(spawned).unwrap().as_slice()[0];
+    let meshes: Vec<_> = ctx
+        .world
+        .query::<(&ModelMesh, &Parent)>()
+        .iter()
+        .map(|(mesh, parent)| (mesh.mesh, parent.get()))
+        .collect();
+    assert_eq!(meshes, [(0, gltf_root), (1, gltf_root)]);
+
+    ctx.world.spawn((
+        Transform::from_xyz(0.0, 0.0, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
+        Camera::perspective(45f32.to_radians(), 0.1, 100.0),
+    ));
+    engine.run_headless(1);
+
+    // The red quad is drawn first but is closer, so the green one only shows around it.
+    let [center, corner] = engine.app().pixels[..] else {
+        panic!("No frame captured");
+    };
+    assert!(center[0] > 100 && center[1] < 20, "{center:?}");
+    assert!(corner[1] > 100 && corner[0] < 20, "{corner:?}");
+
+    let far = engine
+        .context()
+        .world
+        .query::<(&GlobalTransform, &ModelMesh)>()
+        .iter()
+        .find(|(_, mesh)| mesh.mesh == 1)
+        .map(|(transform, _)| transform.translation())
+        .unwrap();
+    assert_eq!(far, Vec3::new(0.0, 0.0, -1.0));
+}
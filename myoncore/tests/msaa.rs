@@ -0,0 +1,87 @@
+use glam::Vec2;
+use image::RgbaImage;
+use myoncore::{
+    graphics::Graphics,
+    renderer::{DEPTH_FORMAT, Renderer, Sprite, SpriteBatch, Tonemapping},
+};
+
+fn draw_diamond(renderer: &mut Renderer, graphics: &Graphics) -> RgbaImage {
+    let mut batch = SpriteBatch::new(renderer);
+
+    renderer.begin_frame(graphics).unwrap();
+
+    let texture_view = renderer.texture_view.as_ref().unwrap();
+    let encoder = renderer.command_encoder.as_mut().unwrap();
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: texture_view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    batch.draw_quad(
+        Sprite::new(Vec2::new(16.0, 16.0), Vec2::new(16.0, 16.0))
+            .rotation(std::f32::consts::FRAC_PI_4),
+    );
+    batch.flush(renderer);
+
+    renderer.execute_graph().unwrap();
+    let image = renderer.capture_frame().unwrap();
+    renderer.end_frame();
+
+    image
+}
+
+fn partial_pixels(image: &RgbaImage) -> usize {
+    image
+        .pixels()
+        .filter(|pixel| pixel.0[0] > 0 && pixel.0[0] < 255)
+        .count()
+}
+
+#[test]
+fn resolves_multisampled_scene() {
+    let graphics = Graphics::new_headless(32, 32);
+
+    let mut renderer = Renderer::new(&graphics, Some(DEPTH_FORMAT));
+    assert_eq!(renderer.sample_count(), 1);
+    let aliased = draw_diamond(&mut renderer, &graphics);
+    assert_eq!(aliased.get_pixel(16, 16).0, [255, 255, 255, 255]);
+    assert_eq!(partial_pixels(&aliased), 0);
+
+    let mut renderer = Renderer::new(&graphics, Some(DEPTH_FORMAT)).msaa_samples(4);
+    assert_eq!(renderer.sample_count(), 4);
+    let smooth = draw_diamond(&mut renderer, &graphics);
+    assert_eq!(smooth.get_pixel(16, 16).0, [255, 255, 255, 255]);
+    assert_eq!(smooth.get_pixel(0, 0).0, [0, 0, 0, 255]);
+    assert!(partial_pixels(&smooth) > 0);
+    assert_eq!(renderer.depth_texture().unwrap().sample_count(), 4);
+
+    let mut renderer = Renderer::new(&graphics, None).hdr(true).msaa_samples(4);
+    renderer.post_process.tonemapping = Tonemapping::None;
+    let hdr = draw_diamond(&mut renderer, &graphics);
+    assert_eq!(hdr.get_pixel(16, 16).0, [255, 255, 255, 255]);
+    assert!(partial_pixels(&hdr) > 0);
+}
+
+#[test]
+fn falls_back_to_supported_sample_counts() {
+    let graphics = Graphics::new_headless(8, 8);
+
+    let renderer = Renderer::new(&graphics, None).msaa_samples(3);
+    assert!([1, 2].contains(&renderer.sample_count()));
+
+    let renderer = Renderer::new(&graphics, None).msaa_samples(64);
+    assert!((4..=16).contains(&renderer.sample_count()));
+
+    let renderer = Renderer::new(&graphics, None).msaa_samples(0);
+    assert_eq!(renderer.sample_count(), 1);
+}
@@ -0,0 +1,118 @@
+use image::RgbaImage;
+use myoncore::{
+    graphics::Graphics,
+    renderer::{HDR_FORMAT, Renderer, Texture, Tonemapping},
+};
+
+fn render(renderer: &mut Renderer, graphics: &Graphics, red: f64, green: f64) -> RgbaImage {
+    renderer.begin_frame(graphics).unwrap();
+
+    let texture_view = renderer.texture_view.as_ref().unwrap();
+    let encoder = renderer.command_encoder.as_mut().unwrap();
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: texture_view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color {
+                    r: red,
+                    g: green,
+                    b: 0.0,
+                    a: 1.0,
+                }),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    renderer.execute_graph().unwrap();
+    let image = renderer.capture_frame().unwrap();
+    renderer.end_frame();
+
+    image
+}
+
+fn assert_near(actual: [u8; 4], expected: [u8; 4]) {
+    let near = actual
+        .iter()
+        .zip(expected)
+        .all(|(actual, expected)| actual.abs_diff(expected) <= 2);
+    assert!(near, "{actual:?} != {expected:?}");
+}
+
+#[test]
+fn resolves_hdr_target_into_the_surface() {
+    let graphics = Graphics::new_headless(32, 32);
+    let renderer = Renderer::new(&graphics, None);
+    assert!(!renderer.is_hdr());
+    assert_eq!(renderer.format(), renderer.output_format());
+
+    let mut renderer = renderer.hdr(true);
+    assert_eq!(renderer.format(), HDR_FORMAT);
+
+    // Linear 0.5 and 0.25 are 188 and 137 in sRGB.
+    renderer.post_process.tonemapping = Tonemapping::None;
+    let image = render(&mut renderer, &graphics, 0.5, 0.25);
+    assert_near(image.get_pixel(16, 16).0, [188, 137, 0, 255]);
+
+    // Values above 1 survive until tonemapping.
+    let image = render(&mut renderer, &graphics, 4.0, 0.5);
+    assert_near(image.get_pixel(16, 16).0, [255, 188, 0, 255]);
+    renderer.post_process.exposure = 0.125;
+    let image = render(&mut renderer, &graphics, 4.0, 2.0);
+    assert_near(image.get_pixel(16, 16).0, [188, 137, 0, 255]);
+
+    renderer.post_process.exposure = 1.0;
+    renderer.post_process.tonemapping = Tonemapping::Reinhard;
+    let [red, green, ..] = render(&mut renderer, &graphics, 4.0, 4.0)
+        .get_pixel(16, 16)
+        .0;
+    assert!(red < 255 && red == green, "{red} {green}");
+
+    let image = render(&mut renderer, &graphics, 0.0, 0.0);
+    assert_eq!(image.get_pixel(16, 16).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn toggles_effects_between_frames() {
+    let graphics = Graphics::new_headless(32, 32);
+    let mut renderer = Renderer::new(&graphics, None).hdr(true);
+    renderer.post_process.tonemapping = Tonemapping::None;
+
+    renderer.post_process.vignette.enabled = true;
+    renderer.post_process.vignette.intensity = 1.0;
+    renderer.post_process.vignette.radius = 0.5;
+    renderer.post_process.vignette.smoothness = 0.0;
+    let image = render(&mut renderer, &graphics, 0.5, 0.5);
+    assert_near(image.get_pixel(16, 16).0, [188, 188, 0, 255]);
+    assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+
+    renderer.post_process.vignette.enabled = false;
+    let image = render(&mut renderer, &graphics, 0.5, 0.5);
+    assert_near(image.get_pixel(0, 0).0, [188, 188, 0, 255]);
+
+    // Half of what's above the threshold glows, and blurring a flat color keeps it flat.
+    renderer.post_process.bloom.enabled = true;
+    renderer.post_process.bloom.threshold = 0.25;
+    renderer.post_process.bloom.intensity = 1.0;
+    renderer.post_process.fxaa = true;
+    let image = render(&mut renderer, &graphics, 0.5, 0.0);
+    assert_near(image.get_pixel(16, 16).0, [225, 0, 0, 255]);
+
+    renderer.post_process.bloom.enabled = false;
+    renderer.post_process.color_grading.enabled = true;
+    renderer.post_process.color_grading.lut = Some(Texture::from_rgba8(
+        renderer.device(),
+        renderer.queue(),
+        4,
+        2,
+        &[0, 255, 0, 255].repeat(8),
+        None,
+    ));
+    let image = render(&mut renderer, &graphics, 0.5, 0.0);
+    assert_near(image.get_pixel(16, 16).0, [0, 255, 0, 255]);
+}
@@ -0,0 +1,79 @@
+use myoncore::graphics::{Graphics, PresentSettings};
+use wgpu::{CompositeAlphaMode, PresentMode};
+
+#[test]
+fn picks_supported_present_modes() {
+    let all = [
+        PresentMode::Fifo,
+        PresentMode::Mailbox,
+        PresentMode::Immediate,
+    ];
+    let fifo_only = [PresentMode::Fifo];
+
+    let vsync = PresentSettings::default();
+    assert_eq!(vsync.choose_present_mode(&all), PresentMode::Fifo);
+
+    let no_vsync = PresentSettings {
+        vsync: false,
+        ..Default::default()
+    };
+    assert_eq!(no_vsync.choose_present_mode(&all), PresentMode::Immediate);
+    assert_eq!(
+        no_vsync.choose_present_mode(&[PresentMode::Fifo, PresentMode::Mailbox]),
+        PresentMode::Mailbox
+    );
+    assert_eq!(no_vsync.choose_present_mode(&fifo_only), PresentMode::Fifo);
+
+    let mailbox = PresentSettings {
+        present_mode: Some(PresentMode::Mailbox),
+        ..Default::default()
+    };
+    assert_eq!(mailbox.choose_present_mode(&all), PresentMode::Mailbox);
+    assert_eq!(mailbox.choose_present_mode(&fifo_only), PresentMode::Fifo);
+}
+
+#[test]
+fn picks_supported_alpha_modes() {
+    let supported = [
+        CompositeAlphaMode::Opaque,
+        CompositeAlphaMode::PreMultiplied,
+    ];
+
+    let settings = PresentSettings::default();
+    assert_eq!(
+        settings.choose_alpha_mode(&supported),
+        CompositeAlphaMode::Auto
+    );
+
+    let settings = PresentSettings {
+        alpha_mode: CompositeAlphaMode::PreMultiplied,
+        ..Default::default()
+    };
+    assert_eq!(
+        settings.choose_alpha_mode(&supported),
+        CompositeAlphaMode::PreMultiplied
+    );
+
+    let settings = PresentSettings {
+        alpha_mode: CompositeAlphaMode::PostMultiplied,
+        ..Default::default()
+    };
+    assert_eq!(
+        settings.choose_alpha_mode(&supported),
+        CompositeAlphaMode::Auto
+    );
+}
+
+#[test]
+fn headless_graphics_keep_present_settings() {
+    let mut graphics = Graphics::new_headless(8, 8);
+    assert_eq!(graphics.present_mode(), None);
+
+    let settings = PresentSettings {
+        vsync: false,
+        frame_latency: 1,
+        ..Default::default()
+    };
+    graphics.set_present_settings(settings);
+    assert_eq!(*graphics.present_settings(), settings);
+}
@@ -0,0 +1,113 @@
+use myoncore::{
+    AppHandler, Engine, EngineConfig, EngineContext,
+    gui::Gui,
+    input::{
+        AxisBinding, Binding, GamepadAxis, GamepadButton, InputEvent, InputMap, InputRecording,
+        VirtualGamepads,
+    },
+    renderer::Renderer,
+    utils::FrameTimer,
+};
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, keyboard::KeyCode, window::Window};
+
+/// Logs what every update saw.
+#[derive(Default)]
+struct Logger {
+    log: Vec<(bool, f32)>,
+}
+
+impl AppHandler for Logger {
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
+
+    fn on_update(&mut self, ctx: &mut EngineContext, _dt: f32) {
+        self.log
+            .push((ctx.input.action_pressed("jump"), ctx.input.axis("move_x")));
+    }
+
+    fn on_render(&mut self, _ctx: &mut EngineContext, _renderer: &mut Renderer, _alpha: f32) {}
+
+    fn on_gui(
+        &mut self,
+        _gui: &mut Gui,
+        _frametimer: &FrameTimer,
+        _window: Option<&Window>,
+        _event_loop: Option<&ActiveEventLoop>,
+    ) {
+    }
+}
+
+fn logging_engine() -> Engine<Logger> {
+    let config = EngineConfig::new()
+        .width(8)
+        .height(8)
+        .update_rate(30.0)
+        .gamepads(false);
+    let mut engine = Engine::new(config, Logger::default());
+
+    engine.context().input.map = InputMap::new()
+        .bind("jump", Binding::Key(KeyCode::Space))
+        .bind("jump", Binding::Gamepad(GamepadButton::South))
+        .bind_axis("move_x", AxisBinding::GamepadAxis(GamepadAxis::LeftStickX));
+
+    engine
+}
+
+#[test]
+fn replays_recorded_sessions_exactly() {
+    let pads = VirtualGamepads::new();
+    let mut engine = logging_engine();
+    engine.context().input.gamepads.set_backend(pads.clone());
+
+    let id = pads.connect("Virtual pad");
+    engine.context().start_recording();
+    engine.run_headless(2);
+
+    pads.press(id, GamepadButton::South);
+    pads.set_axis(id, GamepadAxis::LeftStickX, 1.0);
+    engine.run_headless(1);
+
+    pads.release(id, GamepadButton::South);
+    pads.disconnect(id);
+    engine.run_headless(2);
+
+    let recording = engine.context().stop_recording().unwrap();
+    assert_eq!(recording.frames(), 5);
+    assert_eq!(recording.update_rate, 30.0);
+
+    let path = std::env::temp_dir().join(format!("myon-replay-{}.ron", std::process::id()));
+    recording.save(&path).unwrap();
+    let loaded = InputRecording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, recording);
+
+    let log = &engine.app().log;
+    assert_eq!(log[2], (true, 1.0));
+    assert_eq!(log[4], (false, 0.0));
+
+    // Replays see exactly the same input, without the gamepad backend.
+    let mut replayed = logging_engine();
+    replayed.replay(&loaded);
+    assert_eq!(&replayed.app().log, log);
+}
+
+#[test]
+fn replays_hand_written_recordings() {
+    let mut recording = InputRecording::new(60.0);
+    recording.push_frame(1);
+    recording.push_event(InputEvent::Key {
+        code: KeyCode::Space,
+        pressed: true,
+    });
+    // A frame without updates keeps the press for the next one.
+    recording.push_frame(0);
+    recording.push_frame(2);
+
+    let mut engine = logging_engine();
+    engine.replay(&InputRecording::from_ron(&recording.to_ron().unwrap()).unwrap());
+
+    assert_eq!(
+        engine.app().log,
+        [(false, 0.0), (true, 0.0), (false, 0.0)],
+        "only the first update of the catch-up frame sees the press"
+    );
+}
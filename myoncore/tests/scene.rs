@@ -0,0 +1,173 @@
+use glam::Vec3;
+use myoncore::{
+    assets::{AssetRef, Text},
+    ecs::{Entity, World},
+    scene::{Children, Parent, SceneRegistry, Transform, set_parent},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct Sprite {
+    texture: AssetRef<Text>,
+    layer: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+enum Team {
+    Red,
+    Blue,
+}
+
+/// Not registered, so it isn't saved.
+struct Scratch;
+
+fn registry() -> SceneRegistry {
+    SceneRegistry::new()
+        .register::<Sprite>("Sprite")
+        .register::<Team>("Team")
+}
+
+fn build(world: &mut World) -> (Entity, Entity) {
+    let player = world.spawn((
+        Transform::from_xyz(1.0, 2.0, 3.0),
+        Sprite {
+            texture: AssetRef::new("sprites/player.txt"),
+            layer: 2,
+        },
+        Team::Blue,
+        Scratch,
+    ));
+    let weapon = world.spawn((Transform::from_xyz(0.5, 0.0, 0.0), Team::Red));
+    set_parent(world, weapon, player);
+
+    (player, weapon)
+}
+
+fn check(world: &World, entities: &[Entity]) {
+    let [player, weapon] = entities else {
+        panic!("Expected two entities, got {entities:?}");
+    };
+
+    assert_eq!(
+        *world.get::<Transform>(*player).unwrap(),
+        Transform::from_xyz(1.0, 2.0, 3.0)
+    );
+    let sprite = world.get::<Sprite>(*player).unwrap();
+    assert_eq!(sprite.texture.path().to_str(), Some("sprites/player.txt"));
+    assert_eq!(sprite.layer, 2);
+    assert!(!world.has::<Scratch>(*player));
+
+    assert_eq!(*world.get::<Team>(*weapon).unwrap(), Team::Red);
+    assert_eq!(world.get::<Parent>(*weapon).unwrap().get(), *player);
+    assert_eq!(
+        world.get::<Children>(*player).unwrap().as_slice(),
+        [*weapon]
+    );
+}
+
+#[test]
+fn round_trips_through_ron_and_json() {
+    let registry = registry();
+    let mut world = World::new();
+    build(&mut world);
+
+    let ron = registry.to_ron(&world).unwrap();
+    assert!(ron.contains("\"sprites/player.txt\""), "{ron}");
+
+    let mut loaded = World::new();
+    let entities = registry.from_ron(&mut loaded, &ron).unwrap();
+    check(&loaded, &entities);
+
+    let json = registry.to_json(&world).unwrap();
+    let mut loaded = World::new();
+    let entities = registry.from_json(&mut loaded, &json).unwrap();
+    check(&loaded, &entities);
+
+    let path = std::env::temp_dir().join("myon-scene-test.json");
+    registry.save(&world, &path).unwrap();
+    let mut loaded = World::new();
+    let entities = registry.load(&mut loaded, &path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    check(&loaded, &entities);
+
+    assert!(registry.save(&world, "scene.txt").is_err());
+}
+
+#[test]
+fn loads_hand_written_scenes() {
+    let registry = registry();
+    let mut world = World::new();
+
+    // Children may come before their parents, and missing Transform fields are defaults.
+    let entities = registry
+        .from_ron(
+            &mut world,
+            r#"(
+                entities: [
+                    (id: 7, parent: Some(3), components: {"Team": Red}),
+                    (id: 3, components: {"Transform": (translation: (0.0, 5.0, 0.0))}),
+                ],
+            )"#,
+        )
+        .unwrap();
+
+    assert_eq!(world.get::<Parent>(entities[0]).unwrap().get(), entities[1]);
+    let transform = *world.get::<Transform>(entities[1]).unwrap();
+    assert_eq!(transform.translation, Vec3::new(0.0, 5.0, 0.0));
+    assert_eq!(transform.scale, Vec3::ONE);
+
+    let error = registry
+        .from_ron(
+            &mut world,
+            r#"(entities: [(id: 0, components: {"Health": 10})])"#,
+        )
+        .unwrap_err();
+    assert!(
+        format!("{error:#}").contains("Unknown component Health"),
+        "{error:#}"
+    );
+}
+
+#[test]
+fn rejects_duplicate_entity_ids() {
+    let registry = registry();
+    let mut world = World::new();
+
+    let error = registry
+        .from_ron(
+            &mut world,
+            r#"(
+                entities: [
+                    (id: 1, components: {"Team": Red}),
+                    (id: 1, components: {"Team": Blue}),
+                    (id: 2, parent: Some(1), components: {}),
+                ],
+            )"#,
+        )
+        .unwrap_err();
+
+    assert!(
+        format!("{error:#}").contains("Duplicate entity id 1"),
+        "{error:#}"
+    );
+    assert!(world.is_empty());
+}
+
+#[test]
+fn rejects_unknown_parents() {
+    let registry = registry();
+    let mut world = World::new();
+
+    let error = registry
+        .from_ron(
+            &mut world,
+            r#"(entities: [(id: 1, parent: Some(4), components: {"Team": Red})])"#,
+        )
+        .unwrap_err();
+
+    assert!(
+        format!("{error:#}").contains("Scene entity 1 has unknown parent 4"),
+        "{error:#}"
+    );
+    assert!(world.is_empty());
+}
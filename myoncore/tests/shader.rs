@@ -0,0 +1,175 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use myoncore::{
+    AppHandler, Engine, EngineConfig, EngineContext,
+    graphics::Graphics,
+    gui::Gui,
+    renderer::Renderer,
+    shader::{Preprocessor, ShaderDefs, ShaderError, ShaderLibrary},
+    utils::FrameTimer,
+};
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
+
+fn shader_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("myon-shader-{name}-{}", std::process::id()));
+
+    for (path, source) in files {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, source).unwrap();
+    }
+
+    root
+}
+
+const COMMON: &str = "\
+#define SCALE 2.0
+fn scale(v: f32) -> f32 {
+    return v * SCALE;
+}
+";
+
+const MAIN: &str = "\
+#include \"lib/common.wgsl\"
+#include \"lib/common.wgsl\"
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+#ifdef RED
+    return vec4<f32>(scale(0.5), 0.0, 0.0, 1.0);
+#else
+    return vec4<f32>(0.0, 0.0, scale(0.5), 1.0);
+#endif
+}
+";
+
+#[test]
+fn resolves_includes_and_variants() {
+    let root = shader_dir(
+        "variants",
+        &[("lib/common.wgsl", COMMON), ("main.wgsl", MAIN)],
+    );
+    let preprocessor = Preprocessor::new(&root);
+
+    let blue = preprocessor
+        .process("main.wgsl", &ShaderDefs::new())
+        .unwrap();
+    let red = preprocessor
+        .process("main.wgsl", &ShaderDefs::new().define("RED"))
+        .unwrap();
+
+    assert_eq!(blue.source.matches("fn scale").count(), 1);
+    assert!(blue.source.contains("return v * 2.0;"));
+    assert!(blue.source.contains("vec4<f32>(0.0, 0.0, scale(0.5), 1.0)"));
+    assert!(red.source.contains("vec4<f32>(scale(0.5), 0.0, 0.0, 1.0)"));
+    assert_eq!(
+        blue.files(),
+        [PathBuf::from("main.wgsl"), PathBuf::from("lib/common.wgsl")]
+    );
+
+    // Line 2 of the output is `return v * SCALE;` from the include.
+    assert_eq!(blue.map_line(2), Some((Path::new("lib/common.wgsl"), 3)));
+
+    let unterminated = preprocessor
+        .process_source("inline.wgsl", "#ifdef RED\n", &ShaderDefs::new())
+        .unwrap_err();
+    assert_eq!(
+        (unterminated.line, unterminated.file),
+        (1, PathBuf::from("inline.wgsl"))
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn caches_modules_and_maps_errors_to_files() {
+    let broken = "fn broken() -> f32 {\n    return 1.0\n}\n";
+    let root = shader_dir(
+        "compile",
+        &[
+            ("lib/common.wgsl", COMMON),
+            ("lib/broken.wgsl", broken),
+            ("main.wgsl", MAIN),
+            ("uses_broken.wgsl", "#include \"lib/broken.wgsl\"\n"),
+        ],
+    );
+
+    let graphics = Graphics::new_headless(1, 1);
+    let mut shaders = ShaderLibrary::new(&root);
+    shaders.attach(&graphics.device);
+
+    shaders.get("main.wgsl", &ShaderDefs::new()).unwrap();
+    shaders.get("./lib/../main.wgsl", &ShaderDefs::new()).unwrap();
+    shaders
+        .get("main.wgsl", &ShaderDefs::new().define("RED"))
+        .unwrap();
+    assert_eq!(shaders.len(), 2);
+
+    shaders.invalidate("lib/common.wgsl");
+    assert!(shaders.is_empty());
+
+    let error = shaders
+        .get("uses_broken.wgsl", &ShaderDefs::new())
+        .unwrap_err();
+    let error = error.downcast_ref::<ShaderError>().unwrap();
+
+    assert_eq!(error.file, PathBuf::from("lib/broken.wgsl"));
+    assert!((2..=3).contains(&error.line), "{error}");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+struct Idle;
+
+impl AppHandler for Idle {
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
+
+    fn on_update(&mut self, _ctx: &mut EngineContext, _dt: f32) {}
+
+    fn on_render(&mut self, _ctx: &mut EngineContext, _renderer: &mut Renderer, _alpha: f32) {}
+
+    fn on_gui(
+        &mut self,
+        _gui: &mut Gui,
+        _frametimer: &FrameTimer,
+        _window: Option<&Window>,
+        _event_loop: Option<&ActiveEventLoop>,
+    ) {
+    }
+}
+
+#[test]
+fn hot_reload_invalidates_shaders_through_includes() {
+    let root = shader_dir(
+        "reload",
+        &[("lib/common.wgsl", COMMON), ("main.wgsl", MAIN)],
+    );
+
+    let config = EngineConfig::new()
+        .width(8)
+        .height(8)
+        .asset_root(&root)
+        .hot_reload(true)
+        .gamepads(false);
+    let mut engine = Engine::new(config, Idle);
+    engine.run_headless(1);
+
+    let shaders = &mut engine.context().shaders;
+    shaders.get("main.wgsl", &ShaderDefs::new()).unwrap();
+    assert_eq!(shaders.len(), 1);
+
+    std::fs::write(root.join("lib/common.wgsl"), COMMON.replace("2.0", "3.0")).unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !engine.context().shaders.is_empty() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+        engine.run_headless(1);
+    }
+
+    assert!(engine.context().shaders.is_empty());
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,145 @@
+use std::time::{Duration, Instant};
+
+use myoncore::{
+    AppHandler, Engine, EngineConfig, EngineContext,
+    gui::Gui,
+    renderer::Renderer,
+    utils::{FixedTimestep, FrameLimiter, FrameTimer, RedrawSchedule},
+};
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
+
+#[test]
+fn accumulates_and_clamps_steps() {
+    let mut timestep = FixedTimestep::new(100.0, 4);
+
+    assert_eq!(timestep.advance(Duration::from_millis(5)), 0);
+    assert!((timestep.alpha() - 0.5).abs() < 1e-4);
+
+    assert_eq!(timestep.advance(Duration::from_millis(25)), 3);
+    assert!(timestep.alpha() < 1e-4);
+
+    // A one second hitch only catches up `max_steps` steps.
+    assert_eq!(timestep.advance(Duration::from_secs(1)), 4);
+    assert_eq!(timestep.advance(Duration::ZERO), 0);
+}
+
+#[derive(Default)]
+struct Counter {
+    updates: u32,
+    dt: f32,
+    alphas: Vec<f32>,
+}
+
+impl AppHandler for Counter {
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
+
+    fn on_update(&mut self, _ctx: &mut EngineContext, dt: f32) {
+        self.updates += 1;
+        self.dt = dt;
+    }
+
+    fn on_render(&mut self, _ctx: &mut EngineContext, _renderer: &mut Renderer, alpha: f32) {
+        self.alphas.push(alpha);
+    }
+
+    fn on_gui(
+        &mut self,
+        _gui: &mut Gui,
+        _frametimer: &FrameTimer,
+        _window: Option<&Window>,
+        _event_loop: Option<&ActiveEventLoop>,
+    ) {
+    }
+}
+
+#[test]
+#[should_panic(expected = "Update rate must be positive")]
+fn rejects_zero_update_rate() {
+    FixedTimestep::new(0.0, 4);
+}
+
+#[test]
+fn handles_very_high_update_rates() {
+    let mut timestep = FixedTimestep::new(1e12, 4);
+    assert!(timestep.step() > Duration::ZERO);
+    assert_eq!(timestep.advance(Duration::from_millis(1)), 4);
+}
+
+#[test]
+fn limiter_holds_frames_to_the_cap() {
+    let mut limiter = FrameLimiter::new(200.0);
+    assert_eq!(limiter.period(), Duration::from_millis(5));
+
+    let start = Instant::now();
+    for _ in 0..11 {
+        limiter.wait();
+    }
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(49), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(500), "{elapsed:?}");
+
+    // A slow frame doesn't make the next ones rush to catch up.
+    std::thread::sleep(Duration::from_millis(20));
+    limiter.wait();
+    let start = Instant::now();
+    limiter.wait();
+    assert!(start.elapsed() >= Duration::from_millis(4));
+}
+
+#[test]
+#[should_panic(expected = "Frame rate cap must be positive")]
+fn limiter_rejects_zero_fps() {
+    FrameLimiter::new(0.0);
+}
+
+#[test]
+fn schedules_reactive_redraws() {
+    let now = Instant::now();
+    let ms = Duration::from_millis;
+    let mut schedule = RedrawSchedule::new();
+
+    // Nothing to do: sleep until an event, or until the next poll.
+    schedule.after_frame(now, Duration::MAX);
+    assert!(!schedule.take_due(now));
+    assert_eq!(schedule.wake_at(now, None), None);
+    assert_eq!(schedule.wake_at(now, Some(ms(16))), Some(now + ms(16)));
+
+    // egui wants an animation frame in 100ms, polling still wakes up in between.
+    schedule.after_frame(now, ms(100));
+    assert_eq!(schedule.wake_at(now, None), Some(now + ms(100)));
+    assert_eq!(schedule.wake_at(now, Some(ms(16))), Some(now + ms(16)));
+    assert!(!schedule.take_due(now + ms(50)));
+
+    // A gamepad or file change pulls it forward, a later request doesn't push it back.
+    schedule.redraw_after(now + ms(50), Duration::ZERO);
+    schedule.redraw_after(now + ms(50), ms(500));
+    assert!(schedule.take_due(now + ms(50)));
+    assert!(!schedule.take_due(now + ms(60)));
+    assert_eq!(schedule.wake_at(now, None), None);
+
+    schedule.after_frame(now, ms(10));
+    assert!(schedule.take_due(now + ms(10)));
+}
+
+#[test]
+fn headless_runs_one_update_per_frame() {
+    let config = EngineConfig::new().width(8).height(8).update_rate(50.0);
+    let mut engine = Engine::new(config, Counter::default());
+
+    engine.run_headless(3);
+
+    assert_eq!(engine.app().updates, 3);
+    assert!((engine.app().dt - 0.02).abs() < 1e-6);
+    assert_eq!(engine.app().alphas, vec![0.0; 3]);
+}
+
+#[test]
+fn default_config_ignores_invalid_update_rates() {
+    let config = EngineConfig::default().width(8).height(8).update_rate(0.0);
+    let mut engine = Engine::new(config, Counter::default());
+
+    engine.run_headless(1);
+
+    assert_eq!(engine.app().updates, 1);
+    assert!((engine.app().dt - 1.0 / 60.0).abs() < 1e-6);
+}
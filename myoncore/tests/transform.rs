@@ -0,0 +1,183 @@
+use glam::{Quat, Vec3};
+use myoncore::{
+    AppHandler, Engine, EngineConfig, EngineContext,
+    ecs::{Entity, World},
+    gui::Gui,
+    renderer::Renderer,
+    scene::{
+        Children, GlobalTransform, Parent, Transform, despawn_recursive, propagate_transforms,
+        remove_parent, set_parent,
+    },
+    utils::FrameTimer,
+};
+use winit::{event::WindowEvent, event_loop::ActiveEventLoop, window::Window};
+
+fn translation(world: &World, entity: Entity) -> Vec3 {
+    world.get::<GlobalTransform>(entity).unwrap().translation()
+}
+
+#[test]
+fn propagates_through_the_hierarchy() {
+    let mut world = World::new();
+
+    let root = world.spawn((Transform::from_xyz(10.0, 0.0, 0.0)
+        .rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),));
+    let child = world.spawn((Transform::from_xyz(1.0, 0.0, 0.0),));
+    let grandchild = world.spawn((Transform::from_xyz(0.0, 0.0, 2.0),));
+
+    assert!(set_parent(&mut world, child, root));
+    assert!(set_parent(&mut world, grandchild, child));
+    propagate_transforms(&mut world);
+
+    assert!(translation(&world, child).abs_diff_eq(Vec3::new(10.0, 1.0, 0.0), 1e-5));
+    assert!(translation(&world, grandchild).abs_diff_eq(Vec3::new(10.0, 1.0, 2.0), 1e-5));
+
+    // Changing a parent moves its whole subtree.
+    world.get_mut::<Transform>(root).unwrap().rotation = Quat::IDENTITY;
+    propagate_transforms(&mut world);
+    assert!(translation(&world, grandchild).abs_diff_eq(Vec3::new(11.0, 0.0, 2.0), 1e-5));
+
+    world
+        .get_mut::<Transform>(grandchild)
+        .unwrap()
+        .translation
+        .z = 3.0;
+    propagate_transforms(&mut world);
+    assert!(translation(&world, grandchild).abs_diff_eq(Vec3::new(11.0, 0.0, 3.0), 1e-5));
+    assert!(translation(&world, child).abs_diff_eq(Vec3::new(11.0, 0.0, 0.0), 1e-5));
+}
+
+#[test]
+fn passes_through_parents_without_transforms() {
+    let mut world = World::new();
+
+    let root = world.spawn((Transform::from_xyz(5.0, 0.0, 0.0),));
+    let group = world.spawn_empty();
+    let child = world.spawn((Transform::from_xyz(0.0, 1.0, 0.0),));
+
+    assert!(set_parent(&mut world, group, root));
+    assert!(set_parent(&mut world, child, group));
+    propagate_transforms(&mut world);
+
+    assert!(!world.has::<GlobalTransform>(group));
+    assert!(translation(&world, child).abs_diff_eq(Vec3::new(5.0, 1.0, 0.0), 1e-5));
+
+    world.get_mut::<Transform>(root).unwrap().translation.x = 7.0;
+    propagate_transforms(&mut world);
+    assert!(translation(&world, child).abs_diff_eq(Vec3::new(7.0, 1.0, 0.0), 1e-5));
+
+    // A parentless entity without a transform acts as the identity too.
+    remove_parent(&mut world, group);
+    propagate_transforms(&mut world);
+    assert!(translation(&world, child).abs_diff_eq(Vec3::new(0.0, 1.0, 0.0), 1e-5));
+}
+
+#[test]
+fn reparents_without_cycles() {
+    let mut world = World::new();
+
+    let a = world.spawn((Transform::from_xyz(1.0, 0.0, 0.0),));
+    let b = world.spawn((Transform::from_xyz(0.0, 1.0, 0.0),));
+    let c = world.spawn((Transform::IDENTITY,));
+
+    assert!(set_parent(&mut world, b, a));
+    assert!(set_parent(&mut world, c, b));
+    assert!(!set_parent(&mut world, a, c), "a is an ancestor of c");
+    assert!(!set_parent(&mut world, a, a));
+    propagate_transforms(&mut world);
+    assert_eq!(translation(&world, c), Vec3::new(1.0, 1.0, 0.0));
+
+    assert!(set_parent(&mut world, c, a));
+    assert_eq!(world.get::<Children>(a).unwrap().as_slice(), [b, c]);
+    assert!(world.get::<Children>(b).is_none());
+    assert_eq!(world.get::<Parent>(c).unwrap().get(), a);
+    propagate_transforms(&mut world);
+    assert_eq!(translation(&world, c), Vec3::new(1.0, 0.0, 0.0));
+
+    assert!(remove_parent(&mut world, c));
+    assert!(!remove_parent(&mut world, c));
+    propagate_transforms(&mut world);
+    assert_eq!(translation(&world, c), Vec3::ZERO);
+}
+
+#[test]
+fn despawns_subtrees() {
+    let mut world = World::new();
+
+    let root = world.spawn((Transform::from_xyz(1.0, 0.0, 0.0),));
+    let middle = world.spawn((Transform::from_xyz(1.0, 0.0, 0.0),));
+    let leaf = world.spawn((Transform::IDENTITY,));
+    let orphan = world.spawn((Transform::IDENTITY,));
+
+    set_parent(&mut world, middle, root);
+    set_parent(&mut world, leaf, middle);
+    set_parent(&mut world, orphan, middle);
+    propagate_transforms(&mut world);
+    assert_eq!(translation(&world, orphan), Vec3::new(2.0, 0.0, 0.0));
+
+    remove_parent(&mut world, orphan);
+    set_parent(&mut world, orphan, root);
+    despawn_recursive(&mut world, middle);
+
+    assert!(!world.is_alive(middle) && !world.is_alive(leaf));
+    assert_eq!(world.get::<Children>(root).unwrap().as_slice(), [orphan]);
+
+    // Children of plainly despawned entities become roots.
+    world.despawn(root);
+    propagate_transforms(&mut world);
+    assert_eq!(translation(&world, orphan), Vec3::ZERO);
+}
+
+#[derive(Default)]
+struct Follower {
+    child: Option<Entity>,
+    rendered: Vec<Vec3>,
+}
+
+impl AppHandler for Follower {
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
+
+    fn on_update(&mut self, ctx: &mut EngineContext, _dt: f32) {
+        let child = self.child.unwrap();
+        let parent = ctx.world.get::<Parent>(child).unwrap().get();
+
+        ctx.world
+            .get_mut::<Transform>(parent)
+            .unwrap()
+            .translation
+            .x += 1.0;
+    }
+
+    fn on_render(&mut self, ctx: &mut EngineContext, _renderer: &mut Renderer, _alpha: f32) {
+        let global = ctx.world.get::<GlobalTransform>(self.child.unwrap());
+        self.rendered.push(global.unwrap().translation());
+    }
+
+    fn on_gui(
+        &mut self,
+        _gui: &mut Gui,
+        _frametimer: &FrameTimer,
+        _window: Option<&Window>,
+        _event_loop: Option<&ActiveEventLoop>,
+    ) {
+    }
+}
+
+#[test]
+fn propagates_before_on_render() {
+    let config = EngineConfig::new().width(8).height(8);
+    let mut engine = Engine::new(config, Follower::default());
+
+    let world = &mut engine.context().world;
+    let parent = world.spawn((Transform::IDENTITY,));
+    let child = world.spawn((Transform::from_xyz(0.0, 1.0, 0.0),));
+    set_parent(world, child, parent);
+    engine.app_mut().child = Some(child);
+
+    engine.run_headless(2);
+
+    assert_eq!(
+        engine.app().rendered,
+        [Vec3::new(1.0, 1.0, 0.0), Vec3::new(2.0, 1.0, 0.0)]
+    );
+}
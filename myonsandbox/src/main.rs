@@ -1,5 +1,10 @@
-use egui::Context;
-use myoncore::{renderer::Renderer, utils::FrameTimer, AppHandler, Engine, EngineConfig};
+use glam::{Vec2, Vec4};
+use myoncore::{
+    gui::Gui,
+    renderer::{Renderer, Sprite, SpriteBatch},
+    utils::FrameTimer,
+    AppHandler, Engine, EngineConfig, EngineContext,
+};
 use winit::{
     event::WindowEvent,
     event_loop::{ActiveEventLoop, EventLoop},
@@ -8,14 +13,20 @@ use winit::{
 
 struct Sandbox {
     show_fps: bool,
+    sprite_batch: Option<SpriteBatch>,
+    rotation: f32,
+    previous_rotation: f32,
 }
 
 impl AppHandler for Sandbox {
-    fn on_event(&mut self, event_loop: &ActiveEventLoop, event: &WindowEvent) {}
+    fn on_event(&mut self, _event_loop: &ActiveEventLoop, _event: &WindowEvent) {}
 
-    fn on_update(&mut self) {}
+    fn on_update(&mut self, _ctx: &mut EngineContext, dt: f32) {
+        self.previous_rotation = self.rotation;
+        self.rotation += dt;
+    }
 
-    fn on_render(&mut self, renderer: &mut Renderer) {
+    fn on_render(&mut self, _ctx: &mut EngineContext, renderer: &mut Renderer, alpha: f32) {
         let texture_view = renderer.texture_view.as_ref().expect("TextureView missing");
         let encoder = renderer
             .command_encoder
@@ -43,21 +54,39 @@ impl AppHandler for Sandbox {
         };
 
         {
-            let render_pass = encoder.begin_render_pass(&render_pass_descriptor);
+            let _render_pass = encoder.begin_render_pass(&render_pass_descriptor);
         }
+
+        let sprite_batch = self
+            .sprite_batch
+            .get_or_insert_with(|| SpriteBatch::new(renderer));
+
+        let rotation = self.previous_rotation + (self.rotation - self.previous_rotation) * alpha;
+
+        sprite_batch.draw_quad(
+            Sprite::new(Vec2::new(400.0, 300.0), Vec2::new(128.0, 128.0))
+                .rotation(rotation)
+                .color(Vec4::new(0.35, 0.55, 0.95, 1.0)),
+        );
+
+        sprite_batch.flush(renderer);
     }
 
     fn on_gui(
         &mut self,
-        ctx: &mut Context,
+        gui: &mut Gui,
         frametimer: &FrameTimer,
-        window: &Window,
-        event_loop: &ActiveEventLoop,
+        _window: Option<&Window>,
+        event_loop: Option<&ActiveEventLoop>,
     ) {
+        let ctx = &gui.ctx;
+
         egui::TopBottomPanel::top("debug_bar").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.menu_button("File", |ui| {
-                    if ui.button("Exit").clicked() {
+                    if ui.button("Exit").clicked()
+                        && let Some(event_loop) = event_loop
+                    {
                         event_loop.exit();
                     }
                 });
@@ -92,7 +121,15 @@ fn main() -> anyhow::Result<()> {
         .height(600)
         .resizable(true);
 
-    let mut engine = Engine::new(engineconfig, Sandbox { show_fps: false });
+    let mut engine = Engine::new(
+        engineconfig,
+        Sandbox {
+            show_fps: false,
+            sprite_batch: None,
+            rotation: std::f32::consts::FRAC_PI_4,
+            previous_rotation: std::f32::consts::FRAC_PI_4,
+        },
+    );
     event_loop.run_app(&mut engine)?;
 
     Ok(())